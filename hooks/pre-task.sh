@@ -6,6 +6,9 @@
 #
 # Required environment variables:
 # - CLAUDE_USER_PROMPT: The user's initial prompt (if available)
+#
+# Optional environment variables:
+# - TEMPERA_CONTEXT_BUDGET: Approximate token budget for the brief (default: 1500)
 
 set -e
 
@@ -27,8 +30,8 @@ echo "---"
 echo "Checking episodic memory for relevant past experiences..."
 echo ""
 
-tempera retrieve "$CLAUDE_USER_PROMPT" --project "$PROJECT_NAME" --limit 3 --format text 2>/dev/null || \
-tempera retrieve "$CLAUDE_USER_PROMPT" --limit 3 --format text 2>/dev/null || \
+tempera context-pack --query "$CLAUDE_USER_PROMPT" --project "$PROJECT_NAME" \
+    --budget "${TEMPERA_CONTEXT_BUDGET:-1500}" 2>/dev/null || \
 echo "No relevant past episodes found."
 
 echo "---"
@@ -9,7 +9,10 @@ use regex::Regex;
 use std::path::PathBuf;
 
 use crate::config::Config;
-use crate::episode::{Context as EpisodeContext, Episode, ErrorRecord, OutcomeStatus, TaskType};
+use crate::episode::{
+    Context as EpisodeContext, Episode, ErrorRecord, OutcomeStatus, TaskType, TestResults,
+};
+use crate::implicit_feedback;
 use crate::llm::{AnthropicClient, SessionAnalysis};
 use crate::store::EpisodeStore;
 
@@ -19,7 +22,7 @@ pub async fn run(
     project: Option<PathBuf>,
     extract_intent: bool,
     capture_diff: bool,
-    _config: &Config,
+    config: &Config,
 ) -> Result<()> {
     let project_dir = project.unwrap_or_else(|| std::env::current_dir().unwrap());
     let project_name = extract_project_name(&project_dir);
@@ -27,12 +30,14 @@ pub async fn run(
     println!("📝 Capturing episode for project: {}", project_name);
 
     // Parse session transcript if provided
+    let mut test_runs = Vec::new();
     let (raw_prompt, context, outcome_status) = if let Some(session_path) = &session {
         let transcript = std::fs::read_to_string(session_path)
             .with_context(|| format!("Failed to read session file: {}", session_path.display()))?;
         let prompt = extract_first_prompt(&transcript);
         let ctx = extract_context_from_transcript(&transcript, &project_dir);
         let status = determine_outcome(&transcript);
+        test_runs = extract_test_runs(&transcript);
         (prompt, ctx, status)
     } else {
         // Interactive mode: ask user for information
@@ -52,6 +57,11 @@ pub async fn run(
     episode.timestamp_end = Utc::now();
     episode.context = context;
     episode.outcome.status = outcome_status;
+    // First and last test run of the session, when there was more than one
+    episode.outcome.tests_after = test_runs.last().cloned();
+    if test_runs.len() > 1 {
+        episode.outcome.tests_before = test_runs.first().cloned();
+    }
 
     // Extract intent with classification
     apply_intent_extraction(&mut episode, &raw_prompt, session.as_ref(), extract_intent).await;
@@ -81,6 +91,12 @@ pub async fn run(
     println!("   Type: {}", episode.intent.task_type);
     println!("   Tags: {}", episode.intent.domain.join(", "));
     println!("   Outcome: {}", episode.outcome.status);
+    if let Some(after) = &episode.outcome.tests_after {
+        match &episode.outcome.tests_before {
+            Some(before) => println!("   Tests: {} → {}", before, after),
+            None => println!("   Tests: {}", after),
+        }
+    }
     if !episode.context.files_modified.is_empty() {
         println!(
             "   Files modified: {}",
@@ -88,6 +104,21 @@ pub async fn run(
         );
     }
 
+    // Credit episodes retrieved earlier in the session that this capture used
+    match implicit_feedback::record_capture(&store, &episode, config) {
+        Ok(inferred) => {
+            for (id, implicit) in inferred {
+                let signals: Vec<String> = implicit.signals.iter().map(|s| s.to_string()).collect();
+                println!(
+                    "   Implicit feedback: {} ({})",
+                    &id[..8.min(id.len())],
+                    signals.join(", ")
+                );
+            }
+        }
+        Err(e) => eprintln!("⚠️  Implicit feedback skipped: {}", e),
+    }
+
     Ok(())
 }
 
@@ -163,7 +194,7 @@ fn extract_file_reads(transcript: &str) -> Vec<String> {
 }
 
 /// Get modified files from git
-fn get_modified_files(project_dir: &PathBuf) -> Result<Vec<String>> {
+pub(crate) fn get_modified_files(project_dir: &PathBuf) -> Result<Vec<String>> {
     let mut files = Vec::new();
 
     if let Ok(repo) = Repository::open(project_dir) {
@@ -189,6 +220,40 @@ fn get_modified_files(project_dir: &PathBuf) -> Result<Vec<String>> {
     Ok(files)
 }
 
+/// Get files changed in the last `count` commits on HEAD
+pub(crate) fn get_recent_commit_files(project_dir: &PathBuf, count: usize) -> Result<Vec<String>> {
+    let mut files = Vec::new();
+
+    if let Ok(repo) = Repository::open(project_dir) {
+        let mut commit = match repo.head().and_then(|head| head.peel_to_commit()) {
+            Ok(commit) => commit,
+            Err(_) => return Ok(files), // No commits yet
+        };
+
+        for _ in 0..count {
+            let tree = commit.tree()?;
+            let parent = commit.parent(0).ok();
+            let parent_tree = parent.as_ref().map(|p| p.tree()).transpose()?;
+            let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
+
+            for delta in diff.deltas() {
+                if let Some(path) = delta.new_file().path() {
+                    files.push(path.to_string_lossy().to_string());
+                }
+            }
+
+            match parent {
+                Some(p) => commit = p,
+                None => break,
+            }
+        }
+    }
+
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
 /// Extract tool calls from transcript
 fn extract_tool_calls(transcript: &str) -> Vec<String> {
     let mut tools = Vec::new();
@@ -251,6 +316,62 @@ fn extract_errors(transcript: &str) -> Vec<ErrorRecord> {
     errors
 }
 
+/// Test runs in a transcript, in order. A run starts at a test command
+/// (`cargo test`, `pytest`, `npm test`, ...) and sums every summary line up
+/// to the next one, so a cargo run with several test binaries counts once.
+fn extract_test_runs(transcript: &str) -> Vec<TestResults> {
+    let Ok(command) = Regex::new(
+        r"(?:cargo (?:nextest run|test)|pytest|(?:npm|yarn|pnpm) (?:run )?test|npx (?:jest|vitest)|go test)\b",
+    ) else {
+        return vec![];
+    };
+
+    let mut runs = Vec::new();
+    let mut current: Option<TestResults> = None;
+    for line in transcript.lines() {
+        if command.is_match(line) {
+            runs.extend(current.take());
+        } else if let Some(summary) = parse_test_summary(line) {
+            current
+                .get_or_insert_with(TestResults::default)
+                .add(&summary);
+        }
+    }
+    runs.extend(current);
+    runs
+}
+
+/// Parse a test summary line from cargo test, pytest or jest
+fn parse_test_summary(line: &str) -> Option<TestResults> {
+    let line = line.trim();
+    let is_summary = line.starts_with("test result:")
+        || line.starts_with("Tests:")
+        || (line.starts_with('=') && line.ends_with('='));
+    if !is_summary {
+        return None;
+    }
+
+    let count = |pattern: &str| -> Option<u32> {
+        let re = Regex::new(pattern).ok()?;
+        Some(
+            re.captures_iter(line)
+                .filter_map(|c| c.get(1)?.as_str().parse::<u32>().ok())
+                .sum(),
+        )
+    };
+    let passed = count(r"(\d+) passed")?;
+    let failed = count(r"(\d+) (?:failed|errors?)\b")?;
+    let skipped = count(r"(\d+) (?:skipped|ignored)")?;
+    if passed + failed + skipped == 0 {
+        return None;
+    }
+    Some(TestResults {
+        passed,
+        failed,
+        skipped,
+    })
+}
+
 /// Determine outcome from transcript
 fn determine_outcome(transcript: &str) -> OutcomeStatus {
     let lower = transcript.to_lowercase();
@@ -531,6 +652,38 @@ mod tests {
         assert_eq!(extract_first_prompt(transcript), "Fix the login bug");
     }
 
+    #[test]
+    fn test_extract_test_runs() {
+        let transcript = "\
+$ cargo test
+test result: FAILED. 10 passed; 2 failed; 1 ignored; 0 measured
+test result: ok. 5 passed; 0 failed; 0 ignored; 0 measured
+Fixing the off-by-one in parser.rs
+$ cargo test
+test result: ok. 12 passed; 0 failed; 1 ignored; 0 measured
+test result: ok. 5 passed; 0 failed; 0 ignored; 0 measured
+$ pytest tests/
+===== 1 failed, 20 passed, 2 skipped in 0.52s =====
+Tests:       3 failed, 40 passed, 43 total";
+
+        let runs = extract_test_runs(transcript);
+        assert_eq!(runs.len(), 3);
+        assert_eq!(
+            runs[0],
+            TestResults {
+                passed: 15,
+                failed: 2,
+                skipped: 1
+            }
+        );
+        assert!(runs[1].is_green());
+        // Without a command in between, summaries add up into one run
+        assert_eq!(runs[2].failed, 4);
+        assert_eq!(runs[2].passed, 60);
+
+        assert!(extract_test_runs("no tests here\n== heading ==").is_empty());
+    }
+
     #[test]
     fn test_determine_outcome() {
         assert_eq!(determine_outcome("All tests pass!"), OutcomeStatus::Success);
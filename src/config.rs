@@ -7,6 +7,8 @@ use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
+use crate::episode::EpisodeRelation;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     #[serde(default)]
@@ -19,6 +21,8 @@ pub struct Config {
     pub bellman: BellmanConfig,
     #[serde(default)]
     pub storage: StorageConfig,
+    #[serde(default)]
+    pub maintenance: MaintenanceConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -77,6 +81,57 @@ pub struct RetrievalConfig {
     /// Half-life for recency decay in days
     #[serde(default = "default_recency_halflife_days")]
     pub recency_halflife_days: f32,
+    /// Rescore top vector hits with a cross-encoder (off by default, opt-in)
+    #[serde(default)]
+    pub rerank: bool,
+    /// Number of top vector hits passed to the reranker
+    #[serde(default = "default_rerank_top_n")]
+    pub rerank_top_n: usize,
+    /// Cross-encoder model used for reranking
+    #[serde(default = "default_rerank_model")]
+    pub rerank_model: String,
+    /// Weight for overlap with files in the current git working tree (0.0 = off, opt-in)
+    #[serde(default = "default_working_tree_weight")]
+    pub working_tree_weight: f32,
+    /// Weight for the personalized PageRank score over similarity, session and
+    /// explicit links, seeded from the top hits (0.0 = off, opt-in)
+    #[serde(default = "default_graph_weight")]
+    pub graph_weight: f32,
+    /// Number of top hits that seed the graph walk
+    #[serde(default = "default_graph_seeds")]
+    pub graph_seeds: usize,
+    /// Max failure episodes returned in the separate "mistakes to avoid" section (0 = off)
+    #[serde(default = "default_mistakes_limit")]
+    pub mistakes_limit: usize,
+    /// Minimum similarity for a failure episode to be shown as a mistake to avoid
+    #[serde(default = "default_mistakes_min_similarity")]
+    pub mistakes_min_similarity: f32,
+    /// Repeated impressions of an episode within one session and this many
+    /// minutes count once toward its retrieval count
+    #[serde(default = "default_impression_window_minutes")]
+    pub impression_window_minutes: u32,
+    /// How many trials the global utility counts as when blended with an
+    /// episode's utility in the retrieving project (higher = trust local
+    /// feedback later)
+    #[serde(default = "default_project_utility_prior")]
+    pub project_utility_prior: f32,
+    /// Credit for feedback inferred from a capture that follows a retrieval,
+    /// relative to explicit feedback (0.0 = off)
+    #[serde(default = "default_implicit_feedback_weight")]
+    pub implicit_feedback_weight: f32,
+    /// Floor for the learned examination propensity of low ranks; bounds how
+    /// much a skipped low-ranked impression is discounted (1.0 = no
+    /// position-bias correction)
+    #[serde(default = "default_min_propensity")]
+    pub min_propensity: f32,
+    /// Fraction of retrievals that explore under-tested episodes (0.0 = off, opt-in)
+    #[serde(default)]
+    pub exploration_rate: f32,
+    /// How an exploring retrieval explores: "thompson" samples utility from
+    /// each episode's Beta posterior, "reserve" keeps one slot for a relevant
+    /// under-tested episode
+    #[serde(default = "default_exploration_mode")]
+    pub exploration_mode: String,
 }
 
 impl Default for RetrievalConfig {
@@ -89,6 +144,20 @@ impl Default for RetrievalConfig {
             mmr_lambda: default_mmr_lambda(),
             recency_weight: default_recency_weight(),
             recency_halflife_days: default_recency_halflife_days(),
+            rerank: false,
+            rerank_top_n: default_rerank_top_n(),
+            rerank_model: default_rerank_model(),
+            working_tree_weight: default_working_tree_weight(),
+            graph_weight: default_graph_weight(),
+            graph_seeds: default_graph_seeds(),
+            mistakes_limit: default_mistakes_limit(),
+            mistakes_min_similarity: default_mistakes_min_similarity(),
+            impression_window_minutes: default_impression_window_minutes(),
+            project_utility_prior: default_project_utility_prior(),
+            implicit_feedback_weight: default_implicit_feedback_weight(),
+            min_propensity: default_min_propensity(),
+            exploration_rate: 0.0,
+            exploration_mode: default_exploration_mode(),
         }
     }
 }
@@ -113,6 +182,20 @@ pub struct BellmanConfig {
     /// Temporal credit lookback window in hours
     #[serde(default = "default_temporal_credit_window_hours")]
     pub temporal_credit_window_hours: i64,
+    /// Discount factor for penalty propagation from unhelpful episodes
+    #[serde(default = "default_penalty_gamma")]
+    pub penalty_gamma: f32,
+    /// Share of an episode's rated retrievals marked unhelpful above which it
+    /// seeds penalty propagation
+    #[serde(default = "default_penalty_threshold")]
+    pub penalty_threshold: f32,
+    /// Weight of the measured reward from test results (tests fixed, red to
+    /// green) in Bellman seeding and temporal credit (0.0 = off)
+    #[serde(default = "default_test_reward_weight")]
+    pub test_reward_weight: f32,
+    /// Propagation weight per link type
+    #[serde(default)]
+    pub relation_weights: RelationWeights,
 }
 
 impl Default for BellmanConfig {
@@ -125,6 +208,50 @@ impl Default for BellmanConfig {
             propagation_threshold: default_propagation_threshold(),
             max_propagation_depth: default_max_propagation_depth(),
             temporal_credit_window_hours: default_temporal_credit_window_hours(),
+            penalty_gamma: default_penalty_gamma(),
+            penalty_threshold: default_penalty_threshold(),
+            test_reward_weight: default_test_reward_weight(),
+            relation_weights: RelationWeights::default(),
+        }
+    }
+}
+
+/// How much of a helpful episode's value reaches a linked episode, per link
+/// type. Negative weights are competitive: the linked episode loses utility.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelationWeights {
+    /// Episodes from the same session
+    #[serde(default = "default_session_weight")]
+    pub session: f32,
+    #[serde(default = "default_continuation_weight")]
+    pub continuation: f32,
+    #[serde(default = "default_prerequisite_weight")]
+    pub prerequisite: f32,
+    #[serde(default = "default_alternative_weight")]
+    pub alternative: f32,
+    #[serde(default = "default_related_weight")]
+    pub related: f32,
+}
+
+impl Default for RelationWeights {
+    fn default() -> Self {
+        Self {
+            session: default_session_weight(),
+            continuation: default_continuation_weight(),
+            prerequisite: default_prerequisite_weight(),
+            alternative: default_alternative_weight(),
+            related: default_related_weight(),
+        }
+    }
+}
+
+impl RelationWeights {
+    pub fn weight(&self, relation: EpisodeRelation) -> f32 {
+        match relation {
+            EpisodeRelation::Continuation => self.continuation,
+            EpisodeRelation::Prerequisite => self.prerequisite,
+            EpisodeRelation::Alternative => self.alternative,
+            EpisodeRelation::Related => self.related,
         }
     }
 }
@@ -165,6 +292,33 @@ impl Default for StorageConfig {
     }
 }
 
+/// Background maintenance. Decay, Bellman propagation and temporal credit
+/// run every `bellman.propagate_interval`; intervals are "hourly", "daily",
+/// "weekly", a count of hours or days ("6h", "3d"), or "never".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceConfig {
+    /// Run overdue jobs in a background process after CLI commands and
+    /// during MCP sessions
+    #[serde(default = "default_true")]
+    pub auto: bool,
+    /// How often to prune old, low-utility episodes (deletes them; opt-in)
+    #[serde(default = "default_prune_interval")]
+    pub prune_interval: String,
+    /// How often to index episodes missing from the vector index
+    #[serde(default = "default_reindex_interval")]
+    pub reindex_interval: String,
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            auto: true,
+            prune_interval: default_prune_interval(),
+            reindex_interval: default_reindex_interval(),
+        }
+    }
+}
+
 // Default value functions
 fn default_true() -> bool {
     true
@@ -230,6 +384,62 @@ fn default_recency_halflife_days() -> f32 {
     30.0
 }
 
+fn default_rerank_top_n() -> usize {
+    20
+}
+
+fn default_rerank_model() -> String {
+    "bge-reranker-base".to_string()
+}
+
+fn default_working_tree_weight() -> f32 {
+    0.0 // Off by default — opt-in
+}
+
+fn default_graph_weight() -> f32 {
+    0.0 // Off by default — opt-in
+}
+
+fn default_graph_seeds() -> usize {
+    5
+}
+
+fn default_mistakes_limit() -> usize {
+    2
+}
+
+fn default_mistakes_min_similarity() -> f32 {
+    0.6 // Stricter than min_similarity: failures only help when closely related
+}
+
+fn default_impression_window_minutes() -> u32 {
+    60
+}
+
+fn default_project_utility_prior() -> f32 {
+    5.0
+}
+
+fn default_implicit_feedback_weight() -> f32 {
+    0.3 // Inferred, so it counts for well under explicit feedback
+}
+
+fn default_prune_interval() -> String {
+    "never".to_string()
+}
+
+fn default_reindex_interval() -> String {
+    "daily".to_string()
+}
+
+fn default_min_propensity() -> f32 {
+    0.1
+}
+
+fn default_exploration_mode() -> String {
+    "thompson".to_string()
+}
+
 fn default_decay_rate() -> f64 {
     0.01
 }
@@ -246,6 +456,38 @@ fn default_temporal_credit_window_hours() -> i64 {
     1
 }
 
+fn default_penalty_gamma() -> f32 {
+    0.5
+}
+
+fn default_penalty_threshold() -> f32 {
+    0.7
+}
+
+fn default_test_reward_weight() -> f32 {
+    0.8
+}
+
+fn default_session_weight() -> f32 {
+    0.9
+}
+
+fn default_continuation_weight() -> f32 {
+    0.8
+}
+
+fn default_prerequisite_weight() -> f32 {
+    1.0
+}
+
+fn default_alternative_weight() -> f32 {
+    -0.2
+}
+
+fn default_related_weight() -> f32 {
+    0.5
+}
+
 fn default_consolidation_threshold() -> f32 {
     0.85
 }
@@ -270,6 +512,7 @@ impl Default for Config {
             retrieval: RetrievalConfig::default(),
             bellman: BellmanConfig::default(),
             storage: StorageConfig::default(),
+            maintenance: MaintenanceConfig::default(),
         }
     }
 }
@@ -335,6 +578,16 @@ impl Config {
         Ok(Self::data_dir()?.join("feedback.log"))
     }
 
+    /// Get the retrieval event log path (~/.tempera/retrievals.jsonl)
+    pub fn retrieval_events_path() -> Result<PathBuf> {
+        Ok(Self::data_dir()?.join("retrievals.jsonl"))
+    }
+
+    /// Get the utility ledger path (~/.tempera/utility_ledger.jsonl)
+    pub fn utility_ledger_path() -> Result<PathBuf> {
+        Ok(Self::data_dir()?.join("utility_ledger.jsonl"))
+    }
+
     /// Get today's episode directory
     pub fn today_episodes_dir() -> Result<PathBuf> {
         let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
@@ -358,13 +611,45 @@ mod tests {
         assert_eq!(config.bellman.propagation_threshold, 0.5);
         assert_eq!(config.bellman.max_propagation_depth, 2);
         assert_eq!(config.bellman.temporal_credit_window_hours, 1);
+        assert_eq!(config.bellman.penalty_gamma, 0.5);
+        assert_eq!(config.bellman.penalty_threshold, 0.7);
+        assert_eq!(config.bellman.test_reward_weight, 0.8);
+        assert_eq!(config.bellman.relation_weights.session, 0.9);
+        assert_eq!(
+            config
+                .bellman
+                .relation_weights
+                .weight(EpisodeRelation::Prerequisite),
+            1.0
+        );
+        assert!(config.bellman.relation_weights.alternative < 0.0);
         assert_eq!(config.storage.consolidation_threshold, 0.85);
         assert_eq!(config.storage.cluster_threshold, 0.85);
         assert_eq!(config.storage.stale_age_days, 30);
         assert_eq!(config.storage.stale_utility_threshold, 0.2);
+        // Auto-maintenance never prunes unless asked to
+        assert!(config.maintenance.auto);
+        assert_eq!(config.maintenance.prune_interval, "never");
+        assert_eq!(config.maintenance.reindex_interval, "daily");
         // Recency defaults
         assert_eq!(config.retrieval.recency_weight, 0.0);
         assert_eq!(config.retrieval.recency_halflife_days, 30.0);
+        // Reranking is opt-in
+        assert!(!config.retrieval.rerank);
+        assert_eq!(config.retrieval.rerank_top_n, 20);
+        assert_eq!(config.retrieval.rerank_model, "bge-reranker-base");
+        assert_eq!(config.retrieval.working_tree_weight, 0.0);
+        assert_eq!(config.retrieval.graph_weight, 0.0);
+        assert_eq!(config.retrieval.graph_seeds, 5);
+        assert_eq!(config.retrieval.mistakes_limit, 2);
+        assert_eq!(config.retrieval.mistakes_min_similarity, 0.6);
+        assert_eq!(config.retrieval.impression_window_minutes, 60);
+        assert_eq!(config.retrieval.project_utility_prior, 5.0);
+        assert_eq!(config.retrieval.implicit_feedback_weight, 0.3);
+        assert_eq!(config.retrieval.min_propensity, 0.1);
+        // Exploration is opt-in
+        assert_eq!(config.retrieval.exploration_rate, 0.0);
+        assert_eq!(config.retrieval.exploration_mode, "thompson");
     }
 
     #[test]
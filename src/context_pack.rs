@@ -0,0 +1,454 @@
+// Copyright 2024-2026 Andrey Vasilevsky <anvanster@gmail.com>
+// SPDX-License-Identifier: Apache-2.0
+
+#![allow(dead_code)]
+//! Token-budgeted context pack for session start.
+//!
+//! Builds a compact brief from the top best known methods (summary and
+//! resolutions only), relevant failures, and the last session's unfinished
+//! work for the current project, trimmed to fit a token estimate.
+
+use anyhow::Result;
+
+use crate::config::Config;
+use crate::episode::{Episode, OutcomeStatus};
+use crate::events::current_project;
+use crate::retrieve::{self, ScoredEpisode};
+use crate::store::EpisodeStore;
+
+/// Candidates fetched per section before trimming to the budget
+const CANDIDATE_LIMIT: usize = 5;
+
+/// Longest title kept for a single item
+const MAX_TITLE_CHARS: usize = 160;
+
+/// Run the context-pack command
+pub async fn run(
+    query: &str,
+    budget: usize,
+    project: Option<String>,
+    format: &str,
+    config: &Config,
+) -> Result<()> {
+    let format = PackFormat::parse(format)?;
+    let store = EpisodeStore::new()?;
+    let project = project.unwrap_or_else(current_project);
+
+    // BKMs come from every project; unfinished work is specific to this one
+    let outcome = retrieve::search(query, CANDIDATE_LIMIT, None, config, &store).await?;
+    let mut unfinished = unfinished_work(&store, &project)?;
+    unfinished.retain(|ep| {
+        !outcome
+            .episodes
+            .iter()
+            .chain(&outcome.mistakes)
+            .any(|s| s.episode.id == ep.id)
+    });
+
+    let sections = vec![
+        Section::new(SectionKind::Methods, outcome.episodes.iter().map(bkm_item)),
+        Section::new(
+            SectionKind::Unfinished,
+            unfinished.iter().map(unfinished_item),
+        ),
+        Section::new(
+            SectionKind::Mistakes,
+            outcome.mistakes.iter().map(mistake_item),
+        ),
+    ];
+
+    let (pack, included) = build_pack(sections, budget, format);
+    if included.is_empty() {
+        return Ok(());
+    }
+    println!("{}", pack);
+
+    // Only episodes that made it into the pack count as retrieved
+    let retrieved: Vec<(&Episode, f32)> = outcome
+        .episodes
+        .iter()
+        .chain(&outcome.mistakes)
+        .filter(|s| included.contains(&s.episode.id[..8].to_string()))
+        .map(|s| (&s.episode, s.combined_score))
+        .collect();
+    retrieve::record_retrievals(&retrieved, query, &store, config)?;
+
+    Ok(())
+}
+
+/// Output format for the pack
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PackFormat {
+    Markdown,
+    Xml,
+}
+
+impl PackFormat {
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "markdown" | "md" => Ok(PackFormat::Markdown),
+            "xml" => Ok(PackFormat::Xml),
+            _ => anyhow::bail!("Unknown format '{}' (expected markdown or xml)", s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SectionKind {
+    Methods,
+    Unfinished,
+    Mistakes,
+}
+
+impl SectionKind {
+    fn title(&self) -> &'static str {
+        match self {
+            SectionKind::Methods => "Best Known Methods",
+            SectionKind::Unfinished => "Unfinished Work (last session)",
+            SectionKind::Mistakes => "Mistakes to Avoid",
+        }
+    }
+
+    fn tag(&self) -> &'static str {
+        match self {
+            SectionKind::Methods => "best-known-methods",
+            SectionKind::Unfinished => "unfinished-work",
+            SectionKind::Mistakes => "mistakes-to-avoid",
+        }
+    }
+
+    fn item_tag(&self) -> &'static str {
+        match self {
+            SectionKind::Methods => "method",
+            SectionKind::Unfinished => "task",
+            SectionKind::Mistakes => "mistake",
+        }
+    }
+}
+
+/// One episode in the pack: a title line plus optional detail lines,
+/// which are dropped from the end when the item doesn't fit
+#[derive(Debug, Clone)]
+struct PackItem {
+    id: String,
+    title: String,
+    details: Vec<String>,
+}
+
+struct Section {
+    kind: SectionKind,
+    items: Vec<PackItem>,
+}
+
+impl Section {
+    fn new(kind: SectionKind, items: impl Iterator<Item = PackItem>) -> Self {
+        Self {
+            kind,
+            items: items.collect(),
+        }
+    }
+}
+
+/// Rough token estimate (~4 characters per token for English and code)
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// Fill the pack within `budget` tokens. Items are taken round-robin across
+/// sections (top BKM, unfinished task, top mistake, next BKM, ...) so no
+/// section starves the others; an item that doesn't fit is retried with fewer
+/// detail lines before being skipped. Returns the rendered pack and the ids
+/// of included episodes.
+fn build_pack(sections: Vec<Section>, budget: usize, format: PackFormat) -> (String, Vec<String>) {
+    let mut packed: Vec<Section> = sections
+        .iter()
+        .map(|s| Section {
+            kind: s.kind,
+            items: vec![],
+        })
+        .collect();
+
+    let rounds = sections.iter().map(|s| s.items.len()).max().unwrap_or(0);
+    for round in 0..rounds {
+        for (idx, section) in sections.iter().enumerate() {
+            let Some(item) = section.items.get(round) else {
+                continue;
+            };
+
+            let mut candidate = item.clone();
+            loop {
+                packed[idx].items.push(candidate.clone());
+                if estimate_tokens(&render(&packed, format)) <= budget {
+                    break;
+                }
+                packed[idx].items.pop();
+                if candidate.details.pop().is_none() {
+                    break;
+                }
+            }
+        }
+    }
+
+    let included = packed
+        .iter()
+        .flat_map(|s| s.items.iter().map(|i| i.id.clone()))
+        .collect();
+    (render(&packed, format), included)
+}
+
+fn render(sections: &[Section], format: PackFormat) -> String {
+    let sections: Vec<&Section> = sections.iter().filter(|s| !s.items.is_empty()).collect();
+    if sections.is_empty() {
+        return String::new();
+    }
+
+    let mut out = String::new();
+    match format {
+        PackFormat::Markdown => {
+            out.push_str("## Tempera Context\n");
+            for section in sections {
+                out.push_str(&format!("\n### {}\n", section.kind.title()));
+                for item in &section.items {
+                    out.push_str(&format!("- [{}] {}\n", item.id, item.title));
+                    for detail in &item.details {
+                        out.push_str(&format!("  - {}\n", detail));
+                    }
+                }
+            }
+        }
+        PackFormat::Xml => {
+            out.push_str("<tempera-context>\n");
+            for section in sections {
+                let tag = section.kind.tag();
+                let item_tag = section.kind.item_tag();
+                out.push_str(&format!("<{}>\n", tag));
+                for item in &section.items {
+                    out.push_str(&format!(
+                        "<{} id=\"{}\">{}",
+                        item_tag,
+                        item.id,
+                        xml_escape(&item.title)
+                    ));
+                    for detail in &item.details {
+                        out.push_str(&format!("\n- {}", xml_escape(detail)));
+                    }
+                    out.push_str(&format!("</{}>\n", item_tag));
+                }
+                out.push_str(&format!("</{}>\n", tag));
+            }
+            out.push_str("</tempera-context>");
+        }
+    }
+
+    out.trim_end().to_string()
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn title(ep: &Episode) -> String {
+    let text = if ep.intent.extracted_intent.is_empty() {
+        &ep.intent.raw_prompt
+    } else {
+        &ep.intent.extracted_intent
+    };
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() > MAX_TITLE_CHARS {
+        let truncated: String = text.chars().take(MAX_TITLE_CHARS - 3).collect();
+        format!("{}...", truncated)
+    } else {
+        text
+    }
+}
+
+fn short_id(ep: &Episode) -> String {
+    ep.id[..8].to_string()
+}
+
+/// Best known method: summary plus stored resolutions
+fn bkm_item(scored: &ScoredEpisode) -> PackItem {
+    let ep = &scored.episode;
+    let details = ep
+        .context
+        .errors_encountered
+        .iter()
+        .filter(|e| e.resolved)
+        .filter_map(|e| {
+            e.resolution
+                .as_ref()
+                .map(|r| format!("{} → {}", e.message, r))
+        })
+        .take(2)
+        .collect();
+
+    PackItem {
+        id: short_id(ep),
+        title: title(ep),
+        details,
+    }
+}
+
+/// Relevant failure: summary plus what went wrong
+fn mistake_item(scored: &ScoredEpisode) -> PackItem {
+    let ep = &scored.episode;
+    PackItem {
+        id: short_id(ep),
+        title: title(ep),
+        details: unresolved_errors(ep, 2),
+    }
+}
+
+/// Unfinished task from the last session: summary, open errors, files touched
+fn unfinished_item(ep: &Episode) -> PackItem {
+    let mut details = unresolved_errors(ep, 2);
+    if !ep.context.files_modified.is_empty() {
+        let files: Vec<&str> = ep
+            .context
+            .files_modified
+            .iter()
+            .take(5)
+            .map(|f| f.as_str())
+            .collect();
+        details.push(format!("files: {}", files.join(", ")));
+    }
+
+    PackItem {
+        id: short_id(ep),
+        title: format!("({}) {}", outcome_label(&ep.outcome.status), title(ep)),
+        details,
+    }
+}
+
+fn unresolved_errors(ep: &Episode, limit: usize) -> Vec<String> {
+    ep.context
+        .errors_encountered
+        .iter()
+        .filter(|e| !e.resolved)
+        .take(limit)
+        .map(|e| format!("unresolved: {}", e.message))
+        .collect()
+}
+
+fn outcome_label(status: &OutcomeStatus) -> &'static str {
+    match status {
+        OutcomeStatus::Success => "success",
+        OutcomeStatus::Partial => "partial",
+        OutcomeStatus::Failure => "failure",
+    }
+}
+
+/// Episodes from the project's most recent session that didn't succeed
+fn unfinished_work(store: &EpisodeStore, project: &str) -> Result<Vec<Episode>> {
+    let Some(latest) = store.latest_for_project(project)? else {
+        return Ok(vec![]);
+    };
+
+    let session = match &latest.session_id {
+        Some(sid) => store.list_by_session(sid)?,
+        None => vec![latest],
+    };
+
+    Ok(session
+        .into_iter()
+        .filter(|ep| ep.outcome.status != OutcomeStatus::Success)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: &str, title: &str, details: &[&str]) -> PackItem {
+        PackItem {
+            id: id.to_string(),
+            title: title.to_string(),
+            details: details.iter().map(|d| d.to_string()).collect(),
+        }
+    }
+
+    fn sections() -> Vec<Section> {
+        vec![
+            Section {
+                kind: SectionKind::Methods,
+                items: vec![
+                    item(
+                        "aaaa0001",
+                        "Pool exhaustion fixed by raising max_connections",
+                        &["timeout acquiring connection → raise pool size and add idle timeout"],
+                    ),
+                    item(
+                        "aaaa0002",
+                        "Retry transient DB errors with jittered backoff",
+                        &[],
+                    ),
+                ],
+            },
+            Section {
+                kind: SectionKind::Unfinished,
+                items: vec![item(
+                    "bbbb0001",
+                    "(partial) Migrate sessions table",
+                    &["unresolved: deadlock detected"],
+                )],
+            },
+            Section {
+                kind: SectionKind::Mistakes,
+                items: vec![item(
+                    "cccc0001",
+                    "Tried disabling pool health checks",
+                    &["unresolved: stale connections after failover"],
+                )],
+            },
+        ]
+    }
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcd"), 1);
+        assert_eq!(estimate_tokens("abcde"), 2);
+    }
+
+    #[test]
+    fn test_build_pack_fits_everything_with_large_budget() {
+        let (pack, included) = build_pack(sections(), 10_000, PackFormat::Markdown);
+        assert_eq!(included.len(), 4);
+        assert!(pack.contains("### Best Known Methods"));
+        assert!(pack.contains("### Unfinished Work"));
+        assert!(pack.contains("### Mistakes to Avoid"));
+        assert!(pack.contains("raise pool size"));
+    }
+
+    #[test]
+    fn test_build_pack_respects_budget() {
+        for budget in [20, 40, 60, 80] {
+            let (pack, _) = build_pack(sections(), budget, PackFormat::Markdown);
+            assert!(estimate_tokens(&pack) <= budget, "budget {}", budget);
+        }
+
+        // Round-robin: the top item of each section beats a second BKM
+        let full = estimate_tokens(&build_pack(sections(), 10_000, PackFormat::Markdown).0);
+        let (_, included) = build_pack(sections(), full - 10, PackFormat::Markdown);
+        assert!(included.contains(&"bbbb0001".to_string()));
+        assert!(included.contains(&"cccc0001".to_string()));
+
+        let (pack, included) = build_pack(sections(), 0, PackFormat::Markdown);
+        assert!(pack.is_empty());
+        assert!(included.is_empty());
+    }
+
+    #[test]
+    fn test_xml_format() {
+        let mut sections = sections();
+        sections[0].items[0].title = "Use Vec<u8> & friends".to_string();
+        let (pack, _) = build_pack(sections, 10_000, PackFormat::Xml);
+        assert!(pack.starts_with("<tempera-context>"));
+        assert!(pack.ends_with("</tempera-context>"));
+        assert!(pack.contains("<method id=\"aaaa0001\">Use Vec&lt;u8&gt; &amp; friends"));
+        assert!(pack.contains("<mistakes-to-avoid>"));
+        assert!(PackFormat::parse("json").is_err());
+    }
+}
@@ -3,6 +3,8 @@
 
 #![allow(dead_code)]
 
+use std::collections::BTreeMap;
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
@@ -112,41 +114,281 @@ impl std::fmt::Display for OutcomeStatus {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
 pub struct TestResults {
     pub passed: u32,
     pub failed: u32,
     pub skipped: u32,
 }
 
+impl TestResults {
+    /// Tests that ran (passed or failed)
+    pub fn total(&self) -> u32 {
+        self.passed + self.failed
+    }
+
+    /// Whether tests ran and none failed
+    pub fn is_green(&self) -> bool {
+        self.total() > 0 && self.failed == 0
+    }
+
+    /// Add another summary (e.g. one test binary of several in a run)
+    pub fn add(&mut self, other: &TestResults) {
+        self.passed += other.passed;
+        self.failed += other.failed;
+        self.skipped += other.skipped;
+    }
+}
+
+impl std::fmt::Display for TestResults {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} passed, {} failed", self.passed, self.failed)?;
+        if self.skipped > 0 {
+            write!(f, ", {} skipped", self.skipped)?;
+        }
+        Ok(())
+    }
+}
+
+impl Outcome {
+    /// Measured reward from test results, 0.0 - 1.0: half for ending green,
+    /// half for the share of failures at the start that were fixed. A red
+    /// to green session earns 1.0. None unless the first run had failures,
+    /// since staying green measures nothing the session did.
+    pub fn test_reward(&self) -> Option<f32> {
+        let before = self.tests_before.as_ref().filter(|t| t.failed > 0)?;
+        let after = self.tests_after.as_ref().filter(|t| t.total() > 0)?;
+        let fixed = before.failed.saturating_sub(after.failed) as f32 / before.failed as f32;
+        let green = if after.is_green() { 1.0 } else { 0.0 };
+        Some(0.5 * green + 0.5 * fixed)
+    }
+
+    /// Take status and test results from a newer capture of the same work;
+    /// test results are kept when the newer capture has none
+    pub fn update_from(&mut self, newer: &Outcome) {
+        self.status = newer.status.clone();
+        if newer.tests_after.is_some() {
+            self.tests_before = newer.tests_before.clone();
+            self.tests_after = newer.tests_after.clone();
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Utility {
     /// Learned utility score (0.0 - 1.0)
     pub score: Option<f32>,
-    /// Number of times this episode was retrieved
+    /// Number of times this episode was shown in results, counted once per
+    /// session within the impression window
     pub retrieval_count: u32,
     /// Number of times marked as helpful
     pub helpful_count: u32,
+    /// Helpful feedback with partial credit for graded feedback (grade / 3);
+    /// None until the first graded feedback, `helpful_count` stands in before
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub helpful_credit: Option<f32>,
+    /// Number of times opened by ID from the MCP server; shown in stats but
+    /// kept out of the utility score
+    #[serde(default)]
+    pub used_count: u32,
+    /// Impressions weighted by the examination propensity of the rank they
+    /// were shown at (see `position_bias.rs`); None until the first ranked
+    /// impression
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub examined: Option<f32>,
+    /// Score as last set by feedback or propagation, before time decay.
+    /// `score` is derived from it (see `utility::decayed_score`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_score: Option<f32>,
+    /// When `base_score` was set; decay runs from here or the last retrieval,
+    /// whichever is later
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_updated_at: Option<DateTime<Utc>>,
+    /// Impressions and feedback split by the project that retrieved the
+    /// episode (`RetrievalRecord.project`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub by_project: BTreeMap<String, ProjectUtility>,
+    /// Credit inferred from captures that followed a retrieval, already
+    /// scaled down by the implicit feedback weight (see `implicit_feedback.rs`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub implicit_credit: Option<f32>,
+}
+
+/// Impressions and feedback from retrievals made in one project
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct ProjectUtility {
+    pub retrieval_count: u32,
+    pub helpful_count: u32,
+    /// Helpful feedback with partial credit for graded feedback (grade / 3)
+    pub helpful_credit: f32,
+}
+
+impl ProjectUtility {
+    pub fn trials(&self) -> f64 {
+        (self.retrieval_count as f64).max(self.helpful_credit as f64)
+    }
+
+    /// Wilson lower bound of the helpful rate in this project
+    pub fn score(&self) -> f32 {
+        wilson_lower_bound(self.helpful_credit as f64, self.trials())
+    }
+
+    /// Beta posterior over the helpful rate in this project, from the same
+    /// uniform prior as `Utility::posterior`
+    pub fn posterior(&self) -> (f64, f64) {
+        let helpful = self.helpful_credit as f64;
+        (1.0 + helpful, 1.0 + (self.trials() - helpful).max(0.0))
+    }
 }
 
 impl Utility {
+    /// Count a new impression shown at a rank with the given propensity
+    pub fn record_impression(&mut self, propensity: f32) {
+        let examined = self.examined.unwrap_or(self.retrieval_count as f32);
+        self.retrieval_count += 1;
+        self.examined = Some(examined + propensity);
+    }
+
+    /// Record graded feedback: fractional credit of `grade / 3`, and a full
+    /// helpful count from `HELPFUL_GRADE` up
+    pub fn record_grade(&mut self, grade: u8) {
+        let grade = grade.min(MAX_FEEDBACK_GRADE);
+        let credit = grade as f32 / MAX_FEEDBACK_GRADE as f32;
+        self.helpful_credit = Some(self.explicit_successes() + credit);
+        if grade >= HELPFUL_GRADE {
+            self.helpful_count += 1;
+        }
+    }
+
+    /// Add weighted credit inferred from a later capture
+    pub fn record_implicit(&mut self, credit: f32) {
+        self.implicit_credit = Some(self.implicit_credit.unwrap_or(0.0) + credit);
+    }
+
+    /// Take back implicit credit once explicit feedback supersedes it
+    pub fn retract_implicit(&mut self, credit: f32) {
+        if let Some(current) = self.implicit_credit {
+            self.implicit_credit = Some((current - credit).max(0.0));
+        }
+    }
+
+    /// Count a new impression in the retrieving project
+    pub fn record_project_impression(&mut self, project: &str) {
+        self.by_project
+            .entry(project.to_string())
+            .or_default()
+            .retrieval_count += 1;
+    }
+
+    /// Credit graded feedback to the project the retrieval was made in
+    pub fn record_project_grade(&mut self, project: &str, grade: u8) {
+        let grade = grade.min(MAX_FEEDBACK_GRADE);
+        let local = self.by_project.entry(project.to_string()).or_default();
+        local.helpful_credit += grade as f32 / MAX_FEEDBACK_GRADE as f32;
+        if grade >= HELPFUL_GRADE {
+            local.helpful_count += 1;
+        }
+    }
+
+    /// Utility as seen from `project`: the project-local Wilson score blended
+    /// with the global one, weighted by evidence. Local trials count in full
+    /// and the global score counts as `prior` trials, so a few local
+    /// impressions nudge the score and many of them dominate it.
+    pub fn project_score(&self, project: &str, prior: f32) -> f32 {
+        self.blend_project(
+            project,
+            prior,
+            self.calculate_score(),
+            ProjectUtility::score,
+        )
+    }
+
+    /// Blend a global estimate with the matching project-local one, weighted
+    /// as in `project_score`
+    pub fn blend_project(
+        &self,
+        project: &str,
+        prior: f32,
+        global: f32,
+        local: impl FnOnce(&ProjectUtility) -> f32,
+    ) -> f32 {
+        let Some(stats) = self.by_project.get(project).filter(|l| l.trials() > 0.0) else {
+            return global;
+        };
+        let n = stats.trials() as f32;
+        let weight = n / (n + prior.max(0.0));
+        weight * local(stats) + (1.0 - weight) * global
+    }
+
+    /// Successes for the Wilson score and posterior: graded helpful credit,
+    /// or the helpful count for episodes without graded feedback, plus
+    /// implicit credit
+    pub fn successes(&self) -> f64 {
+        (self.explicit_successes() + self.implicit_credit.unwrap_or(0.0)) as f64
+    }
+
+    fn explicit_successes(&self) -> f32 {
+        self.helpful_credit.unwrap_or(self.helpful_count as f32)
+    }
+
+    /// Trials for the Wilson score: position-weighted impressions (plain
+    /// impressions for older episodes), never fewer than the times the
+    /// episode was marked helpful. Opening an episode isn't a failed trial,
+    /// so `used_count` stays out of it.
+    pub fn trials(&self) -> f64 {
+        let impressions = self.examined.unwrap_or(self.retrieval_count as f32) as f64;
+        impressions
+            .max(self.helpful_count as f64)
+            .max(self.successes())
+    }
+
+    /// Set a newly learned score; time decay restarts from `at`
+    pub fn set_score(&mut self, score: f32, at: DateTime<Utc>) {
+        self.base_score = Some(score);
+        self.base_updated_at = Some(at);
+        self.score = Some(score);
+    }
+
+    /// Learned score before time decay; falls back to the Wilson score for
+    /// episodes no learning step has scored yet
+    pub fn base(&self) -> f32 {
+        self.base_score.unwrap_or_else(|| self.calculate_score())
+    }
+
+    /// Beta posterior over the helpful rate, from a uniform Beta(1, 1) prior:
+    /// `(1 + helpful, 1 + trials - helpful)`
+    pub fn posterior(&self) -> (f64, f64) {
+        let helpful = self.successes();
+        (1.0 + helpful, 1.0 + (self.trials() - helpful).max(0.0))
+    }
+
+    /// Posterior mean of the helpful rate
+    pub fn posterior_mean(&self) -> f32 {
+        let (alpha, beta) = self.posterior();
+        (alpha / (alpha + beta)) as f32
+    }
+
     /// Calculate utility score using Wilson score interval (lower bound)
     /// This handles uncertainty for low-sample episodes
     pub fn calculate_score(&self) -> f32 {
-        let n = self.retrieval_count as f64;
-        if n == 0.0 {
-            return 0.5; // Default for unretreived episodes
-        }
+        wilson_lower_bound(self.successes(), self.trials())
+    }
+}
 
-        let p = self.helpful_count as f64 / n;
-        let z = 1.96; // 95% confidence
+/// Wilson score interval lower bound (95%) of `successes / trials`
+fn wilson_lower_bound(successes: f64, n: f64) -> f32 {
+    if n == 0.0 {
+        return 0.5; // Default for unretreived episodes
+    }
 
-        // Wilson score lower bound
-        let score = (p + z * z / (2.0 * n) - z * ((p * (1.0 - p) + z * z / (4.0 * n)) / n).sqrt())
-            / (1.0 + z * z / n);
+    let p = successes / n;
+    let z = 1.96; // 95% confidence
 
-        score as f32
-    }
+    // Wilson score lower bound
+    let score = (p + z * z / (2.0 * n) - z * ((p * (1.0 - p) + z * z / (4.0 * n)) / n).sqrt())
+        / (1.0 + z * z / n);
+
+    score as f32
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -155,6 +397,110 @@ pub struct RetrievalRecord {
     pub project: String,
     pub task_description: String,
     pub was_helpful: Option<bool>,
+    /// Retrieval event this record belongs to (see `events.rs`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retrieval_id: Option<String>,
+    /// Retrieval session, used to deduplicate impressions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    /// 1-based position in the results
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rank: Option<usize>,
+    /// Graded feedback, 0 (not helpful) to `MAX_FEEDBACK_GRADE` (helpful)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grade: Option<u8>,
+    /// Aspects called out in feedback
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aspects: Vec<FeedbackAspect>,
+    /// Feedback inferred from a later capture; cleared when explicit
+    /// feedback arrives for this retrieval
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub implicit: Option<ImplicitFeedback>,
+}
+
+impl RetrievalRecord {
+    /// Whether this retrieval got explicit feedback
+    pub fn has_feedback(&self) -> bool {
+        self.was_helpful.is_some() || self.grade.is_some()
+    }
+}
+
+/// Evidence that a retrieved episode was used, from a later capture
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImplicitSignal {
+    /// The capture modified files the episode also touched
+    Files,
+    /// The capture shares domain tags with the episode
+    Tags,
+    /// The capture ran into an error the episode dealt with
+    Errors,
+    /// The capture succeeded
+    Success,
+}
+
+impl std::fmt::Display for ImplicitSignal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImplicitSignal::Files => write!(f, "files"),
+            ImplicitSignal::Tags => write!(f, "tags"),
+            ImplicitSignal::Errors => write!(f, "errors"),
+            ImplicitSignal::Success => write!(f, "success"),
+        }
+    }
+}
+
+/// Feedback inferred for one retrieval
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ImplicitFeedback {
+    /// Weighted credit added to `Utility.implicit_credit`
+    pub credit: f32,
+    pub signals: Vec<ImplicitSignal>,
+    /// Captured episode the signals came from
+    pub episode_id: String,
+}
+
+/// Highest feedback grade: the episode changed how the task was solved
+pub const MAX_FEEDBACK_GRADE: u8 = 3;
+
+/// Grades from here up count as helpful
+pub const HELPFUL_GRADE: u8 = 2;
+
+/// A specific quality called out in feedback
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedbackAspect {
+    /// On topic for the task
+    Relevant,
+    /// The advice was right
+    Correct,
+    /// No longer applies (code, APIs or conventions have moved on)
+    Outdated,
+    /// Too general to act on
+    TooVague,
+}
+
+impl FeedbackAspect {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
+            "relevant" => Some(Self::Relevant),
+            "correct" => Some(Self::Correct),
+            "outdated" | "stale" => Some(Self::Outdated),
+            "too_vague" | "vague" => Some(Self::TooVague),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for FeedbackAspect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FeedbackAspect::Relevant => write!(f, "relevant"),
+            FeedbackAspect::Correct => write!(f, "correct"),
+            FeedbackAspect::Outdated => write!(f, "outdated"),
+            FeedbackAspect::TooVague => write!(f, "too vague"),
+        }
+    }
 }
 
 /// A link to a related episode
@@ -165,7 +511,7 @@ pub struct RelatedEpisode {
 }
 
 /// The type of relationship between two episodes
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum EpisodeRelation {
     /// Same task, next step
@@ -178,6 +524,35 @@ pub enum EpisodeRelation {
     Related,
 }
 
+impl EpisodeRelation {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "continuation" | "continues" | "next" => Some(Self::Continuation),
+            "prerequisite" | "prereq" | "requires" => Some(Self::Prerequisite),
+            "alternative" | "alt" => Some(Self::Alternative),
+            "related" => Some(Self::Related),
+            _ => None,
+        }
+    }
+
+    /// Symmetric relations are stored on both episodes; directed ones
+    /// (continuation, prerequisite) only on the source
+    pub fn is_symmetric(&self) -> bool {
+        matches!(self, Self::Alternative | Self::Related)
+    }
+}
+
+impl std::fmt::Display for EpisodeRelation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EpisodeRelation::Continuation => write!(f, "continuation"),
+            EpisodeRelation::Prerequisite => write!(f, "prerequisite"),
+            EpisodeRelation::Alternative => write!(f, "alternative"),
+            EpisodeRelation::Related => write!(f, "related"),
+        }
+    }
+}
+
 impl Episode {
     pub fn new(project: String, raw_prompt: String) -> Self {
         Self {
@@ -211,6 +586,69 @@ impl Episode {
         }
     }
 
+    /// Whether being shown at `now` repeats an impression already recorded
+    /// for the same session (or project, without a session) within `window`
+    pub fn is_repeat_impression(
+        &self,
+        project: &str,
+        session_id: Option<&str>,
+        now: DateTime<Utc>,
+        window: chrono::Duration,
+    ) -> bool {
+        self.retrieval_history.iter().any(|r| {
+            let same_scope = match session_id {
+                Some(sid) => r.session_id.as_deref() == Some(sid),
+                None => r.project == project,
+            };
+            same_scope && now - r.timestamp < window
+        })
+    }
+
+    /// Relation from this episode to another, if linked
+    pub fn relation_to(&self, id: &str) -> Option<EpisodeRelation> {
+        self.related_episodes
+            .iter()
+            .find(|r| r.id == id)
+            .map(|r| r.relationship)
+    }
+
+    /// Link to another episode, replacing any existing relation to it
+    pub fn add_relation(&mut self, id: &str, relationship: EpisodeRelation) {
+        match self.related_episodes.iter_mut().find(|r| r.id == id) {
+            Some(existing) => existing.relationship = relationship,
+            None => self.related_episodes.push(RelatedEpisode {
+                id: id.to_string(),
+                relationship,
+            }),
+        }
+    }
+
+    /// Remove the link to another episode; returns whether one existed
+    pub fn remove_relation(&mut self, id: &str) -> bool {
+        let before = self.related_episodes.len();
+        self.related_episodes.retain(|r| r.id != id);
+        self.related_episodes.len() != before
+    }
+
+    /// Short list of linked episodes, e.g. "ab12cd34 (prerequisite), ..."
+    pub fn format_relations(&self) -> String {
+        self.related_episodes
+            .iter()
+            .map(|r| format!("{} ({})", &r.id[..8.min(r.id.len())], r.relationship))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Whether the most recent feedback that named aspects marked this
+    /// episode outdated
+    pub fn marked_outdated(&self) -> bool {
+        self.retrieval_history
+            .iter()
+            .rev()
+            .find(|r| !r.aspects.is_empty())
+            .is_some_and(|r| r.aspects.contains(&FeedbackAspect::Outdated))
+    }
+
     /// Convert to markdown format for human-readable storage
     pub fn to_markdown(&self) -> String {
         let mut md = String::new();
@@ -231,6 +669,12 @@ impl Episode {
         ));
         md.push_str(&format!("**Project**: {}\n", self.project));
         md.push_str(&format!("**Outcome**: {}\n", self.outcome.status));
+        if let Some(after) = &self.outcome.tests_after {
+            match &self.outcome.tests_before {
+                Some(before) => md.push_str(&format!("**Tests**: {} → {}\n", before, after)),
+                None => md.push_str(&format!("**Tests**: {}\n", after)),
+            }
+        }
         if let Some(sid) = &self.session_id {
             md.push_str(&format!("**Session**: {}\n", &sid[..8.min(sid.len())]));
         }
@@ -287,16 +731,10 @@ impl Episode {
         if !self.related_episodes.is_empty() {
             md.push_str("## Related Episodes\n\n");
             for rel in &self.related_episodes {
-                let rel_type = match rel.relationship {
-                    EpisodeRelation::Continuation => "continuation",
-                    EpisodeRelation::Prerequisite => "prerequisite",
-                    EpisodeRelation::Alternative => "alternative",
-                    EpisodeRelation::Related => "related",
-                };
                 md.push_str(&format!(
                     "- {} ({})\n",
                     &rel.id[..8.min(rel.id.len())],
-                    rel_type
+                    rel.relationship
                 ));
             }
             md.push('\n');
@@ -307,11 +745,20 @@ impl Episode {
             md.push_str("| Date | Project | Task | Helpful |\n");
             md.push_str("|------|---------|------|--------|\n");
             for r in &self.retrieval_history {
-                let helpful = match r.was_helpful {
+                let mut helpful = match r.was_helpful {
                     Some(true) => "✅",
                     Some(false) => "❌",
                     None => "?",
-                };
+                }
+                .to_string();
+                if let Some(grade) = r.grade {
+                    helpful.push_str(&format!(" {}/{}", grade, MAX_FEEDBACK_GRADE));
+                }
+                if let Some(implicit) = &r.implicit {
+                    let signals: Vec<String> =
+                        implicit.signals.iter().map(|s| s.to_string()).collect();
+                    helpful.push_str(&format!(" (implicit: {})", signals.join(", ")));
+                }
                 md.push_str(&format!(
                     "| {} | {} | {} | {} |\n",
                     r.timestamp.format("%Y-%m-%d"),
@@ -409,6 +856,8 @@ mod tests {
             score: None,
             retrieval_count: 10,
             helpful_count: 10,
+            used_count: 0,
+            ..Default::default()
         };
         assert!(utility.calculate_score() > 0.7);
 
@@ -417,10 +866,237 @@ mod tests {
             score: None,
             retrieval_count: 10,
             helpful_count: 0,
+            used_count: 0,
+            ..Default::default()
         };
         assert!(utility.calculate_score() < 0.3);
     }
 
+    #[test]
+    fn test_utility_trials() {
+        // Helpful feedback without a counted impression can't push p above 1
+        let utility = Utility {
+            score: None,
+            retrieval_count: 1,
+            helpful_count: 3,
+            used_count: 0,
+            ..Default::default()
+        };
+        assert_eq!(utility.trials(), 3.0);
+        assert!(utility.calculate_score() <= 1.0);
+
+        // Opening an episode doesn't dilute its helpful rate
+        let utility = Utility {
+            score: None,
+            retrieval_count: 1,
+            helpful_count: 1,
+            used_count: 3,
+            ..Default::default()
+        };
+        assert_eq!(utility.trials(), 1.0);
+        let unused = Utility {
+            used_count: 0,
+            ..utility.clone()
+        };
+        assert_eq!(utility.calculate_score(), unused.calculate_score());
+    }
+
+    #[test]
+    fn test_project_score() {
+        let mut utility = Utility::default();
+        for _ in 0..10 {
+            utility.record_impression(1.0);
+            utility.record_grade(3);
+        }
+        let global = utility.calculate_score();
+        // No local evidence: the global score
+        assert_eq!(utility.project_score("web", 5.0), global);
+
+        // Noise in "web": the more local evidence, the further below global
+        utility.record_project_impression("web");
+        utility.record_project_grade("web", 0);
+        let one = utility.project_score("web", 5.0);
+        for _ in 0..9 {
+            utility.record_project_impression("web");
+            utility.record_project_grade("web", 0);
+        }
+        let ten = utility.project_score("web", 5.0);
+        assert!(one < global);
+        assert!(ten < one);
+        assert_eq!(utility.by_project["web"].helpful_count, 0);
+
+        // Other projects are unaffected
+        assert_eq!(utility.project_score("api", 5.0), global);
+    }
+
+    #[test]
+    fn test_position_weighted_trials() {
+        // Two old unranked impressions, then two at a low-propensity rank
+        let mut low = Utility {
+            retrieval_count: 2,
+            helpful_count: 1,
+            ..Default::default()
+        };
+        low.record_impression(0.25);
+        low.record_impression(0.25);
+        assert_eq!(low.retrieval_count, 4);
+        assert_eq!(low.trials(), 2.5);
+
+        let mut top = Utility {
+            retrieval_count: 2,
+            helpful_count: 1,
+            ..Default::default()
+        };
+        top.record_impression(1.0);
+        top.record_impression(1.0);
+        assert_eq!(top.trials(), 4.0);
+
+        // Being skipped at a low rank costs less than being skipped at the top
+        assert!(low.calculate_score() > top.calculate_score());
+    }
+
+    #[test]
+    fn test_utility_posterior() {
+        let utility = Utility::default();
+        assert_eq!(utility.posterior(), (1.0, 1.0));
+        assert_eq!(utility.posterior_mean(), 0.5);
+
+        let utility = Utility {
+            retrieval_count: 8,
+            helpful_count: 6,
+            ..Default::default()
+        };
+        assert_eq!(utility.posterior(), (7.0, 3.0));
+        assert!((utility.posterior_mean() - 0.7).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_graded_feedback_credit() {
+        let mut utility = Utility {
+            retrieval_count: 4,
+            helpful_count: 1,
+            ..Default::default()
+        };
+        let before = utility.calculate_score();
+
+        // Partial credit moves the score without counting as helpful
+        utility.record_grade(1);
+        assert_eq!(utility.helpful_count, 1);
+        assert!((utility.successes() - (1.0 + 1.0 / 3.0)).abs() < 1e-6);
+        assert!(utility.calculate_score() > before);
+
+        utility.record_grade(MAX_FEEDBACK_GRADE);
+        assert_eq!(utility.helpful_count, 2);
+        assert!((utility.successes() - (2.0 + 1.0 / 3.0)).abs() < 1e-6);
+
+        // Not helpful adds no credit
+        let credit = utility.successes();
+        utility.record_grade(0);
+        assert_eq!(utility.successes(), credit);
+    }
+
+    #[test]
+    fn test_outcome_test_reward() {
+        let tests = |passed, failed| TestResults {
+            passed,
+            failed,
+            skipped: 0,
+        };
+        let mut outcome = Episode::new("api".to_string(), "fix".to_string()).outcome;
+        assert_eq!(outcome.test_reward(), None);
+
+        // Red to green
+        outcome.tests_before = Some(tests(8, 4));
+        outcome.tests_after = Some(tests(12, 0));
+        assert_eq!(outcome.test_reward(), Some(1.0));
+
+        // Half the failures fixed, still red
+        outcome.tests_after = Some(tests(10, 2));
+        assert_eq!(outcome.test_reward(), Some(0.25));
+
+        // Green throughout, or no first run to compare with: no reward
+        outcome.tests_before = Some(tests(12, 0));
+        outcome.tests_after = Some(tests(13, 0));
+        assert_eq!(outcome.test_reward(), None);
+        outcome.tests_before = None;
+        assert_eq!(outcome.test_reward(), None);
+
+        // A newer capture without tests keeps the old results
+        let mut newer = outcome.clone();
+        newer.status = OutcomeStatus::Success;
+        newer.tests_after = None;
+        outcome.update_from(&newer);
+        assert_eq!(outcome.status, OutcomeStatus::Success);
+        assert_eq!(outcome.tests_after, Some(tests(13, 0)));
+    }
+
+    #[test]
+    fn test_marked_outdated() {
+        let mut ep = Episode::new("api".to_string(), "fix timeout".to_string());
+        assert!(!ep.marked_outdated());
+
+        let record = |aspects: Vec<FeedbackAspect>| RetrievalRecord {
+            timestamp: Utc::now(),
+            project: "api".to_string(),
+            task_description: "timeout".to_string(),
+            was_helpful: None,
+            retrieval_id: None,
+            session_id: None,
+            rank: None,
+            grade: Some(0),
+            aspects,
+            implicit: None,
+        };
+        ep.retrieval_history.push(record(vec![
+            FeedbackAspect::Relevant,
+            FeedbackAspect::Outdated,
+        ]));
+        ep.retrieval_history.push(record(vec![]));
+        assert!(ep.marked_outdated());
+
+        // Newer feedback without the aspect clears it
+        ep.retrieval_history
+            .push(record(vec![FeedbackAspect::Correct]));
+        assert!(!ep.marked_outdated());
+
+        assert_eq!(
+            FeedbackAspect::parse("too-vague"),
+            Some(FeedbackAspect::TooVague)
+        );
+        assert_eq!(FeedbackAspect::parse("shiny"), None);
+    }
+
+    #[test]
+    fn test_repeat_impression() {
+        let mut ep = Episode::new("api".to_string(), "fix timeout".to_string());
+        let now = Utc::now();
+        let window = chrono::Duration::minutes(60);
+        assert!(!ep.is_repeat_impression("api", Some("s1"), now, window));
+
+        ep.retrieval_history.push(RetrievalRecord {
+            timestamp: now - chrono::Duration::minutes(10),
+            project: "api".to_string(),
+            task_description: "timeout".to_string(),
+            was_helpful: None,
+            retrieval_id: None,
+            session_id: Some("s1".to_string()),
+            rank: Some(1),
+            grade: None,
+            aspects: vec![],
+            implicit: None,
+        });
+
+        assert!(ep.is_repeat_impression("api", Some("s1"), now, window));
+        // A different session is a new impression
+        assert!(!ep.is_repeat_impression("api", Some("s2"), now, window));
+        // So is the same session once the window has passed
+        let later = now + chrono::Duration::minutes(55);
+        assert!(!ep.is_repeat_impression("api", Some("s1"), later, window));
+        // Without sessions, the project scopes deduplication
+        assert!(ep.is_repeat_impression("api", None, now, window));
+        assert!(!ep.is_repeat_impression("web", None, now, window));
+    }
+
     #[test]
     fn test_backward_compat_deserialization() {
         // Old JSON without session_id and related_episodes should deserialize fine
@@ -0,0 +1,200 @@
+// Copyright 2024-2026 Andrey Vasilevsky <anvanster@gmail.com>
+// SPDX-License-Identifier: Apache-2.0
+
+#![allow(dead_code)]
+//! Error-signature normalization for matching compiler and runtime errors.
+//!
+//! Raw error output is dominated by paths, line numbers and project-specific
+//! identifiers, which makes embeddings of it match poorly. A signature keeps
+//! only what identifies the *kind* of error: error codes (`E0599`, `TS2345`),
+//! the exception type, and the remaining message words.
+
+use regex::Regex;
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::LazyLock;
+
+/// Compiler error codes: rustc `E0599`, tsc `TS2345`, C# `CS0103`, MSVC `C2065`
+static CODE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(?:E|TS|CS|C|LNK)\d{3,5}\b").unwrap());
+
+/// Exception / error type names: `TypeError`, `java.lang.NullPointerException`
+static EXCEPTION_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\b(?:[a-z_][a-z0-9_]*\.)*([A-Z][A-Za-z0-9]*(?:Error|Exception|Panic|Fault))\b")
+        .unwrap()
+});
+
+/// Quoted identifiers: `foo`, 'bar', "baz"
+static QUOTED_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"`[^`]*`|'[^'\s]*'|"[^"]*""#).unwrap());
+
+/// File paths, optionally followed by :line:col
+static PATH_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[\w.\-~]*[/\\][\w.\-/\\]+(?::\d+)*").unwrap());
+
+/// Memory addresses
+static ADDR_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b0x[0-9a-fA-F]+\b").unwrap());
+
+/// Qualified or mixed-case identifiers: `foo::bar`, `com.example.Foo`, `snake_case`, `camelCase`
+static IDENT_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\b\w+(?:(?:::|\.)\w+)+\b|\b[a-z0-9]+_\w+\b|\b[a-z]+[A-Z]\w*\b").unwrap()
+});
+
+static NUMBER_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b\d+\b").unwrap());
+
+/// Normalized form of an error message
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ErrorSignature {
+    /// Error codes, uppercased and deduplicated
+    pub codes: Vec<String>,
+    /// Exception or error type name, without package prefix
+    pub exception: Option<String>,
+    /// Remaining lowercase message words with identifiers and noise removed
+    pub tokens: Vec<String>,
+}
+
+impl ErrorSignature {
+    /// Normalize a raw error message (may be multi-line compiler output)
+    pub fn from_message(message: &str) -> Self {
+        let mut codes: Vec<String> = CODE_RE
+            .find_iter(message)
+            .map(|m| m.as_str().to_uppercase())
+            .collect();
+        codes.sort();
+        codes.dedup();
+
+        let exception = EXCEPTION_RE
+            .captures(message)
+            .and_then(|caps| caps.get(1))
+            .map(|m| m.as_str().to_string());
+
+        let mut text = EXCEPTION_RE.replace_all(message, " ").to_string();
+        text = QUOTED_RE.replace_all(&text, " ").to_string();
+        text = PATH_RE.replace_all(&text, " ").to_string();
+        text = ADDR_RE.replace_all(&text, " ").to_string();
+        text = CODE_RE.replace_all(&text, " ").to_string();
+        text = IDENT_RE.replace_all(&text, " ").to_string();
+        text = NUMBER_RE.replace_all(&text, " ").to_string();
+
+        let mut seen = HashSet::new();
+        let tokens = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| w.len() > 1)
+            .map(|w| w.to_lowercase())
+            .filter(|w| seen.insert(w.clone()))
+            .collect();
+
+        Self {
+            codes,
+            exception,
+            tokens,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.codes.is_empty() && self.exception.is_none() && self.tokens.is_empty()
+    }
+
+    /// Similarity between two signatures (0.0 - 1.0).
+    ///
+    /// A shared error code is the strongest signal; conflicting codes mean a
+    /// different error even if the wording overlaps. When either side has no
+    /// code (stored messages often lost it) the message words decide.
+    pub fn similarity(&self, other: &ErrorSignature) -> f32 {
+        let text = jaccard(&self.tokens, &other.tokens);
+
+        let mut score = if self.codes.is_empty() || other.codes.is_empty() {
+            text
+        } else if self.codes.iter().any(|c| other.codes.contains(c)) {
+            0.5 + 0.5 * text
+        } else {
+            0.25 * text
+        };
+
+        if let (Some(a), Some(b)) = (&self.exception, &other.exception) {
+            if a == b {
+                score = 0.3 + 0.7 * score;
+            } else {
+                score *= 0.5;
+            }
+        }
+
+        score
+    }
+}
+
+impl fmt::Display for ErrorSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if !self.codes.is_empty() {
+            parts.push(self.codes.join(","));
+        }
+        if let Some(exception) = &self.exception {
+            parts.push(exception.clone());
+        }
+        if !self.tokens.is_empty() {
+            parts.push(self.tokens.join(" "));
+        }
+        write!(f, "{}", parts.join(" | "))
+    }
+}
+
+fn jaccard(a: &[String], b: &[String]) -> f32 {
+    let a: HashSet<&String> = a.iter().collect();
+    let b: HashSet<&String> = b.iter().collect();
+    let union = a.union(&b).count();
+    if union == 0 {
+        0.0
+    } else {
+        a.intersection(&b).count() as f32 / union as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rust_error_signature() {
+        let sig = ErrorSignature::from_message(
+            "error[E0599]: no method named `frobnicate` found for struct `Widget` in the current scope\n  --> src/widget.rs:42:17",
+        );
+        assert_eq!(sig.codes, vec!["E0599"]);
+        assert!(sig.exception.is_none());
+        assert!(sig.tokens.contains(&"method".to_string()));
+        assert!(!sig.tokens.iter().any(|t| t.contains("frobnicate")));
+        assert!(!sig.tokens.iter().any(|t| t.contains("widget")));
+        assert!(!sig.tokens.iter().any(|t| t == "42"));
+    }
+
+    #[test]
+    fn test_exception_signature() {
+        let sig = ErrorSignature::from_message(
+            "java.lang.NullPointerException at com.example.Foo.bar(Foo.java:12) address 0x7ffe1234",
+        );
+        assert_eq!(sig.exception.as_deref(), Some("NullPointerException"));
+        assert!(!sig.tokens.iter().any(|t| t.contains("7ffe")));
+
+        let ts = ErrorSignature::from_message(
+            "src/app.ts(3,5): error TS2345: Argument of type 'string' is not assignable",
+        );
+        assert_eq!(ts.codes, vec!["TS2345"]);
+    }
+
+    #[test]
+    fn test_signature_similarity() {
+        let a = ErrorSignature::from_message(
+            "error[E0599]: no method named `foo` found for struct `A` in the current scope",
+        );
+        let b = ErrorSignature::from_message(
+            "error[E0599]: no method named `bar` found for struct `B` in the current scope\n --> src/lib.rs:1:1",
+        );
+        let c = ErrorSignature::from_message(
+            "error[E0308]: mismatched types, found struct in the current scope",
+        );
+
+        assert!(a.similarity(&b) > 0.9);
+        assert!(a.similarity(&c) < 0.3);
+        assert!(a.similarity(&b) > a.similarity(&c));
+    }
+}
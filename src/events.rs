@@ -0,0 +1,296 @@
+// Copyright 2024-2026 Andrey Vasilevsky <anvanster@gmail.com>
+// SPDX-License-Identifier: Apache-2.0
+
+#![allow(dead_code)]
+//! Retrieval event log.
+//!
+//! Every retrieval is recorded as an event with its own ID, the query, the
+//! project and session it came from, and the ranked results. Feedback is
+//! attached to an exact event instead of "whatever was retrieved last", so
+//! concurrent MCP servers and projects can't steal each other's feedback.
+//!
+//! Stored as append-only JSON lines in `~/.tempera/retrievals.jsonl` (see
+//! `jsonl.rs`), so old retrievals eventually rotate out.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::config::Config;
+use crate::episode::FeedbackAspect;
+use crate::jsonl::JsonlLog;
+
+/// Session ID for this process, set by long-running servers (one per MCP instance)
+static PROCESS_SESSION: OnceLock<String> = OnceLock::new();
+
+/// Start a process-wide retrieval session (call once at server startup)
+pub fn start_process_session() -> &'static str {
+    PROCESS_SESSION.get_or_init(|| uuid::Uuid::new_v4().to_string())
+}
+
+/// Current retrieval session: `TEMPERA_SESSION_ID` if set, else the process
+/// session if one was started
+pub fn current_session() -> Option<String> {
+    std::env::var("TEMPERA_SESSION_ID")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .or_else(|| PROCESS_SESSION.get().cloned())
+}
+
+/// Project name for the current working directory
+pub fn current_project() -> String {
+    std::env::current_dir()
+        .ok()
+        .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// One ranked result within a retrieval event
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RetrievedResult {
+    pub episode_id: String,
+    /// 1-based position in the returned list
+    pub rank: usize,
+    pub score: f32,
+}
+
+/// A single retrieval: query, where it came from, and what was returned
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetrievalEvent {
+    pub id: String,
+    pub timestamp: DateTime<Utc>,
+    pub query: String,
+    pub project: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    pub results: Vec<RetrievedResult>,
+}
+
+impl RetrievalEvent {
+    pub fn new(query: &str, project: &str, results: Vec<RetrievedResult>) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            query: query.to_string(),
+            project: project.to_string(),
+            session_id: current_session(),
+            results,
+        }
+    }
+
+    pub fn episode_ids(&self) -> Vec<String> {
+        self.results.iter().map(|r| r.episode_id.clone()).collect()
+    }
+}
+
+/// Feedback bound to a retrieval event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedbackEvent {
+    pub retrieval_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub helpful: Option<bool>,
+    pub episode_ids: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grade: Option<u8>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aspects: Vec<FeedbackAspect>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum LogRecord {
+    Retrieval(RetrievalEvent),
+    Feedback(FeedbackEvent),
+}
+
+/// Append-only retrieval event log
+pub struct EventLog {
+    log: JsonlLog,
+}
+
+impl EventLog {
+    pub fn new() -> Result<Self> {
+        Ok(Self::with_path(Config::retrieval_events_path()?))
+    }
+
+    pub fn with_path(path: PathBuf) -> Self {
+        Self {
+            log: JsonlLog::new(path),
+        }
+    }
+
+    /// Rotate at `max_bytes` instead of `jsonl::MAX_LOG_BYTES`
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.log = self.log.with_max_bytes(max_bytes);
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        self.log.path()
+    }
+
+    pub fn append(&self, record: &LogRecord) -> Result<()> {
+        self.log.append(record)
+    }
+
+    /// Read every record in both log generations, oldest first
+    pub fn records(&self) -> Result<Vec<LogRecord>> {
+        self.log.read()
+    }
+
+    pub fn retrievals(&self) -> Result<Vec<RetrievalEvent>> {
+        Ok(self
+            .records()?
+            .into_iter()
+            .filter_map(|r| match r {
+                LogRecord::Retrieval(e) => Some(e),
+                LogRecord::Feedback(_) => None,
+            })
+            .collect())
+    }
+
+    pub fn feedback(&self) -> Result<Vec<FeedbackEvent>> {
+        Ok(self
+            .records()?
+            .into_iter()
+            .filter_map(|r| match r {
+                LogRecord::Feedback(f) => Some(f),
+                LogRecord::Retrieval(_) => None,
+            })
+            .collect())
+    }
+
+    /// Find a retrieval by full ID or unique prefix
+    pub fn find(&self, id: &str) -> Result<Option<RetrievalEvent>> {
+        let matches: Vec<RetrievalEvent> = self
+            .retrievals()?
+            .into_iter()
+            .filter(|e| e.id.starts_with(id))
+            .collect();
+        if matches.len() > 1 {
+            anyhow::bail!("Retrieval ID '{}' is ambiguous", id);
+        }
+        Ok(matches.into_iter().next())
+    }
+
+    /// Most recent retrieval for a project, restricted to a session when given
+    pub fn last_for(
+        &self,
+        project: &str,
+        session_id: Option<&str>,
+    ) -> Result<Option<RetrievalEvent>> {
+        Ok(self
+            .retrievals()?
+            .into_iter()
+            .filter(|e| e.project.eq_ignore_ascii_case(project))
+            .filter(|e| session_id.is_none() || e.session_id.as_deref() == session_id)
+            .max_by_key(|e| e.timestamp))
+    }
+
+    /// Resolve "last" (for the current project/session) or an event ID
+    pub fn resolve(&self, id: &str) -> Result<RetrievalEvent> {
+        let event = if id.eq_ignore_ascii_case("last") {
+            let session = current_session();
+            self.last_for(&current_project(), session.as_deref())?
+        } else {
+            self.find(id)?
+        };
+        event.with_context(|| format!("No retrieval found for '{}'", id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn event(project: &str, session: Option<&str>, ids: &[&str]) -> RetrievalEvent {
+        let mut e = RetrievalEvent::new(
+            "query",
+            project,
+            ids.iter()
+                .enumerate()
+                .map(|(i, id)| RetrievedResult {
+                    episode_id: id.to_string(),
+                    rank: i + 1,
+                    score: 0.5,
+                })
+                .collect(),
+        );
+        e.session_id = session.map(String::from);
+        e
+    }
+
+    #[test]
+    fn test_append_and_find() {
+        let temp = TempDir::new().unwrap();
+        let log = EventLog::with_path(temp.path().join("retrievals.jsonl"));
+
+        let e1 = event("api", None, &["a1", "a2"]);
+        log.append(&LogRecord::Retrieval(e1.clone())).unwrap();
+        log.append(&LogRecord::Feedback(FeedbackEvent {
+            retrieval_id: e1.id.clone(),
+            timestamp: Utc::now(),
+            helpful: Some(true),
+            episode_ids: vec!["a1".to_string()],
+            grade: Some(3),
+            aspects: vec![],
+        }))
+        .unwrap();
+
+        let found = log.find(&e1.id[..8]).unwrap().unwrap();
+        assert_eq!(found.episode_ids(), vec!["a1", "a2"]);
+        assert_eq!(log.feedback().unwrap().len(), 1);
+        assert!(log.find("zzzzzzzz").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_last_for_project_and_session() {
+        let temp = TempDir::new().unwrap();
+        let log = EventLog::with_path(temp.path().join("retrievals.jsonl"));
+
+        let mut api_s1 = event("api", Some("s1"), &["a1"]);
+        api_s1.timestamp = Utc::now() - chrono::Duration::minutes(2);
+        let mut web = event("web", Some("s2"), &["w1"]);
+        web.timestamp = Utc::now() - chrono::Duration::minutes(1);
+        let api_s2 = event("api", Some("s2"), &["a2"]);
+        for e in [&api_s1, &web, &api_s2] {
+            log.append(&LogRecord::Retrieval(e.clone())).unwrap();
+        }
+
+        // Another project's later retrieval doesn't win
+        let last_api = log.last_for("api", None).unwrap().unwrap();
+        assert_eq!(last_api.id, api_s2.id);
+
+        // Session scoping picks the session's own retrieval
+        let last_s1 = log.last_for("api", Some("s1")).unwrap().unwrap();
+        assert_eq!(last_s1.id, api_s1.id);
+        assert!(log.last_for("api", Some("s3")).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_rotation_bounds_the_log() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("retrievals.jsonl");
+        let log = EventLog::with_path(path.clone()).with_max_bytes(1);
+
+        let events: Vec<RetrievalEvent> = (0..3)
+            .map(|i| event("api", None, &[&format!("e{}", i)]))
+            .collect();
+        for e in &events {
+            log.append(&LogRecord::Retrieval(e.clone())).unwrap();
+        }
+
+        // Each append rotated the one before; only the last two generations remain
+        let ids: Vec<String> = log
+            .retrievals()
+            .unwrap()
+            .into_iter()
+            .map(|e| e.id)
+            .collect();
+        assert_eq!(ids, vec![events[1].id.clone(), events[2].id.clone()]);
+        assert!(temp.path().join("retrievals.jsonl.1").exists());
+    }
+}
@@ -0,0 +1,202 @@
+// Copyright 2024-2026 Andrey Vasilevsky <anvanster@gmail.com>
+// SPDX-License-Identifier: Apache-2.0
+
+#![allow(dead_code)]
+//! Exploration for retrieval: Thompson sampling over the Beta utility posterior.
+//!
+//! Established episodes dominate the utility term, so new ones are rarely
+//! shown and never get the feedback that would tell us whether they're good.
+//! A configurable fraction of retrievals explores, either by ranking with a
+//! utility sampled from each episode's posterior (`thompson`) or by reserving
+//! one slot for a relevant but under-tested episode (`reserve`).
+
+use crate::config::Config;
+use crate::episode::Utility;
+
+/// Episodes with fewer (position-weighted) trials than this are under-tested
+pub const UNDER_TESTED_TRIALS: f64 = 3.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExplorationMode {
+    /// Rank with utility sampled from the posterior
+    Thompson,
+    /// Reserve one slot for a relevant, under-tested episode
+    Reserve,
+}
+
+impl ExplorationMode {
+    pub fn from_config(config: &Config) -> Self {
+        match config.retrieval.exploration_mode.to_lowercase().as_str() {
+            "reserve" | "slot" => Self::Reserve,
+            _ => Self::Thompson,
+        }
+    }
+
+    /// Decide whether this retrieval explores, per `exploration_rate`
+    pub fn roll(config: &Config, rng: &mut Rng) -> Option<Self> {
+        let rate = config.retrieval.exploration_rate;
+        if rate <= 0.0 || rng.next_f64() >= rate as f64 {
+            return None;
+        }
+        Some(Self::from_config(config))
+    }
+}
+
+/// Whether an episode has too little feedback to trust its utility
+pub fn is_under_tested(utility: &Utility) -> bool {
+    utility.trials() < UNDER_TESTED_TRIALS
+}
+
+/// Draw a utility as seen from `project`: a sample from the global Beta
+/// posterior blended with one from the project's, the way
+/// `Utility::project_score` blends the point estimates
+pub fn sample_utility(utility: &Utility, project: &str, prior: f32, rng: &mut Rng) -> f32 {
+    let (alpha, beta) = utility.posterior();
+    let global = sample_beta(alpha, beta, rng) as f32;
+    utility.blend_project(project, prior, global, |local| {
+        let (alpha, beta) = local.posterior();
+        sample_beta(alpha, beta, rng) as f32
+    })
+}
+
+/// Small SplitMix64 generator; exploration only needs cheap, unbiased draws
+pub struct Rng(u64);
+
+impl Rng {
+    /// Seeded from a random UUID
+    pub fn new() -> Self {
+        Self(uuid::Uuid::new_v4().as_u128() as u64)
+    }
+
+    pub fn seeded(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Standard normal (Box-Muller)
+    fn next_normal(&mut self) -> f64 {
+        let u1 = 1.0 - self.next_f64(); // (0, 1], safe for ln
+        let u2 = self.next_f64();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Gamma(shape, 1) via Marsaglia-Tsang
+fn sample_gamma(shape: f64, rng: &mut Rng) -> f64 {
+    if shape < 1.0 {
+        // Boost: Gamma(a) = Gamma(a + 1) * U^(1/a)
+        let u = 1.0 - rng.next_f64();
+        return sample_gamma(shape + 1.0, rng) * u.powf(1.0 / shape);
+    }
+
+    let d = shape - 1.0 / 3.0;
+    let c = 1.0 / (9.0 * d).sqrt();
+    loop {
+        let x = rng.next_normal();
+        let v = (1.0 + c * x).powi(3);
+        if v <= 0.0 {
+            continue;
+        }
+        let u = 1.0 - rng.next_f64();
+        if u.ln() < 0.5 * x * x + d - d * v + d * v.ln() {
+            return d * v;
+        }
+    }
+}
+
+/// Beta(alpha, beta) from two Gamma draws
+pub fn sample_beta(alpha: f64, beta: f64, rng: &mut Rng) -> f64 {
+    let x = sample_gamma(alpha, rng);
+    let y = sample_gamma(beta, rng);
+    if x + y == 0.0 { 0.5 } else { x / (x + y) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mean_of(alpha: f64, beta: f64) -> f64 {
+        let mut rng = Rng::seeded(42);
+        let n = 5000;
+        (0..n)
+            .map(|_| sample_beta(alpha, beta, &mut rng))
+            .sum::<f64>()
+            / n as f64
+    }
+
+    #[test]
+    fn test_beta_sample_means() {
+        assert!((mean_of(1.0, 1.0) - 0.5).abs() < 0.03);
+        assert!((mean_of(9.0, 1.0) - 0.9).abs() < 0.03);
+        assert!((mean_of(0.5, 2.0) - 0.2).abs() < 0.03);
+
+        let mut rng = Rng::seeded(7);
+        for _ in 0..1000 {
+            let x = sample_beta(2.0, 3.0, &mut rng);
+            assert!((0.0..=1.0).contains(&x));
+        }
+    }
+
+    #[test]
+    fn test_roll_respects_rate() {
+        let mut config = Config::default();
+        let mut rng = Rng::seeded(1);
+        assert_eq!(ExplorationMode::roll(&config, &mut rng), None);
+
+        config.retrieval.exploration_rate = 1.0;
+        assert_eq!(
+            ExplorationMode::roll(&config, &mut rng),
+            Some(ExplorationMode::Thompson)
+        );
+        config.retrieval.exploration_mode = "reserve".to_string();
+        assert_eq!(
+            ExplorationMode::roll(&config, &mut rng),
+            Some(ExplorationMode::Reserve)
+        );
+    }
+
+    #[test]
+    fn test_sample_includes_project_counts() {
+        // Helpful everywhere else, never in this project
+        let mut utility = Utility {
+            retrieval_count: 40,
+            helpful_count: 30,
+            ..Default::default()
+        };
+        utility.by_project.insert(
+            "api".to_string(),
+            crate::episode::ProjectUtility {
+                retrieval_count: 40,
+                ..Default::default()
+            },
+        );
+
+        let mean = |project: &str| {
+            let mut rng = Rng::seeded(3);
+            (0..2000)
+                .map(|_| sample_utility(&utility, project, 5.0, &mut rng))
+                .sum::<f32>()
+                / 2000.0
+        };
+        assert!(mean("web") > 0.6);
+        assert!(mean("api") < 0.2);
+    }
+}
@@ -3,30 +3,129 @@
 
 #![allow(dead_code)]
 
-use anyhow::Result;
+use anyhow::{Result, bail};
 use chrono::Utc;
 use std::io::{BufRead, Write};
 
 use crate::config::Config;
+use crate::episode::{Episode, FeedbackAspect, HELPFUL_GRADE, MAX_FEEDBACK_GRADE};
+use crate::events::{EventLog, FeedbackEvent, LogRecord, RetrievalEvent};
+use crate::ledger::{LedgerEntry, Mechanism, UtilityLedger};
 use crate::store::EpisodeStore;
 
+/// Feedback on retrieved episodes: a grade plus the aspects called out
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Feedback {
+    /// 0 (not helpful) to `MAX_FEEDBACK_GRADE` (helpful); None skips grading
+    pub grade: Option<u8>,
+    pub aspects: Vec<FeedbackAspect>,
+}
+
+impl Feedback {
+    /// Boolean feedback: full credit or none
+    pub fn from_helpful(helpful: bool) -> Self {
+        Self {
+            grade: Some(if helpful { MAX_FEEDBACK_GRADE } else { 0 }),
+            aspects: vec![],
+        }
+    }
+
+    /// Parse a feedback type: helpful (grade 3), mixed (1), not-helpful (0)
+    /// or skip (no grade)
+    pub fn parse(feedback_type: &str) -> Option<Self> {
+        let grade = match feedback_type.to_lowercase().as_str() {
+            "helpful" | "yes" | "y" | "1" | "good" => Some(MAX_FEEDBACK_GRADE),
+            "mixed" | "partial" => Some(1),
+            "not-helpful" | "unhelpful" | "no" | "n" | "0" | "bad" => Some(0),
+            "skip" => None,
+            _ => return None,
+        };
+        Some(Self {
+            grade,
+            aspects: vec![],
+        })
+    }
+
+    /// Yes/no view of the grade; None for mixed or ungraded feedback
+    pub fn was_helpful(&self) -> Option<bool> {
+        match self.grade {
+            Some(grade) if grade >= HELPFUL_GRADE => Some(true),
+            Some(0) => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Short description, e.g. "✅ helpful (3/3) [relevant, correct]"
+    pub fn describe(&self) -> String {
+        let mut text = match (self.was_helpful(), self.grade) {
+            (Some(true), _) => "✅ helpful".to_string(),
+            (Some(false), _) => "❌ not helpful".to_string(),
+            (None, Some(_)) => "➖ mixed".to_string(),
+            (None, None) => "➖ skipped".to_string(),
+        };
+        if let Some(grade) = self.grade {
+            text.push_str(&format!(" ({}/{})", grade, MAX_FEEDBACK_GRADE));
+        }
+        if !self.aspects.is_empty() {
+            let aspects: Vec<String> = self.aspects.iter().map(|a| a.to_string()).collect();
+            text.push_str(&format!(" [{}]", aspects.join(", ")));
+        }
+        text
+    }
+}
+
+/// Parse a comma-separated aspect list, e.g. "relevant,outdated"
+pub fn parse_aspects(aspects: &str) -> Result<Vec<FeedbackAspect>> {
+    let mut parsed = Vec::new();
+    for name in aspects.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        match FeedbackAspect::parse(name) {
+            Some(aspect) if !parsed.contains(&aspect) => parsed.push(aspect),
+            Some(_) => {}
+            None => bail!(
+                "Unknown aspect: {}. Use relevant, correct, outdated or too-vague.",
+                name
+            ),
+        }
+    }
+    Ok(parsed)
+}
+
 /// Run the feedback command
-pub async fn run(feedback_type: &str, episodes: Option<String>, _config: &Config) -> Result<()> {
+pub async fn run(
+    feedback_type: Option<String>,
+    grade: Option<u8>,
+    aspects: Option<String>,
+    episodes: Option<String>,
+    retrieval: Option<String>,
+    _config: &Config,
+) -> Result<()> {
     let store = EpisodeStore::new()?;
+    let log = EventLog::new()?;
+
+    let wants_last = episodes
+        .as_deref()
+        .is_some_and(|ids| ids.eq_ignore_ascii_case("last"));
+
+    // Find the retrieval event this feedback belongs to
+    let event = match &retrieval {
+        Some(id) => Some(log.resolve(id)?),
+        None if wants_last => log.resolve("last").ok(),
+        None => None,
+    };
 
     // Determine which episodes to provide feedback for
-    let episode_ids = match episodes {
-        Some(ids) if ids.to_lowercase() == "last" => {
-            // Get IDs from last retrieval in feedback log
+    let episode_ids = match (episodes, &event) {
+        (Some(ids), Some(event)) if !wants_last => select_event_episodes(event, &parse_ids(&ids))?,
+        (_, Some(event)) => event.episode_ids(),
+        (Some(_), None) if wants_last => {
+            // No event for this project/session: fall back to the legacy feedback log
             get_last_retrieved_ids()?
         }
-        Some(ids) => {
-            // Parse comma-separated IDs
-            ids.split(',').map(|s| s.trim().to_string()).collect()
-        }
-        None => {
-            // Interactive: show recent episodes and let user select
-            println!("No episodes specified. Use --episodes <id1,id2,...> or --episodes last");
+        (Some(ids), None) => parse_ids(&ids),
+        (None, None) => {
+            println!(
+                "No episodes specified. Use --retrieval <id|last>, --episodes <id1,id2,...> or --episodes last"
+            );
             return Ok(());
         }
     };
@@ -36,36 +135,51 @@ pub async fn run(feedback_type: &str, episodes: Option<String>, _config: &Config
         return Ok(());
     }
 
-    // Parse feedback type
-    let is_helpful = match feedback_type.to_lowercase().as_str() {
-        "helpful" | "yes" | "y" | "1" | "good" => Some(true),
-        "not-helpful" | "unhelpful" | "no" | "n" | "0" | "bad" => Some(false),
-        "mixed" | "partial" | "skip" => None,
-        _ => {
-            println!(
-                "Unknown feedback type: {}. Use 'helpful', 'not-helpful', or 'mixed'.",
-                feedback_type
-            );
+    // Parse feedback type; an explicit grade takes precedence
+    let mut feedback = match (grade, feedback_type.as_deref()) {
+        (Some(grade), _) => Feedback {
+            grade: Some(grade.min(MAX_FEEDBACK_GRADE)),
+            aspects: vec![],
+        },
+        (None, Some(feedback_type)) => match Feedback::parse(feedback_type) {
+            Some(feedback) => feedback,
+            None => {
+                println!(
+                    "Unknown feedback type: {}. Use 'helpful', 'not-helpful', 'mixed' or --grade 0-3.",
+                    feedback_type
+                );
+                return Ok(());
+            }
+        },
+        (None, None) => {
+            println!("No feedback given. Pass 'helpful', 'not-helpful', 'mixed' or --grade 0-3.");
             return Ok(());
         }
     };
+    if let Some(aspects) = aspects {
+        feedback.aspects = parse_aspects(&aspects)?;
+    }
 
-    println!(
-        "📝 Recording feedback for {} episode(s)...",
-        episode_ids.len()
-    );
+    match &event {
+        Some(event) => println!(
+            "📝 Recording feedback for {} episode(s) from retrieval {} (\"{}\")...",
+            episode_ids.len(),
+            &event.id[..8],
+            event.query
+        ),
+        None => println!(
+            "📝 Recording feedback for {} episode(s)...",
+            episode_ids.len()
+        ),
+    }
 
+    let retrieval_id = event.as_ref().map(|e| e.id.as_str());
     let mut updated = 0;
     for id in &episode_ids {
-        match update_episode_feedback(&store, id, is_helpful) {
+        match update_episode_feedback(&store, id, &feedback, retrieval_id) {
             Ok(_) => {
                 updated += 1;
-                let feedback_str = match is_helpful {
-                    Some(true) => "✅ helpful",
-                    Some(false) => "❌ not helpful",
-                    None => "➖ mixed/skipped",
-                };
-                println!("  {} -> {}", &id[..8.min(id.len())], feedback_str);
+                println!("  {} -> {}", &id[..8.min(id.len())], feedback.describe());
             }
             Err(e) => {
                 println!("  {} -> ⚠️ failed: {}", &id[..8.min(id.len())], e);
@@ -76,30 +190,132 @@ pub async fn run(feedback_type: &str, episodes: Option<String>, _config: &Config
     println!("\n✅ Updated {} episode(s)", updated);
 
     // Log the feedback
-    log_feedback(&episode_ids, is_helpful)?;
+    record_feedback(&log, &episode_ids, &feedback, event.as_ref())?;
 
     Ok(())
 }
 
-/// Update episode with feedback
-fn update_episode_feedback(store: &EpisodeStore, id: &str, is_helpful: Option<bool>) -> Result<()> {
-    let mut episode = store.load(id)?;
+fn parse_ids(ids: &str) -> Vec<String> {
+    ids.split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
 
-    // Update the most recent retrieval record
-    if let Some(last_retrieval) = episode.retrieval_history.last_mut() {
-        last_retrieval.was_helpful = is_helpful;
+/// Restrict a retrieval's episodes to the given IDs (full or prefix). IDs
+/// that weren't part of the retrieval are an error rather than being
+/// silently skipped.
+pub fn select_event_episodes(event: &RetrievalEvent, ids: &[String]) -> Result<Vec<String>> {
+    let retrieved = event.episode_ids();
+    let missing: Vec<&str> = ids
+        .iter()
+        .filter(|id| !retrieved.iter().any(|full| full.starts_with(id.as_str())))
+        .map(|id| id.as_str())
+        .collect();
+    if !missing.is_empty() {
+        bail!(
+            "Episode(s) {} not part of retrieval {}",
+            missing.join(", "),
+            &event.id[..8.min(event.id.len())]
+        );
     }
 
-    // Update utility counts
-    if let Some(true) = is_helpful {
-        episode.utility.helpful_count += 1;
+    Ok(retrieved
+        .into_iter()
+        .filter(|full| ids.iter().any(|id| full.starts_with(id.as_str())))
+        .collect())
+}
+
+/// Update episode with feedback, attached to the given retrieval when known
+pub fn update_episode_feedback(
+    store: &EpisodeStore,
+    id: &str,
+    feedback: &Feedback,
+    retrieval_id: Option<&str>,
+) -> Result<()> {
+    let mut episode = store.load(id)?;
+
+    let project = mark_retrieval(&mut episode, feedback, retrieval_id);
+
+    // Update utility counts with (fractional) credit, globally and for the
+    // project that retrieved the episode
+    if let Some(grade) = feedback.grade {
+        episode.utility.record_grade(grade);
+        if let Some(project) = project {
+            episode.utility.record_project_grade(&project, grade);
+        }
     }
 
     // Recalculate utility score
-    episode.utility.score = Some(episode.utility.calculate_score());
+    let old_score = episode
+        .utility
+        .score
+        .unwrap_or_else(|| episode.utility.calculate_score());
+    let score = episode.utility.calculate_score();
+    episode.utility.set_score(score, Utc::now());
 
     // Save updated episode
     store.update(&episode)?;
+    UtilityLedger::new()?.append(&LedgerEntry::new(
+        &episode.id,
+        Mechanism::Feedback,
+        old_score,
+        score,
+    ))?;
+
+    Ok(())
+}
+
+/// Store feedback on the retrieval record for `retrieval_id`, or on the most
+/// recent record for unbound feedback and records written before retrievals
+/// had IDs. Implicit credit inferred for the record is retracted. Returns
+/// the project the marked retrieval was made in.
+fn mark_retrieval(
+    episode: &mut Episode,
+    feedback: &Feedback,
+    retrieval_id: Option<&str>,
+) -> Option<String> {
+    let history = &mut episode.retrieval_history;
+    let index = retrieval_id
+        .and_then(|rid| {
+            history
+                .iter()
+                .rposition(|r| r.retrieval_id.as_deref() == Some(rid))
+        })
+        .or_else(|| history.len().checked_sub(1));
+
+    let record = &mut history[index?];
+    record.was_helpful = feedback.was_helpful();
+    record.grade = feedback.grade;
+    record.aspects = feedback.aspects.clone();
+    let project = record.project.clone();
+
+    // Explicit feedback supersedes whatever was inferred for this retrieval
+    if let Some(implicit) = record.implicit.take() {
+        episode.utility.retract_implicit(implicit.credit);
+    }
+    Some(project)
+}
+
+/// Write feedback to feedback.log and, when bound, to the retrieval event log
+pub fn record_feedback(
+    log: &EventLog,
+    episode_ids: &[String],
+    feedback: &Feedback,
+    retrieval: Option<&RetrievalEvent>,
+) -> Result<()> {
+    log_feedback(episode_ids, feedback)?;
+
+    if let Some(event) = retrieval {
+        log.append(&LogRecord::Feedback(FeedbackEvent {
+            retrieval_id: event.id.clone(),
+            timestamp: Utc::now(),
+            helpful: feedback.was_helpful(),
+            episode_ids: episode_ids.to_vec(),
+            grade: feedback.grade,
+            aspects: feedback.aspects.clone(),
+        }))?;
+    }
 
     Ok(())
 }
@@ -138,14 +354,26 @@ fn get_last_retrieved_ids() -> Result<Vec<String>> {
 }
 
 /// Log feedback to feedback.log
-fn log_feedback(episode_ids: &[String], is_helpful: Option<bool>) -> Result<()> {
+fn log_feedback(episode_ids: &[String], feedback: &Feedback) -> Result<()> {
     let feedback_log = Config::feedback_log_path()?;
 
-    let feedback_str = match is_helpful {
+    let mut feedback_str = match feedback.was_helpful() {
         Some(true) => "helpful",
         Some(false) => "not-helpful",
         None => "mixed",
-    };
+    }
+    .to_string();
+    if let Some(grade) = feedback.grade {
+        feedback_str.push_str(&format!("\tgrade:{}", grade));
+    }
+    if !feedback.aspects.is_empty() {
+        let aspects: Vec<String> = feedback
+            .aspects
+            .iter()
+            .map(|a| a.to_string().replace(' ', "-"))
+            .collect();
+        feedback_str.push_str(&format!("\taspects:{}", aspects.join(",")));
+    }
 
     let log_entry = format!(
         "{}\tfeedback:{}\tids:{}\n",
@@ -169,15 +397,16 @@ pub fn batch_feedback(
     episode_ids: &[String],
     is_helpful: bool,
 ) -> Result<usize> {
+    let feedback = Feedback::from_helpful(is_helpful);
     let mut updated = 0;
 
     for id in episode_ids {
-        if update_episode_feedback(store, id, Some(is_helpful)).is_ok() {
+        if update_episode_feedback(store, id, &feedback, None).is_ok() {
             updated += 1;
         }
     }
 
-    log_feedback(episode_ids, Some(is_helpful))?;
+    log_feedback(episode_ids, &feedback)?;
 
     Ok(updated)
 }
@@ -200,13 +429,102 @@ mod tests {
         ];
 
         for (input, expected) in test_cases {
-            let result = match input.to_lowercase().as_str() {
-                "helpful" | "yes" | "y" | "1" | "good" => Some(true),
-                "not-helpful" | "unhelpful" | "no" | "n" | "0" | "bad" => Some(false),
-                "mixed" | "partial" | "skip" => None,
-                _ => None,
-            };
+            let result = Feedback::parse(input).unwrap().was_helpful();
             assert_eq!(result, expected, "Failed for input: {}", input);
         }
+
+        // Mixed earns partial credit, skip earns none
+        assert_eq!(Feedback::parse("mixed").unwrap().grade, Some(1));
+        assert_eq!(Feedback::parse("skip").unwrap().grade, None);
+        assert_eq!(Feedback::parse("maybe"), None);
+    }
+
+    #[test]
+    fn test_parse_aspects() {
+        assert_eq!(
+            parse_aspects("relevant, too-vague,relevant").unwrap(),
+            vec![FeedbackAspect::Relevant, FeedbackAspect::TooVague]
+        );
+        assert!(parse_aspects("relevant,shiny").is_err());
+
+        let feedback = Feedback {
+            grade: Some(2),
+            aspects: vec![FeedbackAspect::Outdated],
+        };
+        assert_eq!(feedback.was_helpful(), Some(true));
+        assert_eq!(feedback.describe(), "✅ helpful (2/3) [outdated]");
+    }
+
+    fn record(retrieval_id: Option<&str>) -> crate::episode::RetrievalRecord {
+        crate::episode::RetrievalRecord {
+            timestamp: Utc::now(),
+            project: "api".to_string(),
+            task_description: "query".to_string(),
+            was_helpful: None,
+            retrieval_id: retrieval_id.map(String::from),
+            session_id: None,
+            rank: None,
+            grade: None,
+            aspects: vec![],
+            implicit: None,
+        }
+    }
+
+    #[test]
+    fn test_feedback_binds_to_retrieval() {
+        let mut ep = Episode::new("api".to_string(), "fix timeout".to_string());
+        ep.retrieval_history = vec![record(Some("r1")), record(Some("r2")), record(None)];
+
+        // Bound feedback lands on its own retrieval, not the latest one
+        let project = mark_retrieval(&mut ep, &Feedback::from_helpful(true), Some("r1"));
+        assert_eq!(project.as_deref(), Some("api"));
+        assert_eq!(ep.retrieval_history[0].was_helpful, Some(true));
+        assert_eq!(ep.retrieval_history[0].grade, Some(MAX_FEEDBACK_GRADE));
+        assert_eq!(ep.retrieval_history[2].was_helpful, None);
+
+        // Unbound or unknown retrievals fall back to the most recent record
+        mark_retrieval(&mut ep, &Feedback::from_helpful(false), Some("unknown"));
+        assert_eq!(ep.retrieval_history[2].was_helpful, Some(false));
+        assert_eq!(ep.retrieval_history[1].was_helpful, None);
+
+        // Explicit feedback replaces credit inferred for the same retrieval
+        ep.retrieval_history[1].implicit = Some(crate::episode::ImplicitFeedback {
+            credit: 0.2,
+            signals: vec![crate::episode::ImplicitSignal::Files],
+            episode_id: "later".to_string(),
+        });
+        ep.utility.record_implicit(0.2);
+        mark_retrieval(&mut ep, &Feedback::from_helpful(false), Some("r2"));
+        assert!(ep.retrieval_history[1].implicit.is_none());
+        assert_eq!(ep.utility.implicit_credit, Some(0.0));
+    }
+
+    #[test]
+    fn test_select_event_episodes() {
+        use crate::events::RetrievedResult;
+
+        let event = RetrievalEvent::new(
+            "query",
+            "api",
+            ["aaaa1111-x", "bbbb2222-y"]
+                .iter()
+                .enumerate()
+                .map(|(i, id)| RetrievedResult {
+                    episode_id: id.to_string(),
+                    rank: i + 1,
+                    score: 0.5,
+                })
+                .collect(),
+        );
+
+        let ids = select_event_episodes(&event, &["bbbb2222".to_string()]).unwrap();
+        assert_eq!(ids, vec!["bbbb2222-y"]);
+
+        // IDs outside the retrieval are named in the error
+        let err = select_event_episodes(&event, &["bbbb2222".to_string(), "cccc".to_string()])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("cccc"));
+        assert!(!err.contains("bbbb2222"));
     }
 }
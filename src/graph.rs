@@ -0,0 +1,191 @@
+// Copyright 2024-2026 Andrey Vasilevsky <anvanster@gmail.com>
+// SPDX-License-Identifier: Apache-2.0
+
+#![allow(dead_code)]
+//! Episode graph for retrieval ranking.
+//!
+//! Single-hop similarity misses episodes that matter because of what they're
+//! connected to: the prerequisite of a top hit rarely matches the query
+//! itself. Retrieval builds a small graph over the candidates and the
+//! episodes they link to, with edges from similarity, shared sessions and
+//! explicit `related_episodes` links (weighted per relation type, see
+//! `RelationWeights`), then runs personalized PageRank seeded from the top
+//! hits. The normalized visit rate is the graph score in `combined_score`.
+
+use std::collections::HashMap;
+
+use crate::config::RelationWeights;
+use crate::episode::Episode;
+
+/// Probability of following an edge rather than jumping back to a seed
+pub const DAMPING: f32 = 0.85;
+
+const MAX_ITERATIONS: usize = 50;
+const TOLERANCE: f32 = 1e-6;
+
+/// Undirected weighted graph over episodes
+#[derive(Debug, Default)]
+pub struct EpisodeGraph {
+    ids: Vec<String>,
+    index: HashMap<String, usize>,
+    edges: Vec<HashMap<usize, f32>>,
+}
+
+impl EpisodeGraph {
+    /// Nodes for `episodes`, with session and explicit-link edges between
+    /// them. Links with a non-positive weight (competitive relations) are
+    /// left out: they shouldn't pull activation toward each other.
+    pub fn new(episodes: &[&Episode], weights: &RelationWeights) -> Self {
+        let mut graph = Self::default();
+        for ep in episodes {
+            if !graph.index.contains_key(&ep.id) {
+                graph.index.insert(ep.id.clone(), graph.ids.len());
+                graph.ids.push(ep.id.clone());
+                graph.edges.push(HashMap::new());
+            }
+        }
+
+        let mut sessions: HashMap<&str, Vec<&str>> = HashMap::new();
+        for ep in episodes {
+            for rel in &ep.related_episodes {
+                graph.add_edge(&ep.id, &rel.id, weights.weight(rel.relationship));
+            }
+            if let Some(sid) = &ep.session_id {
+                sessions.entry(sid).or_default().push(&ep.id);
+            }
+        }
+        for members in sessions.values() {
+            for (i, a) in members.iter().enumerate() {
+                for b in &members[i + 1..] {
+                    graph.add_edge(a, b, weights.session);
+                }
+            }
+        }
+
+        graph
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Add (or strengthen) an undirected edge; unknown nodes, self-loops and
+    /// non-positive weights are ignored
+    pub fn add_edge(&mut self, a: &str, b: &str, weight: f32) {
+        if weight <= 0.0 {
+            return;
+        }
+        let (Some(&i), Some(&j)) = (self.index.get(a), self.index.get(b)) else {
+            return;
+        };
+        if i == j {
+            return;
+        }
+        for (from, to) in [(i, j), (j, i)] {
+            let w = self.edges[from].entry(to).or_insert(0.0);
+            *w = w.max(weight);
+        }
+    }
+
+    /// Personalized PageRank from weighted seeds. Scores are scaled so the
+    /// best-connected node gets 1.0; nodes the walk never reaches get 0.0.
+    pub fn personalized_pagerank(&self, seeds: &[(&str, f32)]) -> HashMap<String, f32> {
+        let n = self.ids.len();
+        let mut restart = vec![0.0_f32; n];
+        for (id, weight) in seeds {
+            if let Some(&i) = self.index.get(*id) {
+                restart[i] += weight.max(0.0);
+            }
+        }
+        let total: f32 = restart.iter().sum();
+        if total == 0.0 {
+            return HashMap::new();
+        }
+        restart.iter_mut().for_each(|r| *r /= total);
+
+        let out_weight: Vec<f32> = self.edges.iter().map(|e| e.values().sum()).collect();
+        let mut rank = restart.clone();
+        for _ in 0..MAX_ITERATIONS {
+            // Dangling nodes hand their mass back to the seeds
+            let dangling: f32 = (0..n)
+                .filter(|&i| out_weight[i] == 0.0)
+                .map(|i| rank[i])
+                .sum();
+            let mut next: Vec<f32> = restart
+                .iter()
+                .map(|r| (1.0 - DAMPING + DAMPING * dangling) * r)
+                .collect();
+            for (i, edges) in self.edges.iter().enumerate() {
+                if out_weight[i] == 0.0 {
+                    continue;
+                }
+                for (&j, &w) in edges {
+                    next[j] += DAMPING * rank[i] * w / out_weight[i];
+                }
+            }
+
+            let delta: f32 = next.iter().zip(&rank).map(|(a, b)| (a - b).abs()).sum();
+            rank = next;
+            if delta < TOLERANCE {
+                break;
+            }
+        }
+
+        let max = rank.iter().cloned().fold(0.0_f32, f32::max);
+        self.ids
+            .iter()
+            .zip(rank)
+            .map(|(id, r)| (id.clone(), if max > 0.0 { r / max } else { 0.0 }))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::episode::EpisodeRelation;
+
+    fn episode(prompt: &str) -> Episode {
+        Episode::new("api".to_string(), prompt.to_string())
+    }
+
+    #[test]
+    fn test_pagerank_follows_links() {
+        let mut parent = episode("deploy service");
+        let prereq = episode("configure registry credentials");
+        let unrelated = episode("rename variable");
+        parent.add_relation(&prereq.id, EpisodeRelation::Prerequisite);
+
+        let graph = EpisodeGraph::new(&[&parent, &prereq, &unrelated], &RelationWeights::default());
+        let scores = graph.personalized_pagerank(&[(parent.id.as_str(), 1.0)]);
+
+        assert_eq!(scores[&parent.id], 1.0);
+        assert!(scores[&prereq.id] > 0.3);
+        assert_eq!(scores[&unrelated.id], 0.0);
+    }
+
+    #[test]
+    fn test_competitive_links_and_sessions() {
+        let mut a = episode("retry with backoff");
+        let mut b = episode("circuit breaker");
+        let mut c = episode("investigate timeout");
+        a.add_relation(&b.id, EpisodeRelation::Alternative);
+        a.session_id = Some("s1".to_string());
+        c.session_id = Some("s1".to_string());
+        b.session_id = Some("s2".to_string());
+
+        let graph = EpisodeGraph::new(&[&a, &b, &c], &RelationWeights::default());
+        let scores = graph.personalized_pagerank(&[(a.id.as_str(), 1.0)]);
+
+        // Alternatives don't spread activation; session members do
+        assert_eq!(scores[&b.id], 0.0);
+        assert!(scores[&c.id] > 0.0);
+
+        // No reachable seed, no scores
+        assert!(graph.personalized_pagerank(&[("missing", 1.0)]).is_empty());
+    }
+}
@@ -0,0 +1,424 @@
+// Copyright 2024-2026 Andrey Vasilevsky <anvanster@gmail.com>
+// SPDX-License-Identifier: Apache-2.0
+
+#![allow(dead_code)]
+//! Export the episode graph for Graphviz or Gephi.
+//!
+//! Nodes are episodes with their utility, task type and outcome. Edges are
+//! the three ways value flows between them: embedding similarity above
+//! `propagation_threshold` (what Bellman propagation follows), consecutive
+//! episodes in a session, and explicit `related_episodes` links weighted per
+//! relation type. Each node carries its degree so isolated episodes and
+//! over-connected clusters stand out.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::config::{Config, RelationWeights};
+use crate::episode::Episode;
+use crate::indexer::EpisodeIndexer;
+use crate::retrieve::{cosine_similarity, text_overlap_similarity};
+use crate::store::EpisodeStore;
+
+/// Longest node label before truncation
+const MAX_LABEL_CHARS: usize = 60;
+
+/// Output format for the export
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Dot,
+    GraphMl,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "dot" | "gv" => Ok(ExportFormat::Dot),
+            "graphml" => Ok(ExportFormat::GraphMl),
+            "json" => Ok(ExportFormat::Json),
+            _ => anyhow::bail!("Unknown format '{}' (expected dot, graphml or json)", s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportNode {
+    pub id: String,
+    pub label: String,
+    pub project: String,
+    pub task_type: String,
+    pub outcome: String,
+    pub utility: f32,
+    pub degree: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportEdge {
+    pub source: String,
+    pub target: String,
+    /// similarity, session, or the relation type of an explicit link
+    pub kind: String,
+    pub weight: f32,
+    pub directed: bool,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct GraphExport {
+    pub nodes: Vec<ExportNode>,
+    pub edges: Vec<ExportEdge>,
+}
+
+/// Run the graph command
+pub async fn run(
+    project: Option<String>,
+    format: &str,
+    output: Option<String>,
+    config: &Config,
+) -> Result<()> {
+    let format = ExportFormat::parse(format)?;
+    let store = EpisodeStore::new()?;
+
+    let mut episodes = store.list_all()?;
+    if let Some(proj) = &project {
+        episodes.retain(|e| e.project.to_lowercase().contains(&proj.to_lowercase()));
+    }
+    episodes.sort_by_key(|e| e.timestamp_start);
+
+    let similarities = similarity_pairs(&episodes, config.bellman.propagation_threshold).await;
+    let graph = GraphExport::build(&episodes, &similarities, &config.bellman.relation_weights);
+    let rendered = graph.render(format)?;
+
+    match output {
+        Some(path) => {
+            std::fs::write(&path, rendered).with_context(|| format!("Failed to write {}", path))?;
+            let isolated = graph.nodes.iter().filter(|n| n.degree == 0).count();
+            eprintln!(
+                "Wrote {} nodes and {} edges to {} ({} isolated)",
+                graph.nodes.len(),
+                graph.edges.len(),
+                path,
+                isolated
+            );
+        }
+        None => println!("{}", rendered),
+    }
+    Ok(())
+}
+
+/// Pairs of episodes (by index) whose similarity reaches `threshold`.
+/// Uses embeddings when the model is available, word overlap otherwise.
+async fn similarity_pairs(episodes: &[Episode], threshold: f32) -> Vec<(usize, usize, f32)> {
+    let embeddings = match embed_all(episodes).await {
+        Ok(embeddings) => Some(embeddings),
+        Err(e) => {
+            eprintln!(
+                "Embeddings unavailable ({}), using word overlap for similarity edges",
+                e
+            );
+            None
+        }
+    };
+
+    let mut pairs = Vec::new();
+    for i in 0..episodes.len() {
+        for j in i + 1..episodes.len() {
+            let similarity = match &embeddings {
+                Some(vectors) => cosine_similarity(&vectors[i], &vectors[j]),
+                None => text_overlap_similarity(&episodes[i], &episodes[j]),
+            };
+            if similarity >= threshold {
+                pairs.push((i, j, similarity));
+            }
+        }
+    }
+    pairs
+}
+
+async fn embed_all(episodes: &[Episode]) -> Result<Vec<Vec<f32>>> {
+    if episodes.is_empty() {
+        return Ok(Vec::new());
+    }
+    let indexer = EpisodeIndexer::new().await?;
+    episodes
+        .iter()
+        .map(|ep| indexer.embed(&EpisodeIndexer::episode_to_embedding_text(ep)))
+        .collect()
+}
+
+impl GraphExport {
+    /// Build the export from episodes sorted by start time and precomputed
+    /// similarity pairs (indices into `episodes`)
+    pub fn build(
+        episodes: &[Episode],
+        similarities: &[(usize, usize, f32)],
+        weights: &RelationWeights,
+    ) -> Self {
+        let ids: HashSet<&str> = episodes.iter().map(|e| e.id.as_str()).collect();
+        let mut edges = Vec::new();
+
+        for &(i, j, similarity) in similarities {
+            edges.push(ExportEdge {
+                source: episodes[i].id.clone(),
+                target: episodes[j].id.clone(),
+                kind: "similarity".to_string(),
+                weight: similarity,
+                directed: false,
+            });
+        }
+
+        // Consecutive episodes in a session rather than every pair, so long
+        // sessions read as chains instead of hairballs
+        let mut last_in_session: HashMap<&str, &str> = HashMap::new();
+        for ep in episodes {
+            if let Some(sid) = &ep.session_id {
+                if let Some(prev) = last_in_session.insert(sid, &ep.id) {
+                    edges.push(ExportEdge {
+                        source: prev.to_string(),
+                        target: ep.id.clone(),
+                        kind: "session".to_string(),
+                        weight: weights.session,
+                        directed: false,
+                    });
+                }
+            }
+        }
+
+        // Symmetric links are stored on both episodes; export them once
+        let mut seen_links: HashSet<(&str, &str)> = HashSet::new();
+        for ep in episodes {
+            for rel in &ep.related_episodes {
+                if !ids.contains(rel.id.as_str()) {
+                    continue;
+                }
+                let symmetric = rel.relationship.is_symmetric();
+                if symmetric {
+                    let key = if ep.id < rel.id {
+                        (ep.id.as_str(), rel.id.as_str())
+                    } else {
+                        (rel.id.as_str(), ep.id.as_str())
+                    };
+                    if !seen_links.insert(key) {
+                        continue;
+                    }
+                }
+                edges.push(ExportEdge {
+                    source: ep.id.clone(),
+                    target: rel.id.clone(),
+                    kind: rel.relationship.to_string(),
+                    weight: weights.weight(rel.relationship),
+                    directed: !symmetric,
+                });
+            }
+        }
+
+        let mut degree: HashMap<&str, usize> = HashMap::new();
+        for edge in &edges {
+            *degree.entry(edge.source.as_str()).or_default() += 1;
+            *degree.entry(edge.target.as_str()).or_default() += 1;
+        }
+
+        let nodes = episodes
+            .iter()
+            .map(|ep| ExportNode {
+                id: ep.id.clone(),
+                label: label(ep),
+                project: ep.project.clone(),
+                task_type: ep.intent.task_type.to_string(),
+                outcome: ep.outcome.status.to_string(),
+                utility: ep.utility.calculate_score(),
+                degree: degree.get(ep.id.as_str()).copied().unwrap_or(0),
+            })
+            .collect();
+
+        Self { nodes, edges }
+    }
+
+    pub fn render(&self, format: ExportFormat) -> Result<String> {
+        Ok(match format {
+            ExportFormat::Dot => self.to_dot(),
+            ExportFormat::GraphMl => self.to_graphml(),
+            ExportFormat::Json => serde_json::to_string_pretty(self)?,
+        })
+    }
+
+    /// Graphviz DOT. The graph is undirected; directed links get an arrowhead.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("graph tempera {\n  node [shape=box, style=filled];\n");
+        for node in &self.nodes {
+            out.push_str(&format!(
+                "  \"{}\" [label=\"{}\", project=\"{}\", task_type=\"{}\", outcome=\"{}\", utility={:.3}, degree={}, fillcolor=\"{}\"];\n",
+                dot_escape(&node.id),
+                dot_escape(&node.label),
+                dot_escape(&node.project),
+                node.task_type,
+                node.outcome,
+                node.utility,
+                node.degree,
+                utility_color(node.utility)
+            ));
+        }
+        for edge in &self.edges {
+            let style = match edge.kind.as_str() {
+                "similarity" => "dashed",
+                "session" => "dotted",
+                _ => "solid",
+            };
+            out.push_str(&format!(
+                "  \"{}\" -- \"{}\" [kind=\"{}\", weight={:.3}, style={}{}];\n",
+                dot_escape(&edge.source),
+                dot_escape(&edge.target),
+                edge.kind,
+                edge.weight,
+                style,
+                if edge.directed { ", dir=forward" } else { "" }
+            ));
+        }
+        out.push('}');
+        out
+    }
+
+    /// GraphML with typed node and edge attributes (loads directly in Gephi)
+    pub fn to_graphml(&self) -> String {
+        let mut out = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+            "  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n",
+            "  <key id=\"project\" for=\"node\" attr.name=\"project\" attr.type=\"string\"/>\n",
+            "  <key id=\"task_type\" for=\"node\" attr.name=\"task_type\" attr.type=\"string\"/>\n",
+            "  <key id=\"outcome\" for=\"node\" attr.name=\"outcome\" attr.type=\"string\"/>\n",
+            "  <key id=\"utility\" for=\"node\" attr.name=\"utility\" attr.type=\"double\"/>\n",
+            "  <key id=\"degree\" for=\"node\" attr.name=\"degree\" attr.type=\"int\"/>\n",
+            "  <key id=\"kind\" for=\"edge\" attr.name=\"kind\" attr.type=\"string\"/>\n",
+            "  <key id=\"weight\" for=\"edge\" attr.name=\"weight\" attr.type=\"double\"/>\n",
+            "  <graph id=\"tempera\" edgedefault=\"undirected\">\n",
+        ));
+        for node in &self.nodes {
+            out.push_str(&format!(
+                "    <node id=\"{}\">\n      <data key=\"label\">{}</data>\n      <data key=\"project\">{}</data>\n      <data key=\"task_type\">{}</data>\n      <data key=\"outcome\">{}</data>\n      <data key=\"utility\">{:.3}</data>\n      <data key=\"degree\">{}</data>\n    </node>\n",
+                xml_escape(&node.id),
+                xml_escape(&node.label),
+                xml_escape(&node.project),
+                node.task_type,
+                node.outcome,
+                node.utility,
+                node.degree
+            ));
+        }
+        for edge in &self.edges {
+            out.push_str(&format!(
+                "    <edge source=\"{}\" target=\"{}\" directed=\"{}\">\n      <data key=\"kind\">{}</data>\n      <data key=\"weight\">{:.3}</data>\n    </edge>\n",
+                xml_escape(&edge.source),
+                xml_escape(&edge.target),
+                edge.directed,
+                edge.kind,
+                edge.weight
+            ));
+        }
+        out.push_str("  </graph>\n</graphml>");
+        out
+    }
+}
+
+fn label(ep: &Episode) -> String {
+    let text = if ep.intent.extracted_intent.is_empty() {
+        &ep.intent.raw_prompt
+    } else {
+        &ep.intent.extracted_intent
+    };
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() > MAX_LABEL_CHARS {
+        let truncated: String = text.chars().take(MAX_LABEL_CHARS - 3).collect();
+        format!("{}...", truncated)
+    } else {
+        text
+    }
+}
+
+/// Red (low utility) to green (high utility)
+fn utility_color(utility: f32) -> String {
+    let u = utility.clamp(0.0, 1.0);
+    let red = (255.0 * (1.0 - u)) as u8;
+    let green = (200.0 * u) as u8 + 55;
+    format!("#{:02x}{:02x}80", red, green)
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::episode::EpisodeRelation;
+
+    fn episode(prompt: &str) -> Episode {
+        Episode::new("api".to_string(), prompt.to_string())
+    }
+
+    #[test]
+    fn test_build_edges_and_degree() {
+        let mut a = episode("add retries");
+        let mut b = episode("add backoff");
+        let mut c = episode("deploy service");
+        let d = episode("rename variable");
+        a.session_id = Some("s1".to_string());
+        b.session_id = Some("s1".to_string());
+        c.session_id = Some("s1".to_string());
+        // Symmetric links live on both episodes but export once
+        a.add_relation(&b.id, EpisodeRelation::Alternative);
+        b.add_relation(&a.id, EpisodeRelation::Alternative);
+        c.add_relation(&a.id, EpisodeRelation::Prerequisite);
+
+        let episodes = vec![a, b, c, d];
+        let graph = GraphExport::build(&episodes, &[(0, 1, 0.8)], &RelationWeights::default());
+
+        let kinds: Vec<&str> = graph.edges.iter().map(|e| e.kind.as_str()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                "similarity",
+                "session",
+                "session",
+                "alternative",
+                "prerequisite"
+            ]
+        );
+        assert!(graph.edges[4].directed);
+        assert!(!graph.edges[3].directed);
+
+        let degrees: Vec<usize> = graph.nodes.iter().map(|n| n.degree).collect();
+        assert_eq!(degrees, vec![4, 4, 2, 0]);
+    }
+
+    #[test]
+    fn test_render_formats() {
+        let mut ep = episode("fix \"quoted\" <tag> & more");
+        ep.id = "ep-1".to_string();
+        let graph = GraphExport::build(&[ep], &[], &RelationWeights::default());
+
+        let dot = graph.render(ExportFormat::Dot).unwrap();
+        assert!(dot.starts_with("graph tempera {"));
+        assert!(dot.contains("label=\"fix \\\"quoted\\\" <tag> & more\""));
+
+        let graphml = graph.render(ExportFormat::GraphMl).unwrap();
+        assert!(graphml.contains("<node id=\"ep-1\">"));
+        assert!(graphml.contains("fix &quot;quoted&quot; &lt;tag&gt; &amp; more"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&graph.render(ExportFormat::Json).unwrap()).unwrap();
+        assert_eq!(json["nodes"][0]["id"], "ep-1");
+        assert_eq!(json["nodes"][0]["degree"], 0);
+
+        assert!(ExportFormat::parse("gexf").is_err());
+    }
+}
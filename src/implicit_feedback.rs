@@ -0,0 +1,385 @@
+// Copyright 2024-2026 Andrey Vasilevsky <anvanster@gmail.com>
+// SPDX-License-Identifier: Apache-2.0
+
+#![allow(dead_code)]
+//! Implicit feedback from captures.
+//!
+//! Most retrievals never get explicit feedback, but a capture that follows
+//! one says something about what was retrieved: a retrieved episode that
+//! touched the same files, shares tags or dealt with the same errors as the
+//! new one was probably used, more so when the task succeeded. Each signal
+//! counts as one grade point, and the resulting credit is scaled by
+//! `implicit_feedback_weight` so it counts for less than explicit feedback.
+//! It's stored on the retrieval record and in `Utility.implicit_credit`;
+//! explicit feedback for the same retrieval replaces it (see `feedback.rs`).
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+
+use crate::config::Config;
+use crate::episode::{
+    Episode, ImplicitFeedback, ImplicitSignal, MAX_FEEDBACK_GRADE, OutcomeStatus,
+};
+use crate::error_signature::ErrorSignature;
+use crate::events::{EventLog, RetrievalEvent, current_session};
+use crate::ledger::{LedgerEntry, Mechanism, UtilityLedger};
+use crate::store::EpisodeStore;
+
+/// How far back a capture can speak for retrievals
+pub const LOOKBACK_HOURS: i64 = 4;
+
+/// Shared tags needed for the tag signal; one common tag ("rust") says little
+const MIN_SHARED_TAGS: usize = 2;
+
+/// Error signature similarity for the error signal
+const ERROR_MATCH_THRESHOLD: f32 = 0.6;
+
+/// Infer feedback for episodes retrieved before `captured` was saved, and
+/// save it. Returns the retrieved episodes that got credit.
+pub fn record_capture(
+    store: &EpisodeStore,
+    captured: &Episode,
+    config: &Config,
+) -> Result<Vec<(String, ImplicitFeedback)>> {
+    let weight = config.retrieval.implicit_feedback_weight;
+    if weight <= 0.0 {
+        return Ok(vec![]);
+    }
+
+    let events = EventLog::new()?.retrievals()?;
+    let session = current_session();
+    let candidates = latest_retrievals(&events, captured, session.as_deref(), Utc::now());
+
+    let ledger = UtilityLedger::new()?;
+    let mut inferred = Vec::new();
+    for (episode_id, retrieval_id) in candidates {
+        let Ok(mut episode) = store.load(&episode_id) else {
+            continue;
+        };
+        let old_score = episode
+            .utility
+            .score
+            .unwrap_or_else(|| episode.utility.calculate_score());
+        let Some(implicit) = apply(&mut episode, &retrieval_id, captured, weight) else {
+            continue;
+        };
+
+        let score = episode.utility.calculate_score();
+        episode.utility.set_score(score, Utc::now());
+        store.update(&episode)?;
+        ledger.append(
+            &LedgerEntry::new(&episode.id, Mechanism::Implicit, old_score, score)
+                .with_source(&captured.id),
+        )?;
+        inferred.push((episode.id, implicit));
+    }
+
+    Ok(inferred)
+}
+
+/// Retrievals a capture can speak for, as (episode ID, latest retrieval ID)
+/// pairs: those in the current session or, without one, sessionless
+/// retrievals in the captured project, within `LOOKBACK_HOURS` of `now`.
+/// The captured episode itself is left out.
+pub fn latest_retrievals(
+    events: &[RetrievalEvent],
+    captured: &Episode,
+    session: Option<&str>,
+    now: DateTime<Utc>,
+) -> Vec<(String, String)> {
+    let since = now - Duration::hours(LOOKBACK_HOURS);
+    let mut latest: HashMap<&str, &RetrievalEvent> = HashMap::new();
+    let mut order = Vec::new();
+
+    for event in events {
+        if event.timestamp < since || event.timestamp > now {
+            continue;
+        }
+        let same_session = match session {
+            Some(sid) => event.session_id.as_deref() == Some(sid),
+            None => {
+                event.session_id.is_none() && event.project.eq_ignore_ascii_case(&captured.project)
+            }
+        };
+        if !same_session {
+            continue;
+        }
+
+        for result in &event.results {
+            if result.episode_id == captured.id {
+                continue;
+            }
+            match latest.get(result.episode_id.as_str()) {
+                Some(prev) if prev.timestamp > event.timestamp => {}
+                Some(_) => {
+                    latest.insert(&result.episode_id, event);
+                }
+                None => {
+                    latest.insert(&result.episode_id, event);
+                    order.push(result.episode_id.as_str());
+                }
+            }
+        }
+    }
+
+    order
+        .into_iter()
+        .map(|id| (id.to_string(), latest[id].id.clone()))
+        .collect()
+}
+
+/// Record implicit feedback on `episode`'s record for `retrieval_id`. Skipped
+/// when the record is missing, already has feedback of either kind, or the
+/// capture shows no sign of having used the episode.
+pub fn apply(
+    episode: &mut Episode,
+    retrieval_id: &str,
+    captured: &Episode,
+    weight: f32,
+) -> Option<ImplicitFeedback> {
+    let found = signals(episode, captured);
+    let record = episode
+        .retrieval_history
+        .iter_mut()
+        .rfind(|r| r.retrieval_id.as_deref() == Some(retrieval_id))?;
+    if record.has_feedback() || record.implicit.is_some() || found.is_empty() {
+        return None;
+    }
+
+    let implicit = ImplicitFeedback {
+        credit: credit(&found) * weight,
+        signals: found,
+        episode_id: captured.id.clone(),
+    };
+    record.implicit = Some(implicit.clone());
+    episode.utility.record_implicit(implicit.credit);
+    Some(implicit)
+}
+
+/// Signals that `captured` made use of `retrieved`. A successful outcome
+/// only counts alongside some overlap: on its own it says nothing about
+/// which retrieved episode helped.
+pub fn signals(retrieved: &Episode, captured: &Episode) -> Vec<ImplicitSignal> {
+    let mut signals = Vec::new();
+
+    let files = &retrieved.context.files_modified;
+    if captured
+        .context
+        .files_modified
+        .iter()
+        .any(|f| files.iter().any(|g| same_file(f, g)))
+    {
+        signals.push(ImplicitSignal::Files);
+    }
+
+    let shared_tags = captured
+        .intent
+        .domain
+        .iter()
+        .filter(|t| {
+            retrieved
+                .intent
+                .domain
+                .iter()
+                .any(|d| d.eq_ignore_ascii_case(t))
+        })
+        .count();
+    if shared_tags >= MIN_SHARED_TAGS {
+        signals.push(ImplicitSignal::Tags);
+    }
+
+    let errors: Vec<ErrorSignature> = retrieved
+        .context
+        .errors_encountered
+        .iter()
+        .map(|e| ErrorSignature::from_message(&e.message))
+        .filter(|s| !s.is_empty())
+        .collect();
+    if captured.context.errors_encountered.iter().any(|e| {
+        let signature = ErrorSignature::from_message(&e.message);
+        !signature.is_empty()
+            && errors
+                .iter()
+                .any(|s| s.similarity(&signature) >= ERROR_MATCH_THRESHOLD)
+    }) {
+        signals.push(ImplicitSignal::Errors);
+    }
+
+    if !signals.is_empty() && captured.outcome.status == OutcomeStatus::Success {
+        signals.push(ImplicitSignal::Success);
+    }
+    signals
+}
+
+/// Unweighted credit: one grade point per signal, out of `MAX_FEEDBACK_GRADE`
+pub fn credit(signals: &[ImplicitSignal]) -> f32 {
+    signals.len().min(MAX_FEEDBACK_GRADE as usize) as f32 / MAX_FEEDBACK_GRADE as f32
+}
+
+fn same_file(a: &str, b: &str) -> bool {
+    a.trim_start_matches("./") == b.trim_start_matches("./")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::episode::{ErrorRecord, RetrievalRecord};
+    use crate::events::RetrievedResult;
+
+    fn episode(prompt: &str, files: &[&str], tags: &[&str]) -> Episode {
+        let mut ep = Episode::new("api".to_string(), prompt.to_string());
+        ep.context.files_modified = files.iter().map(|f| f.to_string()).collect();
+        ep.intent.domain = tags.iter().map(|t| t.to_string()).collect();
+        ep
+    }
+
+    fn retrieval(session: Option<&str>, ids: &[&str], at: DateTime<Utc>) -> RetrievalEvent {
+        let mut e = RetrievalEvent::new(
+            "query",
+            "api",
+            ids.iter()
+                .enumerate()
+                .map(|(i, id)| RetrievedResult {
+                    episode_id: id.to_string(),
+                    rank: i + 1,
+                    score: 0.5,
+                })
+                .collect(),
+        );
+        e.session_id = session.map(String::from);
+        e.timestamp = at;
+        e
+    }
+
+    fn record(retrieval_id: &str) -> RetrievalRecord {
+        RetrievalRecord {
+            timestamp: Utc::now(),
+            project: "api".to_string(),
+            task_description: "query".to_string(),
+            was_helpful: None,
+            retrieval_id: Some(retrieval_id.to_string()),
+            session_id: None,
+            rank: Some(1),
+            grade: None,
+            aspects: vec![],
+            implicit: None,
+        }
+    }
+
+    #[test]
+    fn test_signals_and_credit() {
+        let retrieved = episode(
+            "fix pool timeout",
+            &["src/db/pool.rs"],
+            &["postgres", "pool"],
+        );
+        let mut captured = episode(
+            "raise pool size",
+            &["./src/db/pool.rs", "src/main.rs"],
+            &["postgres", "config"],
+        );
+        captured.outcome.status = OutcomeStatus::Success;
+
+        // One shared tag isn't enough
+        assert_eq!(
+            signals(&retrieved, &captured),
+            vec![ImplicitSignal::Files, ImplicitSignal::Success]
+        );
+
+        // Success alone doesn't single out an episode
+        let unrelated = episode("rename handler", &["src/routes.rs"], &["http"]);
+        assert!(signals(&unrelated, &captured).is_empty());
+
+        let mut with_error = unrelated.clone();
+        with_error.context.errors_encountered.push(ErrorRecord {
+            error_type: "runtime".to_string(),
+            message: "error[E0502]: cannot borrow `pool` as mutable".to_string(),
+            resolved: true,
+            resolution: None,
+        });
+        captured.context.errors_encountered.push(ErrorRecord {
+            error_type: "runtime".to_string(),
+            message: "error[E0502]: cannot borrow `conn` as mutable".to_string(),
+            resolved: true,
+            resolution: None,
+        });
+        assert_eq!(
+            signals(&with_error, &captured),
+            vec![ImplicitSignal::Errors, ImplicitSignal::Success]
+        );
+
+        assert!((credit(&[ImplicitSignal::Files]) - 1.0 / 3.0).abs() < 1e-6);
+        let all = [
+            ImplicitSignal::Files,
+            ImplicitSignal::Tags,
+            ImplicitSignal::Errors,
+            ImplicitSignal::Success,
+        ];
+        assert_eq!(credit(&all), 1.0);
+    }
+
+    #[test]
+    fn test_latest_retrievals() {
+        let now = Utc::now();
+        let captured = episode("raise pool size", &[], &[]);
+        let events = vec![
+            retrieval(Some("s1"), &["a", "b"], now - Duration::minutes(30)),
+            retrieval(
+                Some("s1"),
+                &["a", captured.id.as_str()],
+                now - Duration::minutes(5),
+            ),
+            retrieval(Some("s2"), &["c"], now - Duration::minutes(5)),
+            retrieval(
+                Some("s1"),
+                &["d"],
+                now - Duration::hours(LOOKBACK_HOURS + 1),
+            ),
+            retrieval(None, &["e"], now - Duration::minutes(5)),
+        ];
+
+        let found = latest_retrievals(&events, &captured, Some("s1"), now);
+        assert_eq!(
+            found,
+            vec![
+                ("a".to_string(), events[1].id.clone()),
+                ("b".to_string(), events[0].id.clone()),
+            ]
+        );
+
+        // Without a session, sessionless retrievals in the same project count
+        let found = latest_retrievals(&events, &captured, None, now);
+        assert_eq!(found, vec![("e".to_string(), events[4].id.clone())]);
+    }
+
+    #[test]
+    fn test_apply_and_retract() {
+        let mut retrieved = episode("fix pool timeout", &["src/db/pool.rs"], &[]);
+        retrieved.utility.record_impression(1.0);
+        retrieved.retrieval_history.push(record("r1"));
+        let captured = episode("raise pool size", &["src/db/pool.rs"], &[]);
+        let before = retrieved.utility.calculate_score();
+
+        let implicit = apply(&mut retrieved, "r1", &captured, 0.3).unwrap();
+        assert!((implicit.credit - 0.1).abs() < 1e-6);
+        assert_eq!(retrieved.utility.implicit_credit, Some(implicit.credit));
+        assert!(retrieved.utility.calculate_score() > before);
+
+        // Recorded once per retrieval
+        assert!(apply(&mut retrieved, "r1", &captured, 0.3).is_none());
+        assert!(apply(&mut retrieved, "missing", &captured, 0.3).is_none());
+
+        // Explicit feedback on the record leaves nothing to infer
+        retrieved.retrieval_history.push(RetrievalRecord {
+            grade: Some(0),
+            ..record("r2")
+        });
+        assert!(apply(&mut retrieved, "r2", &captured, 0.3).is_none());
+
+        retrieved.utility.retract_implicit(implicit.credit);
+        assert_eq!(retrieved.utility.implicit_credit, Some(0.0));
+        assert!((retrieved.utility.calculate_score() - before).abs() < 1e-6);
+    }
+}
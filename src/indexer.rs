@@ -12,6 +12,7 @@ use vectrust::{CreateIndexConfig, DistanceMetric, LocalIndex, UpdateRequest, Vec
 
 use crate::config::Config;
 use crate::episode::Episode;
+use crate::query::project_matches;
 use crate::store::EpisodeStore;
 
 /// Embedding dimension for BGE-Small model
@@ -41,14 +42,14 @@ impl EpisodeIndexer {
         std::fs::create_dir_all(&cache_dir)?;
         unsafe { std::env::set_var("FASTEMBED_CACHE_DIR", &cache_dir) };
 
-        println!("Loading embedding model (this may download the model on first run)...");
+        eprintln!("Loading embedding model (this may download the model on first run)...");
         let embedder = TextEmbedding::try_new(
             InitOptions::new(EmbeddingModel::BGESmallENV15)
                 .with_cache_dir(cache_dir)
                 .with_show_download_progress(true),
         )
         .context("Failed to initialize embedding model")?;
-        println!("Embedding model loaded");
+        eprintln!("Embedding model loaded");
 
         Ok(Self {
             embedder,
@@ -82,7 +83,7 @@ impl EpisodeIndexer {
     }
 
     /// Get the global model cache path (~/.tempera/models/)
-    fn model_cache_path() -> Result<PathBuf> {
+    pub(crate) fn model_cache_path() -> Result<PathBuf> {
         let data_dir = Config::data_dir()?;
         Ok(data_dir.join("models"))
     }
@@ -101,7 +102,7 @@ impl EpisodeIndexer {
     }
 
     /// Create embedding text from an episode
-    fn episode_to_embedding_text(episode: &Episode) -> String {
+    pub(crate) fn episode_to_embedding_text(episode: &Episode) -> String {
         let mut parts = Vec::new();
 
         // Intent information
@@ -280,6 +281,19 @@ impl EpisodeIndexer {
         limit: usize,
         project_filter: Option<&str>,
     ) -> Result<Vec<SearchResult>> {
+        let (results, _excluded) = self
+            .search_with_excluded(query, limit, project_filter)
+            .await?;
+        Ok(results)
+    }
+
+    /// Search for similar episodes, also returning hits removed by the project filter
+    pub async fn search_with_excluded(
+        &self,
+        query: &str,
+        limit: usize,
+        project_filter: Option<&str>,
+    ) -> Result<(Vec<SearchResult>, Vec<SearchResult>)> {
         let index = self.open_index().await?;
 
         // Generate query embedding
@@ -298,6 +312,7 @@ impl EpisodeIndexer {
             .context("Failed to search vector index")?;
 
         let mut search_results = Vec::new();
+        let mut excluded = Vec::new();
         for result in results {
             let meta = &result.item.metadata;
 
@@ -313,13 +328,6 @@ impl EpisodeIndexer {
                 .unwrap_or_default()
                 .to_string();
 
-            // Post-filter by project
-            if let Some(filter_project) = project_filter {
-                if project != filter_project {
-                    continue;
-                }
-            }
-
             let intent_text = meta
                 .get("intent_text")
                 .and_then(|v| v.as_str())
@@ -331,20 +339,31 @@ impl EpisodeIndexer {
                 .and_then(|v| v.as_f64())
                 .unwrap_or(0.0) as f32;
 
-            search_results.push(SearchResult {
+            let search_result = SearchResult {
                 id: episode_id,
                 project,
                 intent_text,
                 similarity_score: result.score,
                 utility_score,
-            });
+                vector: result.item.vector,
+            };
+
+            // Post-filter by project
+            if let Some(filter_project) = project_filter {
+                if !project_matches(&search_result.project, filter_project) {
+                    excluded.push(search_result);
+                    continue;
+                }
+            }
+
+            search_results.push(search_result);
 
             if search_results.len() >= limit {
                 break;
             }
         }
 
-        Ok(search_results)
+        Ok((search_results, excluded))
     }
 
     /// Check if the index exists and has data
@@ -409,6 +428,8 @@ pub struct SearchResult {
     pub intent_text: String,
     pub similarity_score: f32,
     pub utility_score: f32,
+    /// Stored embedding of the episode (used for diversity scoring)
+    pub vector: Vec<f32>,
 }
 
 /// Index statistics
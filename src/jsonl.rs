@@ -0,0 +1,153 @@
+// Copyright 2024-2026 Andrey Vasilevsky <anvanster@gmail.com>
+// SPDX-License-Identifier: Apache-2.0
+
+#![allow(dead_code)]
+//! Append-only JSON lines logs.
+//!
+//! Shared by the retrieval event log and the utility ledger. Each record is
+//! written with a single append so concurrent writers don't interleave.
+//! By default the live file rotates to `<name>.1` at `MAX_LOG_BYTES`,
+//! replacing the previous generation; readers parse both generations, so old
+//! records age out and every read stays bounded. Logs that must keep every
+//! record opt out with `unbounded`.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+/// Size at which the live file is rotated
+pub const MAX_LOG_BYTES: u64 = 4 * 1024 * 1024;
+
+/// A JSON lines file with one rotated generation
+pub struct JsonlLog {
+    path: PathBuf,
+    /// None: never rotate
+    max_bytes: Option<u64>,
+}
+
+impl JsonlLog {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            max_bytes: Some(MAX_LOG_BYTES),
+        }
+    }
+
+    /// Rotate at `max_bytes` instead of `MAX_LOG_BYTES`
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Never rotate, keeping every record
+    pub fn unbounded(mut self) -> Self {
+        self.max_bytes = None;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Previous generation of the log
+    fn rotated_path(&self) -> PathBuf {
+        let mut name = self.path.file_name().unwrap_or_default().to_os_string();
+        name.push(".1");
+        self.path.with_file_name(name)
+    }
+
+    /// Move a full live file aside, dropping the generation before it
+    fn rotate_if_full(&self) -> Result<()> {
+        let Some(max_bytes) = self.max_bytes else {
+            return Ok(());
+        };
+        let Ok(meta) = std::fs::metadata(&self.path) else {
+            return Ok(());
+        };
+        if meta.len() < max_bytes {
+            return Ok(());
+        }
+        match std::fs::rename(&self.path, self.rotated_path()) {
+            // Another writer rotated it first
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            result => result.with_context(|| format!("Failed to rotate {}", self.path.display())),
+        }
+    }
+
+    pub fn append<T: Serialize>(&self, record: &T) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        self.rotate_if_full()?;
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open {}", self.path.display()))?
+            .write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    /// Read every record in both generations, oldest first, skipping lines
+    /// that fail to parse
+    pub fn read<T: DeserializeOwned>(&self) -> Result<Vec<T>> {
+        let mut records = Vec::new();
+        for path in [self.rotated_path(), self.path.clone()] {
+            if !path.exists() {
+                continue;
+            }
+            let file = std::fs::File::open(&path)?;
+            records.extend(
+                std::io::BufReader::new(file)
+                    .lines()
+                    .map_while(Result::ok)
+                    .filter_map(|line| serde_json::from_str::<T>(&line).ok()),
+            );
+        }
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_append_read_and_rotate() {
+        let temp = TempDir::new().unwrap();
+        let log = JsonlLog::new(temp.path().join("log.jsonl")).with_max_bytes(4);
+
+        // Two 2-byte lines fill the live file; the third rotates it
+        for i in 0..3u32 {
+            log.append(&i).unwrap();
+        }
+        let records: Vec<u32> = log.read().unwrap();
+        assert_eq!(records, vec![0, 1, 2]);
+
+        // Unparseable lines are skipped
+        std::fs::write(temp.path().join("log.jsonl.1"), "0\nnot json\n1\n").unwrap();
+        let records: Vec<u32> = log.read().unwrap();
+        assert_eq!(records, vec![0, 1, 2]);
+
+        // The next rotation replaces the old generation
+        log.append(&3u32).unwrap();
+        log.append(&4u32).unwrap();
+        let records: Vec<u32> = log.read().unwrap();
+        assert_eq!(records, vec![2, 3, 4]);
+
+        // An unbounded log keeps everything in the live file
+        let path = temp.path().join("ledger.jsonl");
+        let log = JsonlLog::new(path.clone()).unbounded();
+        for i in 0..10u32 {
+            log.append(&i).unwrap();
+        }
+        let records: Vec<u32> = log.read().unwrap();
+        assert_eq!(records, (0..10).collect::<Vec<_>>());
+        assert!(!temp.path().join("ledger.jsonl.1").exists());
+    }
+}
@@ -0,0 +1,238 @@
+// Copyright 2024-2026 Andrey Vasilevsky <anvanster@gmail.com>
+// SPDX-License-Identifier: Apache-2.0
+
+#![allow(dead_code)]
+//! Utility ledger.
+//!
+//! Scores move through feedback, decay, Bellman hops, session propagation and
+//! temporal credit. Every change is appended here with the old and new value,
+//! the mechanism, the source episode and hop depth, so `tempera why <id>` can
+//! explain how an episode reached its current score.
+//!
+//! Stored as append-only JSON lines in `~/.tempera/utility_ledger.jsonl`
+//! (see `jsonl.rs`). Unlike the event log it never rotates: dropping old
+//! entries would leave `why` unable to explain long-lived scores.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::config::Config;
+use crate::jsonl::JsonlLog;
+
+/// What changed an episode's utility
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Mechanism {
+    Feedback,
+    Decay,
+    Bellman,
+    Session,
+    TagPropagation,
+    TemporalCredit,
+    Penalty,
+    Relation,
+    Implicit,
+}
+
+impl fmt::Display for Mechanism {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Mechanism::Feedback => "feedback",
+            Mechanism::Decay => "decay",
+            Mechanism::Bellman => "bellman",
+            Mechanism::Session => "session",
+            Mechanism::TagPropagation => "tag propagation",
+            Mechanism::TemporalCredit => "temporal credit",
+            Mechanism::Penalty => "penalty",
+            Mechanism::Relation => "relation",
+            Mechanism::Implicit => "implicit feedback",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// One utility change
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub episode_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub mechanism: Mechanism,
+    pub old_value: f32,
+    pub new_value: f32,
+    /// Episode the change was propagated or credited from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_episode: Option<String>,
+    /// Propagation hop (1 = direct neighbour of the source)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hop: Option<u32>,
+}
+
+impl LedgerEntry {
+    pub fn new(episode_id: &str, mechanism: Mechanism, old_value: f32, new_value: f32) -> Self {
+        Self {
+            episode_id: episode_id.to_string(),
+            timestamp: Utc::now(),
+            mechanism,
+            old_value,
+            new_value,
+            source_episode: None,
+            hop: None,
+        }
+    }
+
+    pub fn with_source(mut self, source: &str) -> Self {
+        self.source_episode = Some(source.to_string());
+        self
+    }
+
+    pub fn at_hop(mut self, hop: u32) -> Self {
+        self.hop = Some(hop);
+        self
+    }
+
+    pub fn delta(&self) -> f32 {
+        self.new_value - self.old_value
+    }
+}
+
+/// Per-mechanism totals
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MechanismSummary {
+    pub changes: usize,
+    pub total_delta: f64,
+    /// Distinct episodes touched
+    pub episodes: usize,
+}
+
+/// Append-only utility ledger
+pub struct UtilityLedger {
+    log: JsonlLog,
+}
+
+impl UtilityLedger {
+    pub fn new() -> Result<Self> {
+        Ok(Self::with_path(Config::utility_ledger_path()?))
+    }
+
+    pub fn with_path(path: PathBuf) -> Self {
+        Self {
+            log: JsonlLog::new(path).unbounded(),
+        }
+    }
+
+    pub fn append(&self, entry: &LedgerEntry) -> Result<()> {
+        self.log.append(entry)
+    }
+
+    /// Read every entry, skipping lines that fail to parse
+    pub fn entries(&self) -> Result<Vec<LedgerEntry>> {
+        self.log.read()
+    }
+
+    /// Entries for one episode (full ID or prefix), oldest first
+    pub fn for_episode(&self, id: &str) -> Result<Vec<LedgerEntry>> {
+        let mut entries: Vec<LedgerEntry> = self
+            .entries()?
+            .into_iter()
+            .filter(|e| e.episode_id.starts_with(id))
+            .collect();
+        entries.sort_by_key(|e| e.timestamp);
+        Ok(entries)
+    }
+}
+
+/// Aggregate entries per mechanism
+pub fn summarize(entries: &[LedgerEntry]) -> BTreeMap<Mechanism, MechanismSummary> {
+    let mut touched: BTreeMap<Mechanism, std::collections::HashSet<&str>> = BTreeMap::new();
+    let mut summary: BTreeMap<Mechanism, MechanismSummary> = BTreeMap::new();
+
+    for entry in entries {
+        let s = summary.entry(entry.mechanism).or_default();
+        s.changes += 1;
+        s.total_delta += entry.delta() as f64;
+        touched
+            .entry(entry.mechanism)
+            .or_default()
+            .insert(entry.episode_id.as_str());
+    }
+    for (mechanism, ids) in touched {
+        if let Some(s) = summary.get_mut(&mechanism) {
+            s.episodes = ids.len();
+        }
+    }
+
+    summary
+}
+
+/// One-line description of a ledger entry
+pub fn format_entry(entry: &LedgerEntry) -> String {
+    let mut line = format!(
+        "{}  {:<15} {:.3} → {:.3} ({:+.3})",
+        entry.timestamp.format("%Y-%m-%d %H:%M"),
+        entry.mechanism.to_string(),
+        entry.old_value,
+        entry.new_value,
+        entry.delta()
+    );
+    if let Some(source) = &entry.source_episode {
+        line.push_str(&format!("  from {}", &source[..8.min(source.len())]));
+    }
+    if let Some(hop) = entry.hop {
+        line.push_str(&format!(" (hop {})", hop));
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_append_and_filter() {
+        let temp = TempDir::new().unwrap();
+        let ledger = UtilityLedger::with_path(temp.path().join("ledger.jsonl"));
+
+        ledger
+            .append(&LedgerEntry::new("aaaa1111", Mechanism::Feedback, 0.5, 0.6))
+            .unwrap();
+        ledger
+            .append(
+                &LedgerEntry::new("bbbb2222", Mechanism::Bellman, 0.5, 0.55)
+                    .with_source("aaaa1111")
+                    .at_hop(1),
+            )
+            .unwrap();
+        ledger
+            .append(&LedgerEntry::new("aaaa1111", Mechanism::Decay, 0.6, 0.58))
+            .unwrap();
+
+        let entries = ledger.for_episode("aaaa").unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].mechanism, Mechanism::Feedback);
+
+        let line = format_entry(&ledger.for_episode("bbbb").unwrap()[0]);
+        assert!(line.contains("bellman"));
+        assert!(line.contains("from aaaa1111 (hop 1)"));
+    }
+
+    #[test]
+    fn test_summarize() {
+        let entries = vec![
+            LedgerEntry::new("a", Mechanism::Decay, 0.6, 0.5),
+            LedgerEntry::new("b", Mechanism::Decay, 0.4, 0.3),
+            LedgerEntry::new("a", Mechanism::Decay, 0.5, 0.45),
+            LedgerEntry::new("a", Mechanism::Feedback, 0.45, 0.7),
+        ];
+        let summary = summarize(&entries);
+        let decay = &summary[&Mechanism::Decay];
+        assert_eq!(decay.changes, 3);
+        assert_eq!(decay.episodes, 2);
+        assert!((decay.total_delta + 0.25).abs() < 1e-6);
+        assert_eq!(summary[&Mechanism::Feedback].changes, 1);
+    }
+}
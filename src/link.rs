@@ -0,0 +1,171 @@
+// Copyright 2024-2026 Andrey Vasilevsky <anvanster@gmail.com>
+// SPDX-License-Identifier: Apache-2.0
+
+#![allow(dead_code)]
+//! Manual links between episodes.
+//!
+//! Symmetric relations (alternative, related) are stored on both episodes.
+//! Directed ones (continuation, prerequisite) are stored on the source only
+//! and shown on the target as incoming links.
+
+use anyhow::{Result, bail};
+use colored::Colorize;
+
+use crate::config::Config;
+use crate::episode::{Episode, EpisodeRelation};
+use crate::store::EpisodeStore;
+
+/// Run the link command
+pub async fn run_link(from: &str, to: &str, relation: &str, _config: &Config) -> Result<()> {
+    let Some(relation) = EpisodeRelation::parse(relation) else {
+        bail!(
+            "Unknown relation: {}. Use continuation, prerequisite, alternative or related.",
+            relation
+        );
+    };
+
+    let store = EpisodeStore::new()?;
+    let (from, to) = link(&store, from, to, relation)?;
+
+    println!(
+        "🔗 Linked {} → {} ({}{})",
+        &from.id[..8],
+        &to.id[..8],
+        relation,
+        if relation.is_symmetric() {
+            ", both ways"
+        } else {
+            ""
+        }
+    );
+    Ok(())
+}
+
+/// Run the unlink command
+pub async fn run_unlink(from: &str, to: &str, _config: &Config) -> Result<()> {
+    let store = EpisodeStore::new()?;
+    if unlink(&store, from, to)? {
+        println!("✂️  Unlinked {} and {}", from, to);
+    } else {
+        println!(
+            "{}",
+            format!("No link between {} and {}", from, to).dimmed()
+        );
+    }
+    Ok(())
+}
+
+/// Check that `from` can be linked to `to`
+pub fn validate_link(from: &Episode, to: &Episode) -> Result<()> {
+    if from.id == to.id {
+        bail!("Cannot link an episode to itself");
+    }
+    // A directed link the other way would be contradicted (or overwritten,
+    // for a symmetric relation)
+    if let Some(reverse) = to.relation_to(&from.id).filter(|r| !r.is_symmetric()) {
+        bail!(
+            "{} is already linked to {} as {}; unlink it first",
+            &to.id[..8.min(to.id.len())],
+            &from.id[..8.min(from.id.len())],
+            reverse
+        );
+    }
+    Ok(())
+}
+
+/// Apply a validated link to both episodes in memory
+pub fn apply_link(from: &mut Episode, to: &mut Episode, relation: EpisodeRelation) {
+    from.add_relation(&to.id, relation);
+    if relation.is_symmetric() {
+        to.add_relation(&from.id, relation);
+    } else if to.relation_to(&from.id).is_some_and(|r| r.is_symmetric()) {
+        // A symmetric link became directed: drop the back-link
+        to.remove_relation(&from.id);
+    }
+}
+
+/// Link two episodes (full IDs or 8-char prefixes) and save both
+pub fn link(
+    store: &EpisodeStore,
+    from: &str,
+    to: &str,
+    relation: EpisodeRelation,
+) -> Result<(Episode, Episode)> {
+    let mut from = store.load(from)?;
+    let mut to = store.load(to)?;
+    validate_link(&from, &to)?;
+
+    apply_link(&mut from, &mut to, relation);
+    store.update(&from)?;
+    store.update(&to)?;
+    Ok((from, to))
+}
+
+/// Remove links between two episodes in both directions; returns whether
+/// any link existed
+pub fn unlink(store: &EpisodeStore, a: &str, b: &str) -> Result<bool> {
+    let mut a = store.load(a)?;
+    let mut b = store.load(b)?;
+
+    let removed_ab = a.remove_relation(&b.id);
+    let removed_ba = b.remove_relation(&a.id);
+    if removed_ab {
+        store.update(&a)?;
+    }
+    if removed_ba {
+        store.update(&b)?;
+    }
+    Ok(removed_ab || removed_ba)
+}
+
+/// Directed links pointing at an episode from others
+pub fn incoming(store: &EpisodeStore, id: &str) -> Result<Vec<(Episode, EpisodeRelation)>> {
+    Ok(store
+        .list_all()?
+        .into_iter()
+        .filter_map(|ep| {
+            let relation = ep.relation_to(id).filter(|r| !r.is_symmetric())?;
+            Some((ep, relation))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn episode(prompt: &str) -> Episode {
+        Episode::new("api".to_string(), prompt.to_string())
+    }
+
+    #[test]
+    fn test_symmetric_and_directed_links() {
+        let mut a = episode("add retries");
+        let mut b = episode("add backoff");
+
+        apply_link(&mut a, &mut b, EpisodeRelation::Alternative);
+        assert_eq!(a.relation_to(&b.id), Some(EpisodeRelation::Alternative));
+        assert_eq!(b.relation_to(&a.id), Some(EpisodeRelation::Alternative));
+
+        // Making it directed keeps only the source side
+        validate_link(&a, &b).unwrap();
+        apply_link(&mut a, &mut b, EpisodeRelation::Prerequisite);
+        assert_eq!(a.relation_to(&b.id), Some(EpisodeRelation::Prerequisite));
+        assert_eq!(b.relation_to(&a.id), None);
+        assert_eq!(a.related_episodes.len(), 1);
+    }
+
+    #[test]
+    fn test_validate_link() {
+        let mut a = episode("add retries");
+        let mut b = episode("add backoff");
+
+        assert!(validate_link(&a, &a).is_err());
+
+        apply_link(&mut a, &mut b, EpisodeRelation::Prerequisite);
+        // Any link back would contradict or overwrite the directed one
+        assert!(validate_link(&b, &a).is_err());
+        // Changing the relation in the same direction is fine
+        assert!(validate_link(&a, &b).is_ok());
+    }
+}
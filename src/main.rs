@@ -16,10 +16,24 @@ use clap::{Parser, Subcommand};
 
 mod capture;
 mod config;
+mod context_pack;
 mod episode;
+mod error_signature;
+mod events;
+mod exploration;
 mod feedback;
+mod graph;
+mod graph_export;
+mod implicit_feedback;
 mod indexer;
+mod jsonl;
+mod ledger;
+mod link;
 mod llm;
+mod maintenance;
+mod position_bias;
+mod query;
+mod reranker;
 mod retrieve;
 mod stats;
 mod store;
@@ -57,8 +71,10 @@ enum Commands {
 
     /// Retrieve relevant episodes for a task
     Retrieve {
-        /// Task description to find relevant episodes for
-        query: String,
+        /// Task description, optionally with filters
+        /// (e.g. "tag:rust outcome:failure type:bugfix since:30d project:api timeout")
+        #[arg(required_unless_present = "error")]
+        query: Option<String>,
 
         /// Number of episodes to retrieve
         #[arg(long, short, default_value = "3")]
@@ -71,16 +87,55 @@ enum Commands {
         /// Output format (markdown, json)
         #[arg(long, default_value = "markdown")]
         format: String,
+
+        /// Show the full score breakdown and why candidates were dropped
+        #[arg(long)]
+        explain: bool,
+
+        /// Search by error message signature and show stored resolutions
+        #[arg(long, conflicts_with = "query")]
+        error: Option<String>,
+    },
+
+    /// Build a compact, token-budgeted brief for the start of a session
+    ContextPack {
+        /// Task description, optionally with filters
+        #[arg(long, short)]
+        query: String,
+
+        /// Approximate token budget for the whole pack
+        #[arg(long, default_value = "1500")]
+        budget: usize,
+
+        /// Project for unfinished work (defaults to current directory name)
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Output format (markdown, xml)
+        #[arg(long, default_value = "markdown")]
+        format: String,
     },
 
     /// Record feedback on retrieved episodes
     Feedback {
-        /// Feedback type: helpful, not-helpful, mixed
-        feedback_type: String,
+        /// Feedback type: helpful, not-helpful, mixed, skip
+        feedback_type: Option<String>,
+
+        /// Graded feedback instead of a type: 0 (not helpful) to 3 (helpful)
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=3))]
+        grade: Option<u8>,
+
+        /// Aspects (comma-separated): relevant, correct, outdated, too-vague
+        #[arg(long)]
+        aspects: Option<String>,
 
         /// Episode IDs (comma-separated, or "last" for last retrieved)
         #[arg(long)]
         episodes: Option<String>,
+
+        /// Retrieval ID to attach feedback to, or "last" for this project/session
+        #[arg(long)]
+        retrieval: Option<String>,
     },
 
     /// List episodes
@@ -89,6 +144,10 @@ enum Commands {
         #[arg(default_value = "10")]
         limit: usize,
 
+        /// Structured query, e.g. "tag:rust outcome:failure since:30d timeout"
+        #[arg(long, short)]
+        query: Option<String>,
+
         /// Filter by project
         #[arg(long)]
         project: Option<String>,
@@ -108,6 +167,34 @@ enum Commands {
         id: String,
     },
 
+    /// Explain an episode's utility score from its history of changes
+    Why {
+        /// Episode ID or "latest"
+        id: String,
+    },
+
+    /// Link two episodes
+    Link {
+        /// Source episode ID
+        from: String,
+
+        /// Target episode ID
+        to: String,
+
+        /// Relation: continuation, prerequisite, alternative, related
+        #[arg(long = "as", default_value = "related")]
+        relation: String,
+    },
+
+    /// Remove links between two episodes (both directions)
+    Unlink {
+        /// First episode ID
+        a: String,
+
+        /// Second episode ID
+        b: String,
+    },
+
     /// Show statistics
     Stats {
         /// Filter by project
@@ -115,6 +202,21 @@ enum Commands {
         project: Option<String>,
     },
 
+    /// Export the episode graph (nodes, similarity, session and link edges)
+    Graph {
+        /// Filter by project
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Output format (dot, graphml, json)
+        #[arg(long, default_value = "dot")]
+        format: String,
+
+        /// Write to a file instead of stdout
+        #[arg(long, short)]
+        output: Option<String>,
+    },
+
     /// Index episodes for vector search (Phase 2)
     Index {
         /// Reindex all episodes
@@ -131,6 +233,14 @@ enum Commands {
         /// Project filter for propagation
         #[arg(long)]
         project: Option<String>,
+
+        /// Run against an in-memory copy and show the changes without saving
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Dry-run output format (table, json)
+        #[arg(long, default_value = "table", value_parser = ["table", "json"], requires = "dry_run")]
+        format: String,
     },
 
     /// Prune old/low-utility episodes
@@ -159,6 +269,18 @@ enum Commands {
         bucket: String,
     },
 
+    /// Run overdue maintenance jobs (decay, propagation, temporal credit,
+    /// prune, reindex); normally started in the background
+    Maintain {
+        /// Run every enabled job, due or not
+        #[arg(long)]
+        force: bool,
+
+        /// Show when each job last ran and is next due
+        #[arg(long, conflicts_with = "force")]
+        status: bool,
+    },
+
     /// Initialize tempera in current project
     Init,
 }
@@ -167,6 +289,7 @@ enum Commands {
 async fn main() -> Result<()> {
     let cli = Cli::parse();
     let config = config::Config::load()?;
+    let background = !matches!(cli.command, Commands::Maintain { .. } | Commands::Init);
 
     match cli.command {
         Commands::Capture {
@@ -183,40 +306,84 @@ async fn main() -> Result<()> {
             limit,
             project,
             format,
+            explain,
+            error,
+        } => {
+            if let Some(error) = error {
+                retrieve::run_error(&error, limit, project, &format, &config)?;
+            } else if let Some(query) = query {
+                retrieve::run(&query, limit, project, &format, explain, &config).await?;
+            }
+        }
+
+        Commands::ContextPack {
+            query,
+            budget,
+            project,
+            format,
         } => {
-            retrieve::run(&query, limit, project, &format, &config).await?;
+            context_pack::run(&query, budget, project, &format, &config).await?;
         }
 
         Commands::Feedback {
             feedback_type,
+            grade,
+            aspects,
             episodes,
+            retrieval,
         } => {
-            feedback::run(&feedback_type, episodes, &config).await?;
+            feedback::run(feedback_type, grade, aspects, episodes, retrieval, &config).await?;
         }
 
         Commands::List {
             limit,
+            query,
             project,
             tag,
             outcome,
         } => {
-            stats::list(limit, project, tag, outcome, &config).await?;
+            stats::list(limit, query, project, tag, outcome, &config).await?;
         }
 
         Commands::Show { id } => {
             stats::show(&id, &config).await?;
         }
 
+        Commands::Why { id } => {
+            stats::why(&id, &config).await?;
+        }
+
+        Commands::Link { from, to, relation } => {
+            link::run_link(&from, &to, &relation, &config).await?;
+        }
+
+        Commands::Unlink { a, b } => {
+            link::run_unlink(&a, &b, &config).await?;
+        }
+
         Commands::Stats { project } => {
             stats::run(project, &config).await?;
         }
 
+        Commands::Graph {
+            project,
+            format,
+            output,
+        } => {
+            graph_export::run(project, &format, output, &config).await?;
+        }
+
         Commands::Index { reindex } => {
             run_index(reindex).await?;
         }
 
-        Commands::Propagate { temporal, project } => {
-            run_propagate(temporal, project, &config).await?;
+        Commands::Propagate {
+            temporal,
+            project,
+            dry_run,
+            format,
+        } => {
+            run_propagate(temporal, project, dry_run, &format, &config).await?;
         }
 
         Commands::Prune {
@@ -231,11 +398,19 @@ async fn main() -> Result<()> {
             stats::trends(project, &bucket, &config).await?;
         }
 
+        Commands::Maintain { force, status } => {
+            maintenance::run(force, status, &config).await?;
+        }
+
         Commands::Init => {
             init_project()?;
         }
     }
 
+    if background {
+        maintenance::spawn_if_due(&config);
+    }
+
     Ok(())
 }
 
@@ -248,6 +423,7 @@ async fn run_index(reindex: bool) -> Result<()> {
 
     let mut indexer = indexer::EpisodeIndexer::new().await?;
     let indexed = indexer.index_all(reindex).await?;
+    maintenance::mark_done(&[maintenance::Job::Reindex]);
 
     // Get stats
     let stats = indexer.get_stats().await?;
@@ -264,17 +440,57 @@ async fn run_index(reindex: bool) -> Result<()> {
 async fn run_propagate(
     temporal: bool,
     project: Option<String>,
+    dry_run: bool,
+    format: &str,
     config: &config::Config,
 ) -> Result<()> {
-    println!("📈 Running utility propagation...\n");
+    let json = dry_run && format == "json";
+    let store = store::EpisodeStore::new()?;
+    let mut run = utility::PropagationRun::new(&store, dry_run)?;
+
+    if !json {
+        println!("📈 Running utility propagation...\n");
+        if dry_run {
+            println!("📋 DRY RUN - no scores will be saved\n");
+        }
+    }
 
     // Run the main propagation pipeline
-    let result = utility::run_propagation(config).await?;
+    let result = utility::run_propagation(&mut run, config).await?;
+
+    // Run temporal credit assignment if requested
+    let mut credited = None;
+    if temporal {
+        let params = utility::UtilityParams::from_config(config);
+        credited = Some(utility::temporal_credit_assignment(
+            &mut run,
+            project.as_deref(),
+            &params,
+            config,
+        )?);
+    }
+
+    if !dry_run {
+        maintenance::mark_done(&[maintenance::Job::Decay, maintenance::Job::Bellman]);
+        if credited.is_some() && project.is_none() {
+            maintenance::mark_done(&[maintenance::Job::TemporalCredit]);
+        }
+    }
+
+    if json {
+        let report = run.report(&result, credited);
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    let diff = run.diff();
+    let by_mechanism = ledger::summarize(run.entries());
 
     println!("\n📊 Propagation Results:");
     println!("   Episodes processed: {}", result.episodes_processed);
     println!("   Decayed: {}", result.decayed_episodes);
     println!("   Propagated: {}", result.propagated_episodes);
+    println!("   Penalized: {}", result.penalized_episodes);
     println!(
         "   Hops: {}{}",
         result.hops_executed,
@@ -284,18 +500,39 @@ async fn run_propagate(
         "   Total utility change: {:+.3}",
         result.total_utility_change
     );
-
-    // Run temporal credit assignment if requested
-    if temporal {
-        println!("\n⏱️  Running temporal credit assignment...");
-        let store = store::EpisodeStore::new()?;
-        let params = utility::UtilityParams::from_config(config);
-        let updated =
-            utility::temporal_credit_assignment(&store, project.as_deref(), &params, config)?;
-        println!("   Episodes credited: {}", updated);
+    if let Some(credited) = credited {
+        println!("   Episodes credited: {}", credited);
     }
 
-    println!("\n✅ Propagation complete!");
+    if dry_run {
+        println!("\n📋 Changes ({} episodes):", diff.len());
+        for d in &diff {
+            let mechanisms: Vec<String> = d.mechanisms.iter().map(|m| m.to_string()).collect();
+            println!(
+                "  {}  {:.3} → {:.3} ({:+.3})  [{}]  {}",
+                &d.id[..8],
+                d.before,
+                d.after,
+                d.after - d.before,
+                mechanisms.join(", "),
+                d.intent
+            );
+        }
+
+        println!("\n📊 By mechanism:");
+        for (mechanism, summary) in &by_mechanism {
+            println!(
+                "   {:<15} {} change(s) across {} episode(s), net {:+.3}",
+                mechanism.to_string(),
+                summary.changes,
+                summary.episodes,
+                summary.total_delta
+            );
+        }
+        println!("\n✅ Dry run complete - nothing was saved");
+    } else {
+        println!("\n✅ Propagation complete!");
+    }
     Ok(())
 }
 
@@ -314,6 +551,9 @@ fn run_prune(
 
     let store = store::EpisodeStore::new()?;
     let result = utility::prune_episodes(&store, older_than, min_utility, !execute, config)?;
+    if execute {
+        maintenance::mark_done(&[maintenance::Job::Prune]);
+    }
 
     if result.candidates.is_empty() {
         println!("No episodes match pruning criteria.");
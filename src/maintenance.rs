@@ -0,0 +1,505 @@
+// Copyright 2024-2026 Andrey Vasilevsky <anvanster@gmail.com>
+// SPDX-License-Identifier: Apache-2.0
+
+#![allow(dead_code)]
+//! Self-scheduling maintenance.
+//!
+//! Decay, Bellman propagation, temporal credit, pruning and reindexing record
+//! when they last ran in `~/.tempera/maintenance.json`. After a CLI command,
+//! and every so often during an MCP session, overdue jobs are handed to a
+//! detached `maintain` process so the command that noticed them never waits.
+//! An OS lock on `~/.tempera/maintenance.lock` keeps it to one process at a
+//! time; it goes away with the process, so a crashed run never leaves it held.
+//! A failed job waits out `FAILURE_BACKOFF` before it's due again.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{File, TryLockError};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Duration, Utc};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::indexer::EpisodeIndexer;
+use crate::store::EpisodeStore;
+use crate::utility::{self, PropagationRun, UtilityParams};
+
+/// How often a long-running MCP session checks for overdue jobs
+pub const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+
+/// How long a failed job waits before it's retried
+pub const FAILURE_BACKOFF: Duration = Duration::hours(1);
+
+/// A scheduled maintenance job, in the order they run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Job {
+    Reindex,
+    Decay,
+    Bellman,
+    TemporalCredit,
+    Prune,
+}
+
+impl Job {
+    pub const ALL: [Job; 5] = [
+        Job::Reindex,
+        Job::Decay,
+        Job::Bellman,
+        Job::TemporalCredit,
+        Job::Prune,
+    ];
+
+    /// The configured interval setting for this job
+    pub fn interval<'a>(&self, config: &'a Config) -> &'a str {
+        match self {
+            Job::Reindex => &config.maintenance.reindex_interval,
+            Job::Decay | Job::Bellman | Job::TemporalCredit => &config.bellman.propagate_interval,
+            Job::Prune => &config.maintenance.prune_interval,
+        }
+    }
+}
+
+impl fmt::Display for Job {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Job::Reindex => "reindex",
+            Job::Decay => "decay",
+            Job::Bellman => "bellman",
+            Job::TemporalCredit => "temporal credit",
+            Job::Prune => "prune",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Parse an interval: "hourly", "daily", "weekly", a count of hours, days or
+/// weeks ("6h", "3d", "2w"), or "never" (None)
+pub fn parse_interval(s: &str) -> Result<Option<Duration>> {
+    let s = s.trim().to_lowercase();
+    let interval = match s.as_str() {
+        "never" | "off" | "manual" => return Ok(None),
+        "hourly" => Duration::hours(1),
+        "daily" => Duration::days(1),
+        "weekly" => Duration::weeks(1),
+        _ => {
+            let Some(unit) = s.chars().last() else {
+                bail!("Empty interval");
+            };
+            let count: i64 = s[..s.len() - unit.len_utf8()]
+                .parse()
+                .ok()
+                .filter(|n| *n > 0)
+                .with_context(|| format!("Invalid interval '{}'", s))?;
+            match unit {
+                'h' => Duration::hours(count),
+                'd' => Duration::days(count),
+                'w' => Duration::weeks(count),
+                _ => bail!(
+                    "Invalid interval '{}' (expected hourly, daily, weekly, 6h, 3d, 2w or never)",
+                    s
+                ),
+            }
+        }
+    };
+    Ok(Some(interval))
+}
+
+/// When each job last completed, and last failed since then
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MaintenanceState {
+    #[serde(default)]
+    pub last_run: BTreeMap<Job, DateTime<Utc>>,
+    #[serde(default)]
+    pub last_failure: BTreeMap<Job, DateTime<Utc>>,
+}
+
+impl MaintenanceState {
+    /// When a job is next due; None when it's disabled
+    pub fn next_due(&self, job: Job, config: &Config) -> Result<Option<DateTime<Utc>>> {
+        let Some(interval) = parse_interval(job.interval(config))? else {
+            return Ok(None);
+        };
+        let due = match self.last_run.get(&job) {
+            Some(last) => *last + interval,
+            // Never run: due right away
+            None => DateTime::<Utc>::MIN_UTC,
+        };
+        // A failing job backs off instead of respawning on every check
+        Ok(Some(match self.last_failure.get(&job) {
+            Some(failed) => due.max(*failed + FAILURE_BACKOFF),
+            None => due,
+        }))
+    }
+
+    /// Jobs overdue at `now`, in run order
+    pub fn due(&self, config: &Config, now: DateTime<Utc>) -> Result<Vec<Job>> {
+        let mut due = Vec::new();
+        for job in Job::ALL {
+            if self.next_due(job, config)?.is_some_and(|at| at <= now) {
+                due.push(job);
+            }
+        }
+        Ok(due)
+    }
+}
+
+/// Maintenance state and lock files
+pub struct Maintenance {
+    dir: PathBuf,
+}
+
+/// Held while a process runs maintenance jobs; the OS releases it when the
+/// file is closed on drop, or when the process dies
+#[derive(Debug)]
+pub struct MaintenanceLock {
+    file: File,
+}
+
+impl Maintenance {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            dir: Config::data_dir()?,
+        })
+    }
+
+    pub fn with_dir(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn state_path(&self) -> PathBuf {
+        self.dir.join("maintenance.json")
+    }
+
+    fn lock_path(&self) -> PathBuf {
+        self.dir.join("maintenance.lock")
+    }
+
+    /// Load the state; a missing or unreadable file means nothing has run yet
+    pub fn load_state(&self) -> MaintenanceState {
+        std::fs::read_to_string(self.state_path())
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    fn save_state(&self, state: &MaintenanceState) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(self.state_path(), serde_json::to_string_pretty(state)?)
+            .context("Failed to write maintenance state")
+    }
+
+    /// Record that jobs completed at `at`
+    pub fn record(&self, jobs: &[Job], at: DateTime<Utc>) -> Result<()> {
+        let mut state = self.load_state();
+        for job in jobs {
+            state.last_run.insert(*job, at);
+            state.last_failure.remove(job);
+        }
+        self.save_state(&state)
+    }
+
+    /// Record that a job failed at `at`, so it backs off before the next try
+    pub fn record_failure(&self, job: Job, at: DateTime<Utc>) -> Result<()> {
+        let mut state = self.load_state();
+        state.last_failure.insert(job, at);
+        self.save_state(&state)
+    }
+
+    /// Take the lock, or None when another process holds it
+    pub fn try_lock(&self) -> Result<Option<MaintenanceLock>> {
+        std::fs::create_dir_all(&self.dir)?;
+        let mut file = File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.lock_path())
+            .context("Failed to open maintenance lock")?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => return Ok(None),
+            Err(TryLockError::Error(e)) => {
+                return Err(e).context("Failed to take maintenance lock");
+            }
+        }
+        // The holder's pid, for anyone inspecting the file
+        file.set_len(0)?;
+        writeln!(file, "{}", std::process::id())?;
+        Ok(Some(MaintenanceLock { file }))
+    }
+
+    /// Whether another process holds the lock
+    pub fn is_locked(&self) -> bool {
+        let Ok(file) = File::open(self.lock_path()) else {
+            return false;
+        };
+        matches!(file.try_lock_shared(), Err(TryLockError::WouldBlock))
+    }
+}
+
+/// Record jobs run by hand (e.g. `tempera propagate`), so the background
+/// schedule doesn't repeat them. Best effort.
+pub fn mark_done(jobs: &[Job]) {
+    if let Ok(maintenance) = Maintenance::new() {
+        let _ = maintenance.record(jobs, Utc::now());
+    }
+}
+
+/// Spawn a detached `maintain` process when auto-maintenance is on, a job is
+/// overdue and no other process is already running them. Best effort;
+/// returns whether a process was started.
+pub fn spawn_if_due(config: &Config) -> bool {
+    if !config.maintenance.auto {
+        return false;
+    }
+    let Ok(maintenance) = Maintenance::new() else {
+        return false;
+    };
+    if maintenance.is_locked() {
+        return false;
+    }
+    let due = maintenance
+        .load_state()
+        .due(config, Utc::now())
+        .unwrap_or_default();
+    if due.is_empty() {
+        return false;
+    }
+
+    let Ok(exe) = std::env::current_exe() else {
+        return false;
+    };
+    match Command::new(exe)
+        .arg("maintain")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+    {
+        Ok(mut child) => {
+            // Reap it when it exits, should this process outlive it
+            std::thread::spawn(move || child.wait());
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Run overdue jobs (every enabled job with `force`) under the lock.
+/// Returns the jobs that completed, or None when another process holds the
+/// lock. A failing job is reported and the rest still run.
+pub async fn run_due(config: &Config, force: bool) -> Result<Option<Vec<Job>>> {
+    let maintenance = Maintenance::new()?;
+    let Some(_lock) = maintenance.try_lock()? else {
+        return Ok(None);
+    };
+
+    let state = maintenance.load_state();
+    let due = if force {
+        let mut enabled = Vec::new();
+        for job in Job::ALL {
+            if state.next_due(job, config)?.is_some() {
+                enabled.push(job);
+            }
+        }
+        enabled
+    } else {
+        state.due(config, Utc::now())?
+    };
+
+    let mut completed = Vec::new();
+    for job in &due {
+        // Propagation decays first; no need to decay twice
+        if *job == Job::Decay && due.contains(&Job::Bellman) {
+            continue;
+        }
+        println!("🔧 Running {}...", job);
+        match run_job(*job, config).await {
+            Ok(jobs) => {
+                maintenance.record(&jobs, Utc::now())?;
+                completed.extend(jobs);
+            }
+            Err(e) => {
+                eprintln!("⚠️  {} failed: {}", job, e);
+                maintenance.record_failure(*job, Utc::now())?;
+            }
+        }
+    }
+    Ok(Some(completed))
+}
+
+/// Run one job; returns the jobs it covered
+async fn run_job(job: Job, config: &Config) -> Result<Vec<Job>> {
+    let store = EpisodeStore::new()?;
+    match job {
+        Job::Reindex => {
+            let mut indexer = EpisodeIndexer::new().await?;
+            indexer.index_all(false).await?;
+            Ok(vec![Job::Reindex])
+        }
+        Job::Decay => {
+            let mut run = PropagationRun::new(&store, false)?;
+            utility::run_decay(&mut run, config)?;
+            Ok(vec![Job::Decay])
+        }
+        Job::Bellman => {
+            let mut run = PropagationRun::new(&store, false)?;
+            utility::run_propagation(&mut run, config).await?;
+            Ok(vec![Job::Decay, Job::Bellman])
+        }
+        Job::TemporalCredit => {
+            let mut run = PropagationRun::new(&store, false)?;
+            let params = UtilityParams::from_config(config);
+            utility::temporal_credit_assignment(&mut run, None, &params, config)?;
+            Ok(vec![Job::TemporalCredit])
+        }
+        Job::Prune => {
+            utility::prune_episodes(&store, None, None, false, config)?;
+            Ok(vec![Job::Prune])
+        }
+    }
+}
+
+/// Run the maintain command
+pub async fn run(force: bool, status: bool, config: &Config) -> Result<()> {
+    if status {
+        return show_status(config);
+    }
+
+    match run_due(config, force).await? {
+        None => println!(
+            "{}",
+            "Maintenance is already running in another process".dimmed()
+        ),
+        Some(jobs) if jobs.is_empty() => println!("Nothing due"),
+        Some(jobs) => {
+            let names: Vec<String> = jobs.iter().map(|j| j.to_string()).collect();
+            println!("✅ Maintenance complete: {}", names.join(", "));
+        }
+    }
+    Ok(())
+}
+
+fn show_status(config: &Config) -> Result<()> {
+    let maintenance = Maintenance::new()?;
+    let state = maintenance.load_state();
+    let now = Utc::now();
+
+    println!("{}", "🔧 Maintenance".bold());
+    println!(
+        "Background runs: {}{}\n",
+        if config.maintenance.auto { "on" } else { "off" },
+        if maintenance.is_locked() {
+            " (running now)"
+        } else {
+            ""
+        }
+    );
+    for job in Job::ALL {
+        let last = state
+            .last_run
+            .get(&job)
+            .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "never".to_string());
+        let next = match state.next_due(job, config)? {
+            None => "disabled".to_string(),
+            Some(at) if at <= now => "due".to_string(),
+            Some(at) if state.last_failure.contains_key(&job) => {
+                format!("failed, retry {}", at.format("%Y-%m-%d %H:%M"))
+            }
+            Some(at) => format!("next {}", at.format("%Y-%m-%d %H:%M")),
+        };
+        println!(
+            "  {:<16} every {:<8} last {:<17} {}",
+            job.to_string(),
+            job.interval(config),
+            last,
+            next
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(parse_interval("daily").unwrap(), Some(Duration::days(1)));
+        assert_eq!(parse_interval("Hourly").unwrap(), Some(Duration::hours(1)));
+        assert_eq!(parse_interval("6h").unwrap(), Some(Duration::hours(6)));
+        assert_eq!(parse_interval("2w").unwrap(), Some(Duration::weeks(2)));
+        assert_eq!(parse_interval("never").unwrap(), None);
+        assert!(parse_interval("0d").is_err());
+        assert!(parse_interval("5x").is_err());
+        assert!(parse_interval("").is_err());
+    }
+
+    #[test]
+    fn test_due_jobs() {
+        let config = Config::default();
+        let now = Utc::now();
+        let mut state = MaintenanceState::default();
+
+        // Nothing has run: every enabled job is due, prune is off by default
+        assert_eq!(
+            state.due(&config, now).unwrap(),
+            vec![Job::Reindex, Job::Decay, Job::Bellman, Job::TemporalCredit]
+        );
+
+        for job in Job::ALL {
+            state.last_run.insert(job, now - Duration::hours(2));
+        }
+        state
+            .last_run
+            .insert(Job::Bellman, now - Duration::hours(25));
+        assert_eq!(state.due(&config, now).unwrap(), vec![Job::Bellman]);
+    }
+
+    #[test]
+    fn test_failed_job_backs_off() {
+        let dir = tempfile::tempdir().unwrap();
+        let maintenance = Maintenance::with_dir(dir.path().to_path_buf());
+        let config = Config::default();
+        let now = Utc::now();
+
+        maintenance.record_failure(Job::Reindex, now).unwrap();
+        let state = maintenance.load_state();
+        assert!(!state.due(&config, now).unwrap().contains(&Job::Reindex));
+        let retry = now + FAILURE_BACKOFF;
+        assert!(state.due(&config, retry).unwrap().contains(&Job::Reindex));
+
+        // Success clears the failure
+        maintenance.record(&[Job::Reindex], retry).unwrap();
+        assert!(maintenance.load_state().last_failure.is_empty());
+    }
+
+    #[test]
+    fn test_state_round_trip_and_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let maintenance = Maintenance::with_dir(dir.path().to_path_buf());
+        assert!(maintenance.load_state().last_run.is_empty());
+
+        let at = Utc::now();
+        maintenance.record(&[Job::Decay, Job::Bellman], at).unwrap();
+        let state = maintenance.load_state();
+        assert_eq!(state.last_run.get(&Job::Bellman), Some(&at));
+        assert!(!state.last_run.contains_key(&Job::Prune));
+
+        let lock = maintenance.try_lock().unwrap().expect("lock");
+        assert!(maintenance.is_locked());
+        assert!(maintenance.try_lock().unwrap().is_none());
+        drop(lock);
+        assert!(!maintenance.is_locked());
+
+        // Released when the holder goes away, leaving the file in place
+        assert!(maintenance.lock_path().exists());
+        assert!(maintenance.try_lock().unwrap().is_some());
+    }
+}
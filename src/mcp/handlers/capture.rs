@@ -3,8 +3,8 @@
 
 use serde_json::Value;
 
-use crate::mcp::helpers::{extract_project, extract_string_array};
-use crate::{config, episode, indexer, store, utility};
+use crate::mcp::helpers::{extract_project, extract_string_array, extract_test_results};
+use crate::{config, episode, implicit_feedback, indexer, store, utility};
 
 /// Capture a new episode, consolidating with existing BKMs when similar
 pub(crate) async fn handle(args: &Value) -> Result<String, String> {
@@ -40,12 +40,23 @@ pub(crate) async fn handle(args: &Value) -> Result<String, String> {
         _ => episode::TaskType::Unknown,
     };
 
-    let outcome = match outcome_str {
+    let status = match outcome_str {
         "success" => episode::OutcomeStatus::Success,
         "partial" => episode::OutcomeStatus::Partial,
         "failure" => episode::OutcomeStatus::Failure,
         _ => episode::OutcomeStatus::Partial,
     };
+    let outcome = episode::Outcome {
+        status,
+        tests_before: extract_test_results(args, "tests_before"),
+        tests_after: extract_test_results(args, "tests_after"),
+        commit_sha: None,
+        pr_number: None,
+    };
+    // Results are only kept as a before/after pair
+    if outcome.tests_before.is_some() && outcome.tests_after.is_none() {
+        return Err("tests_before requires tests_after".to_string());
+    }
 
     // Parse errors if provided
     let mut errors = Vec::new();
@@ -88,7 +99,7 @@ pub(crate) async fn handle(args: &Value) -> Result<String, String> {
     // No consolidation match — create new episode
     let mut ep = episode::Episode::new(project.clone(), summary.to_string());
     ep.intent.task_type = task_type;
-    ep.outcome.status = outcome;
+    ep.outcome.update_from(&outcome);
     ep.context.files_modified = files_modified;
     ep.intent.domain = tags;
     ep.intent.extracted_intent = summary.to_string();
@@ -120,12 +131,25 @@ pub(crate) async fn handle(args: &Value) -> Result<String, String> {
     if let Some(sid) = &ep.session_id {
         output.push_str(&format!("- Session: {}\n", &sid[..8]));
     }
+    if let Some(after) = &ep.outcome.tests_after {
+        match &ep.outcome.tests_before {
+            Some(before) => output.push_str(&format!("- Tests: {} → {}\n", before, after)),
+            None => output.push_str(&format!("- Tests: {}\n", after)),
+        }
+    }
+    output.push_str(&implicit_feedback_note(&store, &ep));
 
     // Auto-propagate utility to spread value
     output.push_str("\n📈 Running auto-propagation...\n");
     let cfg = config::Config::load().unwrap_or_default();
     let params = utility::UtilityParams::from_config(&cfg);
-    match utility::run_bellman_propagation(&store, &params, Some(project.as_str())).await {
+    let propagation = match utility::PropagationRun::new(&store, false) {
+        Ok(mut run) => {
+            utility::run_bellman_propagation(&mut run, &params, Some(project.as_str())).await
+        }
+        Err(e) => Err(e),
+    };
+    match propagation {
         Ok(r) => output.push_str(&format!(
             "  Propagated value to {} episode(s)\n",
             r.propagated
@@ -145,7 +169,7 @@ async fn try_consolidate(
     summary: &str,
     project: &str,
     task_type: &episode::TaskType,
-    outcome: &episode::OutcomeStatus,
+    outcome: &episode::Outcome,
     tags: &[String],
     files_modified: &[String],
     errors: &[episode::ErrorRecord],
@@ -185,9 +209,9 @@ async fn try_consolidate(
     existing.intent.extracted_intent = summary.to_string();
     existing.intent.raw_prompt = summary.to_string();
 
-    // Update task type and outcome from latest capture
+    // Update task type, outcome and test results from latest capture
     existing.intent.task_type = task_type.clone();
-    existing.outcome.status = outcome.clone();
+    existing.outcome.update_from(outcome);
 
     // Union-merge tags
     for tag in tags {
@@ -231,6 +255,7 @@ async fn try_consolidate(
         existing.outcome.status,
         existing.intent.domain.join(", ")
     );
+    output.push_str(&implicit_feedback_note(store, &existing));
 
     output
         .push_str("\nExisting episode refined with new insights instead of creating a duplicate.");
@@ -244,7 +269,7 @@ fn try_tag_consolidate(
     summary: &str,
     project: &str,
     task_type: &episode::TaskType,
-    outcome: &episode::OutcomeStatus,
+    outcome: &episode::Outcome,
     tags: &[String],
     files_modified: &[String],
     errors: &[episode::ErrorRecord],
@@ -278,7 +303,7 @@ fn try_tag_consolidate(
     existing.intent.extracted_intent = summary.to_string();
     existing.intent.raw_prompt = summary.to_string();
     existing.intent.task_type = task_type.clone();
-    existing.outcome.status = outcome.clone();
+    existing.outcome.update_from(outcome);
 
     for tag in tags {
         if !existing.intent.domain.contains(tag) {
@@ -310,12 +335,33 @@ fn try_tag_consolidate(
         existing.outcome.status,
         existing.intent.domain.join(", ")
     );
+    output.push_str(&implicit_feedback_note(store, &existing));
 
     output
         .push_str("\nExisting episode refined with new insights instead of creating a duplicate.");
     Some(output)
 }
 
+/// Infer feedback for episodes retrieved earlier in this session, one output
+/// line per credited episode
+fn implicit_feedback_note(store: &store::EpisodeStore, captured: &episode::Episode) -> String {
+    let cfg = config::Config::load().unwrap_or_default();
+    match implicit_feedback::record_capture(store, captured, &cfg) {
+        Ok(inferred) => inferred
+            .iter()
+            .map(|(id, implicit)| {
+                let signals: Vec<String> = implicit.signals.iter().map(|s| s.to_string()).collect();
+                format!(
+                    "- Implicit feedback: {} ({})\n",
+                    &id[..8.min(id.len())],
+                    signals.join(", ")
+                )
+            })
+            .collect(),
+        Err(e) => format!("- Implicit feedback skipped: {}\n", e),
+    }
+}
+
 /// Resolve the session_id for a new episode.
 /// If an explicit session_id is provided, use it.
 /// Otherwise, check if the most recent same-project episode was captured within 2 hours —
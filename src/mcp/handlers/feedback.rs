@@ -3,56 +3,83 @@
 
 use serde_json::Value;
 
+use crate::episode::MAX_FEEDBACK_GRADE;
+use crate::events::EventLog;
+use crate::feedback;
 use crate::store;
 
-/// Record feedback on episodes
+/// Record feedback on episodes, bound to a retrieval event when one is given
 pub(crate) async fn handle(args: &Value) -> Result<String, String> {
-    let episode_ids: Vec<String> = args
-        .get("episode_ids")
-        .and_then(|v| v.as_array())
-        .map(|arr| {
-            arr.iter()
-                .filter_map(|v| v.as_str().map(String::from))
-                .collect()
-        })
-        .ok_or("Missing episode_ids parameter")?;
+    let requested: Option<Vec<String>> =
+        args.get("episode_ids")
+            .and_then(|v| v.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v.as_str().map(String::from))
+                    .collect()
+            });
+
+    // A grade takes precedence over the boolean
+    let mut feedback = match (
+        args.get("grade").and_then(|v| v.as_u64()),
+        args.get("helpful").and_then(|v| v.as_bool()),
+    ) {
+        (Some(grade), _) => feedback::Feedback {
+            grade: Some(grade.min(MAX_FEEDBACK_GRADE as u64) as u8),
+            aspects: vec![],
+        },
+        (None, Some(helpful)) => feedback::Feedback::from_helpful(helpful),
+        (None, None) => return Err("Provide grade (0-3) or helpful".to_string()),
+    };
+    if let Some(aspects) = args.get("aspects").and_then(|v| v.as_array()) {
+        let names: Vec<&str> = aspects.iter().filter_map(|v| v.as_str()).collect();
+        feedback.aspects = feedback::parse_aspects(&names.join(",")).map_err(|e| e.to_string())?;
+    }
 
-    let helpful = args
-        .get("helpful")
-        .and_then(|v| v.as_bool())
-        .ok_or("Missing helpful parameter")?;
+    let log = EventLog::new().map_err(|e| e.to_string())?;
+    let event = match args.get("retrieval_id").and_then(|v| v.as_str()) {
+        Some(id) => Some(log.resolve(id).map_err(|e| e.to_string())?),
+        None => None,
+    };
 
     let store = store::EpisodeStore::new().map_err(|e| e.to_string())?;
-    let mut updated = 0;
-
-    for id in &episode_ids {
-        if let Ok(episodes) = store.list_all() {
-            for ep in episodes {
-                if ep.id.starts_with(id) || ep.id[..8] == *id {
-                    let mut episode = ep.clone();
-
-                    if helpful {
-                        episode.utility.helpful_count += 1;
-                    }
-                    episode.utility.score = Some(episode.utility.calculate_score());
-
-                    if let Some(last) = episode.retrieval_history.last_mut() {
-                        last.was_helpful = Some(helpful);
-                    }
-
-                    if store.update(&episode).is_ok() {
-                        updated += 1;
-                    }
-                    break;
-                }
-            }
+
+    let episode_ids: Vec<String> = match (&requested, &event) {
+        (Some(ids), Some(event)) => {
+            feedback::select_event_episodes(event, ids).map_err(|e| e.to_string())?
         }
-    }
+        (None, Some(event)) => event.episode_ids(),
+        (Some(ids), None) => {
+            // Unbound feedback: resolve ID prefixes against the store
+            let all = store.list_all().map_err(|e| e.to_string())?;
+            ids.iter()
+                .filter_map(|id| {
+                    all.iter()
+                        .find(|ep| ep.id.starts_with(id.as_str()))
+                        .map(|ep| ep.id.clone())
+                })
+                .collect()
+        }
+        (None, None) => return Err("Provide episode_ids or retrieval_id".to_string()),
+    };
+
+    let retrieval_id = event.as_ref().map(|e| e.id.as_str());
+    let updated = episode_ids
+        .iter()
+        .filter(|id| feedback::update_episode_feedback(&store, id, &feedback, retrieval_id).is_ok())
+        .count();
+
+    feedback::record_feedback(&log, &episode_ids, &feedback, event.as_ref())
+        .map_err(|e| e.to_string())?;
 
-    let feedback_type = if helpful { "helpful" } else { "not helpful" };
+    let feedback_type = feedback.describe();
+    let bound = event
+        .as_ref()
+        .map(|e| format!(" for retrieval {}", &e.id[..8]))
+        .unwrap_or_default();
     Ok(format!(
-        "Feedback recorded: {} episode(s) marked as {}.\n\
+        "Feedback recorded{}: {} episode(s) marked as {}.\n\
          This helps improve future retrieval quality.",
-        updated, feedback_type
+        bound, updated, feedback_type
     ))
 }
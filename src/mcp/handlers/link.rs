@@ -0,0 +1,58 @@
+// Copyright 2024-2026 Andrey Vasilevsky <anvanster@gmail.com>
+// SPDX-License-Identifier: Apache-2.0
+
+use serde_json::Value;
+
+use crate::episode::EpisodeRelation;
+use crate::{link, store};
+
+/// Link or unlink two episodes
+pub(crate) async fn handle(args: &Value) -> Result<String, String> {
+    let from = args
+        .get("from")
+        .and_then(|v| v.as_str())
+        .ok_or("Missing from parameter")?;
+    let to = args
+        .get("to")
+        .and_then(|v| v.as_str())
+        .ok_or("Missing to parameter")?;
+    let action = args
+        .get("action")
+        .and_then(|v| v.as_str())
+        .unwrap_or("link");
+
+    let store = store::EpisodeStore::new().map_err(|e| e.to_string())?;
+
+    if action == "unlink" {
+        let removed = link::unlink(&store, from, to).map_err(|e| e.to_string())?;
+        return Ok(if removed {
+            format!("Unlinked {} and {}.", from, to)
+        } else {
+            format!("No link between {} and {}.", from, to)
+        });
+    }
+
+    let relation_name = args
+        .get("relation")
+        .and_then(|v| v.as_str())
+        .unwrap_or("related");
+    let relation = EpisodeRelation::parse(relation_name).ok_or_else(|| {
+        format!(
+            "Unknown relation: {}. Use continuation, prerequisite, alternative or related.",
+            relation_name
+        )
+    })?;
+
+    let (from, to) = link::link(&store, from, to, relation).map_err(|e| e.to_string())?;
+    Ok(format!(
+        "Linked {} → {} as {}{}.",
+        &from.id[..8],
+        &to.id[..8],
+        relation,
+        if relation.is_symmetric() {
+            " (both ways)"
+        } else {
+            ""
+        }
+    ))
+}
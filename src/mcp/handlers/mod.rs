@@ -3,6 +3,7 @@
 
 pub(crate) mod capture;
 pub(crate) mod feedback;
+pub(crate) mod link;
 pub(crate) mod propagate;
 pub(crate) mod retrieve;
 pub(crate) mod review;
@@ -3,7 +3,7 @@
 
 use serde_json::Value;
 
-use crate::{config, indexer, store, utility};
+use crate::{config, indexer, maintenance, store, utility};
 
 /// Run utility propagation
 pub(crate) async fn handle(args: &Value) -> Result<String, String> {
@@ -53,7 +53,8 @@ pub(crate) async fn handle(args: &Value) -> Result<String, String> {
     ));
 
     // Run Bellman propagation using vector similarity
-    let bellman = utility::run_bellman_propagation(&store, &params, project_filter)
+    let mut run = utility::PropagationRun::new(&store, false).map_err(|e| e.to_string())?;
+    let bellman = utility::run_bellman_propagation(&mut run, &params, project_filter)
         .await
         .unwrap_or(utility::BellmanResult {
             propagated: 0,
@@ -68,9 +69,26 @@ pub(crate) async fn handle(args: &Value) -> Result<String, String> {
         bellman.hops_executed,
         if bellman.converged { ", converged" } else { "" }
     ));
+
+    // Spread penalties from consistently unhelpful episodes
+    let penalty = utility::run_penalty_propagation(&mut run, &params, project_filter)
+        .await
+        .unwrap_or(utility::BellmanResult {
+            propagated: 0,
+            total_change: 0.0,
+            hops_executed: 0,
+            converged: true,
+        });
+    if penalty.propagated > 0 {
+        output.push_str(&format!(
+            "  🔻 Penalized {} episodes near unhelpful ones\n",
+            penalty.propagated
+        ));
+    }
+
     output.push_str(&format!(
         "  📊 Total utility change: {:+.3}\n",
-        bellman.total_change
+        bellman.total_change + penalty.total_change
     ));
 
     // Temporal credit assignment
@@ -78,7 +96,7 @@ pub(crate) async fn handle(args: &Value) -> Result<String, String> {
         output.push_str("\n⏱️  Running temporal credit assignment...\n");
 
         let credited =
-            utility::temporal_credit_assignment(&store, project_filter, &params, &config)
+            utility::temporal_credit_assignment(&mut run, project_filter, &params, &config)
                 .map_err(|e| e.to_string())?;
 
         output.push_str(&format!("  ✅ Credited {} episodes\n", credited));
@@ -93,6 +111,13 @@ pub(crate) async fn handle(args: &Value) -> Result<String, String> {
         output.push_str("  💾 Synced to vector index\n");
     }
 
+    if project_filter.is_none() {
+        maintenance::mark_done(&[maintenance::Job::Bellman]);
+        if temporal {
+            maintenance::mark_done(&[maintenance::Job::TemporalCredit]);
+        }
+    }
+
     output.push_str("\n✅ Propagation complete!");
 
     Ok(output)
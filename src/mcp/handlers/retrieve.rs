@@ -3,7 +3,9 @@
 
 use serde_json::Value;
 
-use crate::mcp::helpers::record_mcp_retrieval;
+use crate::error_signature::ErrorSignature;
+use crate::events::RetrievalEvent;
+use crate::query::EpisodeQuery;
 use crate::{config, retrieve, store};
 
 /// Retrieve relevant episodes
@@ -12,17 +14,28 @@ pub(crate) async fn handle(args: &Value) -> Result<String, String> {
     let limit = args.get("limit").and_then(|v| v.as_u64()).unwrap_or(5) as usize;
     let project = args.get("project").and_then(|v| v.as_str());
     let list_all = args.get("all").and_then(|v| v.as_bool()).unwrap_or(false);
+    let explain = args
+        .get("explain")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
 
     let config = config::Config::load().map_err(|e| e.to_string())?;
     let store = store::EpisodeStore::new().map_err(|e| e.to_string())?;
 
     // Case 1: List all episodes
     if list_all {
-        return list_all_episodes(&store, limit, project);
+        return list_all_episodes(&store, limit, query, project);
+    }
+
+    // Error-signature search: match against stored errors and return resolutions
+    if let Some(error) = args.get("error").and_then(|v| v.as_str()) {
+        return search_errors(&store, error, limit, project, &config);
     }
 
     // Need query for other cases
-    let query = query.ok_or("Missing query parameter (or use all: true to list episodes)")?;
+    let query = query.ok_or(
+        "Missing query parameter (or use all: true to list episodes, error: <message> to search errors)",
+    )?;
 
     // Case 2: Query looks like an episode ID - show full details
     if looks_like_episode_id(query) {
@@ -32,17 +45,13 @@ pub(crate) async fn handle(args: &Value) -> Result<String, String> {
         // If not found by ID, fall through to search
     }
 
-    // Case 3: Semantic search
-    let episodes = match retrieve::try_vector_search(query, limit, project, &config).await {
-        Ok(eps) if !eps.is_empty() => eps,
-        _ => {
-            // Fallback to text search
-            retrieve::retrieve_episodes_text(query, limit, project, &config, &store)
-                .map_err(|e| e.to_string())?
-        }
-    };
+    // Case 3: Semantic search (falls back to text search)
+    let outcome = retrieve::search(query, limit, project, &config, &store)
+        .await
+        .map_err(|e| e.to_string())?;
+    let episodes = &outcome.episodes;
 
-    if episodes.is_empty() {
+    if outcome.is_empty() && !explain {
         return Ok("No relevant episodes found in memory.".to_string());
     }
 
@@ -90,6 +99,10 @@ pub(crate) async fn handle(args: &Value) -> Result<String, String> {
             output.push_str(&format!("   - Tags: {}\n", ep.intent.domain.join(", ")));
         }
 
+        if !ep.related_episodes.is_empty() {
+            output.push_str(&format!("   - Linked: {}\n", ep.format_relations()));
+        }
+
         // Show resolved errors if any
         let resolved: Vec<_> = ep
             .context
@@ -112,7 +125,7 @@ pub(crate) async fn handle(args: &Value) -> Result<String, String> {
 
     // Surface session context: if any retrieved episode has a session, mention related episodes
     let mut session_context_shown = std::collections::HashSet::new();
-    for scored in &episodes {
+    for scored in episodes {
         if let Some(sid) = &scored.episode.session_id {
             if session_context_shown.insert(sid.clone()) {
                 if let Ok(session_eps) = store.list_by_session(sid) {
@@ -138,14 +151,158 @@ pub(crate) async fn handle(args: &Value) -> Result<String, String> {
         }
     }
 
-    output.push_str("Use tempera_feedback to indicate if these were helpful.");
+    if !outcome.mistakes.is_empty() {
+        output.push_str(&format_mistakes(&outcome.mistakes));
+    }
+
+    if explain {
+        output.push_str(&format_explain(&outcome));
+    }
 
     // Record retrieval for tracking
-    let _ = record_mcp_retrieval(&episodes, query, &store);
+    let retrieved: Vec<_> = episodes
+        .iter()
+        .chain(&outcome.mistakes)
+        .map(|s| (&s.episode, s.combined_score))
+        .collect();
+    let event = retrieve::record_retrievals(&retrieved, query, &store, &config)
+        .ok()
+        .flatten();
+    output.push_str(&feedback_hint(event.as_ref()));
 
     Ok(output)
 }
 
+/// "Mistakes to avoid" section for failure episodes
+fn format_mistakes(mistakes: &[retrieve::ScoredEpisode]) -> String {
+    let mut output = format!("⚠ Mistakes to avoid ({}):\n\n", mistakes.len());
+
+    for scored in mistakes {
+        let ep = &scored.episode;
+        output.push_str(&format!(
+            "- **{}**\n",
+            if ep.intent.extracted_intent.is_empty() {
+                &ep.intent.raw_prompt
+            } else {
+                &ep.intent.extracted_intent
+            }
+        ));
+        output.push_str(&format!(
+            "   - ID: {} | Project: {} | Outcome: {}\n",
+            &ep.id[..8],
+            ep.project,
+            ep.outcome.status
+        ));
+        output.push_str(&format!(
+            "   - Relevance: {:.0}% similarity\n",
+            scored.similarity_score * 100.0
+        ));
+        let unresolved: Vec<_> = ep
+            .context
+            .errors_encountered
+            .iter()
+            .filter(|e| !e.resolved)
+            .collect();
+        if !unresolved.is_empty() {
+            output.push_str("   - Unresolved errors:\n");
+            for err in unresolved.iter().take(2) {
+                output.push_str(&format!("     - {}\n", err.message));
+            }
+        }
+        output.push('\n');
+    }
+
+    output
+}
+
+/// Search stored errors by signature
+fn search_errors(
+    store: &store::EpisodeStore,
+    error: &str,
+    limit: usize,
+    project: Option<&str>,
+    config: &config::Config,
+) -> Result<String, String> {
+    let signature = ErrorSignature::from_message(error);
+    let matches =
+        retrieve::search_errors(&signature, limit, project, store).map_err(|e| e.to_string())?;
+
+    if matches.is_empty() {
+        return Ok(format!("No stored errors match signature: {}", signature));
+    }
+
+    let mut output = format!(
+        "Found {} matching error(s) (signature: {}):\n\n",
+        matches.len(),
+        signature
+    );
+
+    for (i, m) in matches.iter().enumerate() {
+        let ep = &m.episode;
+        output.push_str(&format!("{}. **{}**\n", i + 1, m.error.message));
+        output.push_str(&format!("   - Episode: {} ({})\n", &ep.id[..8], ep.project));
+        output.push_str(&format!("   - Match: {:.0}%\n", m.similarity * 100.0));
+        match (&m.error.resolution, m.error.resolved) {
+            (Some(resolution), _) => output.push_str(&format!("   - Resolution: {}\n", resolution)),
+            (None, true) => output.push_str(&format!(
+                "   - Resolved in: {}\n",
+                if ep.intent.extracted_intent.is_empty() {
+                    &ep.intent.raw_prompt
+                } else {
+                    &ep.intent.extracted_intent
+                }
+            )),
+            (None, false) => output.push_str("   - Unresolved\n"),
+        }
+        output.push('\n');
+    }
+
+    let retrieved: Vec<_> = matches.iter().map(|m| (&m.episode, m.similarity)).collect();
+    let event =
+        retrieve::record_retrievals(&retrieved, &format!("error: {}", signature), store, config)
+            .ok()
+            .flatten();
+    output.push_str(&feedback_hint(event.as_ref()));
+
+    Ok(output)
+}
+
+/// Closing line pointing tempera_feedback at this retrieval
+fn feedback_hint(event: Option<&RetrievalEvent>) -> String {
+    match event {
+        Some(event) => format!(
+            "Retrieval ID: {}. Use tempera_feedback with this retrieval_id to indicate if these were helpful.",
+            &event.id[..8]
+        ),
+        None => "Use tempera_feedback to indicate if these were helpful.".to_string(),
+    }
+}
+
+/// Score breakdown and dropped candidates for `explain: true`
+fn format_explain(outcome: &retrieve::RetrievalOutcome) -> String {
+    let mut output = format!(
+        "Score breakdown ({} search, min_similarity {:.2}):\n",
+        if outcome.semantic { "vector" } else { "text" },
+        outcome.min_similarity
+    );
+    for (i, scored) in outcome.episodes.iter().enumerate() {
+        output.push_str(&format!(
+            "{}. [{}] {}\n",
+            i + 1,
+            &scored.episode.id[..8],
+            retrieve::format_breakdown(&scored.breakdown)
+        ));
+    }
+    if !outcome.dropped.is_empty() {
+        output.push_str("\nDropped candidates:\n");
+        for dropped in &outcome.dropped {
+            output.push_str(&format!("   - {}\n", retrieve::format_dropped(dropped)));
+        }
+    }
+    output.push('\n');
+    output
+}
+
 /// Check if a string looks like an episode ID
 fn looks_like_episode_id(s: &str) -> bool {
     let s = s.trim();
@@ -162,16 +319,16 @@ fn looks_like_episode_id(s: &str) -> bool {
 fn list_all_episodes(
     store: &store::EpisodeStore,
     limit: usize,
+    query: Option<&str>,
     project: Option<&str>,
 ) -> Result<String, String> {
-    let mut episodes = store.list_all().map_err(|e| e.to_string())?;
-
-    if let Some(proj) = project {
-        episodes.retain(|e| e.project.to_lowercase().contains(&proj.to_lowercase()));
-    }
-
-    episodes.sort_by(|a, b| b.timestamp_start.cmp(&a.timestamp_start));
-    episodes.truncate(limit);
+    // An optional query narrows the listing with the same filters as search
+    let query = EpisodeQuery::parse(query.unwrap_or_default())
+        .map_err(|e| e.to_string())?
+        .with_project(project);
+    let episodes = store
+        .list_filtered(limit, &query)
+        .map_err(|e| e.to_string())?;
 
     if episodes.is_empty() {
         return Ok("No episodes found in memory.".to_string());
@@ -221,6 +378,9 @@ fn show_episode_by_id(store: &store::EpisodeStore, id: &str) -> Result<Option<St
         None => return Ok(None),
     };
 
+    // Opening an episode by ID counts as using it, separate from being shown
+    retrieve::record_use(ep, store).map_err(|e| e.to_string())?;
+
     let mut output = String::from("Episode Details\n");
     output.push_str("===============\n\n");
 
@@ -252,6 +412,26 @@ fn show_episode_by_id(store: &store::EpisodeStore, id: &str) -> Result<Option<St
         output.push_str(&format!("**Tags**: {}\n\n", ep.intent.domain.join(", ")));
     }
 
+    let incoming = crate::link::incoming(store, &ep.id).map_err(|e| e.to_string())?;
+    if !ep.related_episodes.is_empty() || !incoming.is_empty() {
+        output.push_str("## Related Episodes\n");
+        for rel in &ep.related_episodes {
+            output.push_str(&format!(
+                "- {} ({})\n",
+                &rel.id[..8.min(rel.id.len())],
+                rel.relationship
+            ));
+        }
+        for (other, relation) in &incoming {
+            output.push_str(&format!(
+                "- {} ({}, linked from)\n",
+                &other.id[..8],
+                relation
+            ));
+        }
+        output.push('\n');
+    }
+
     if !ep.context.files_modified.is_empty() {
         output.push_str("## Files Modified\n");
         for f in &ep.context.files_modified {
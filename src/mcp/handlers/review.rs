@@ -29,7 +29,8 @@ pub(crate) async fn handle(args: &Value) -> Result<String, String> {
     output.push_str(&"=".repeat(22 + project.len()));
     output.push_str("\n\n");
 
-    // Find stale memories (old with low utility, thresholds from config)
+    // Find stale memories (old with low utility, thresholds from config, or
+    // marked outdated by their latest feedback)
     let stale_age = cfg.storage.stale_age_days as i64;
     let stale_util = cfg.storage.stale_utility_threshold;
     let stale: Vec<_> = project_episodes
@@ -37,9 +38,10 @@ pub(crate) async fn handle(args: &Value) -> Result<String, String> {
         .filter(|e| {
             let age_days = (chrono::Utc::now() - e.timestamp_start).num_days();
             let utility = e.utility.calculate_score();
-            age_days > stale_age && utility < stale_util
+            (age_days > stale_age && utility < stale_util) || e.marked_outdated()
         })
         .collect();
+    let outdated_count = stale.iter().filter(|e| e.marked_outdated()).count();
 
     // Find duplicate clusters using vector similarity (or Jaccard fallback)
     let clusters =
@@ -68,7 +70,10 @@ pub(crate) async fn handle(args: &Value) -> Result<String, String> {
 
     output.push_str("📊 Analysis Results:\n");
     output.push_str(&format!("  - Total memories: {}\n", project_episodes.len()));
-    output.push_str(&format!("  - Stale (>30d, low utility): {}\n", stale.len()));
+    output.push_str(&format!(
+        "  - Stale (>30d, low utility, or marked outdated): {}\n",
+        stale.len()
+    ));
     output.push_str(&format!(
         "  - Duplicate clusters: {} ({} episodes)\n",
         clusters.len(),
@@ -86,7 +91,12 @@ pub(crate) async fn handle(args: &Value) -> Result<String, String> {
         output.push_str("📅 Stale Memories:\n");
         for ep in stale.iter().take(5) {
             let summary: String = ep.intent.extracted_intent.chars().take(50).collect();
-            output.push_str(&format!("  - {} ({}...)\n", &ep.id[..8], summary));
+            let flag = if ep.marked_outdated() {
+                " [outdated]"
+            } else {
+                ""
+            };
+            output.push_str(&format!("  - {} ({}...){}\n", &ep.id[..8], summary, flag));
         }
         if stale.len() > 5 {
             output.push_str(&format!("  ... and {} more\n", stale.len() - 5));
@@ -256,6 +266,12 @@ pub(crate) async fn handle(args: &Value) -> Result<String, String> {
                         stale_no_engagement.len()
                     ));
                 }
+                if outdated_count > 0 {
+                    output.push_str(&format!(
+                        "  - {} memories marked outdated by feedback. Update or delete them.\n",
+                        outdated_count
+                    ));
+                }
             }
             if feedback_rate < 0.2 && total_retrievals > 5 {
                 output.push_str(&format!(
@@ -2,8 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use serde_json::Value;
+use std::collections::HashSet;
 
-use crate::{episode, stats, store};
+use crate::{episode, ledger, stats, store};
 
 /// Get memory statistics
 pub(crate) async fn handle(args: &Value) -> Result<String, String> {
@@ -92,5 +93,23 @@ pub(crate) async fn handle(args: &Value) -> Result<String, String> {
         output.push_str(&format!("  - {}: {}\n", task_type, count));
     }
 
+    // Utility changes per mechanism, from the ledger
+    let ids: HashSet<&str> = filtered.iter().map(|e| e.id.as_str()).collect();
+    let entries: Vec<_> = ledger::UtilityLedger::new()
+        .and_then(|l| l.entries())
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|e| ids.contains(e.episode_id.as_str()))
+        .collect();
+    if !entries.is_empty() {
+        output.push_str("\nUtility Changes by Mechanism:\n");
+        for (mechanism, summary) in ledger::summarize(&entries) {
+            output.push_str(&format!(
+                "  - {}: {} change(s) across {} episode(s), net {:+.3}\n",
+                mechanism, summary.changes, summary.episodes, summary.total_delta
+            ));
+        }
+    }
+
     Ok(output)
 }
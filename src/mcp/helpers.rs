@@ -4,6 +4,7 @@
 use anyhow::Result;
 use serde_json::Value;
 
+use crate::events::current_project;
 use crate::{episode, store};
 
 /// Extract project name from args or auto-detect from working directory
@@ -11,12 +12,7 @@ pub(crate) fn extract_project(args: &Value) -> String {
     args.get("project")
         .and_then(|v| v.as_str())
         .map(String::from)
-        .unwrap_or_else(|| {
-            std::env::current_dir()
-                .ok()
-                .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
-                .unwrap_or_else(|| "unknown".to_string())
-        })
+        .unwrap_or_else(current_project)
 }
 
 /// Extract a string array from JSON args
@@ -31,6 +27,17 @@ pub(crate) fn extract_string_array(args: &Value, field: &str) -> Vec<String> {
         .unwrap_or_default()
 }
 
+/// Extract test counts (`{passed, failed, skipped}`) from JSON args
+pub(crate) fn extract_test_results(args: &Value, field: &str) -> Option<episode::TestResults> {
+    let obj = args.get(field)?.as_object()?;
+    let count = |key: &str| obj.get(key).and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+    Some(episode::TestResults {
+        passed: count("passed"),
+        failed: count("failed"),
+        skipped: count("skipped"),
+    })
+}
+
 /// Load all episodes filtered by project (exact match)
 pub(crate) fn load_project_episodes(
     store: &store::EpisodeStore,
@@ -42,29 +49,3 @@ pub(crate) fn load_project_episodes(
         .filter(|e| e.project.to_lowercase() == project.to_lowercase())
         .collect())
 }
-
-/// Record retrieval for tracking
-pub(crate) fn record_mcp_retrieval(
-    episodes: &[crate::retrieve::ScoredEpisode],
-    query: &str,
-    store: &store::EpisodeStore,
-) -> Result<()> {
-    let project = std::env::current_dir()
-        .ok()
-        .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
-        .unwrap_or_else(|| "unknown".to_string());
-
-    for scored in episodes {
-        let mut episode = scored.episode.clone();
-        episode.retrieval_history.push(episode::RetrievalRecord {
-            timestamp: chrono::Utc::now(),
-            project: project.clone(),
-            task_description: query.to_string(),
-            was_helpful: None,
-        });
-        episode.utility.retrieval_count += 1;
-        store.update(&episode)?;
-    }
-
-    Ok(())
-}
@@ -16,6 +16,9 @@ pub(crate) struct McpServer {
 
 impl McpServer {
     pub fn new() -> Self {
+        // Each server instance is its own retrieval session, so "last" feedback
+        // from one client never lands on another client's retrieval
+        crate::events::start_process_session();
         Self { initialized: false }
     }
 
@@ -94,6 +97,7 @@ impl McpServer {
             "tempera_status" => handlers::status::handle(&arguments).await,
             "tempera_propagate" => handlers::propagate::handle(&arguments).await,
             "tempera_review" => handlers::review::handle(&arguments).await,
+            "tempera_link" => handlers::link::handle(&arguments).await,
             _ => Err(format!("Unknown tool: {}", name)),
         };
 
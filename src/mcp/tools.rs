@@ -16,7 +16,7 @@ pub(crate) fn tool_definitions() -> Vec<Tool> {
                 "properties": {
                     "query": {
                         "type": "string",
-                        "description": "Describe the challenge or pattern you're facing, not just the topic. Good: 'tree-sitter grammar producing ERROR nodes instead of expected AST'. Bad: 'fix codegraph-tcl'. An episode ID also works for full details."
+                        "description": "Describe the challenge or pattern you're facing, not just the topic. Good: 'tree-sitter grammar producing ERROR nodes instead of expected AST'. Bad: 'fix codegraph-tcl'. Supports filters: tag:rust outcome:failure type:bugfix since:30d project:api \"quoted phrase\". An episode ID also works for full details."
                     },
                     "limit": {
                         "type": "integer",
@@ -29,7 +29,16 @@ pub(crate) fn tool_definitions() -> Vec<Tool> {
                     },
                     "all": {
                         "type": "boolean",
-                        "description": "If true, list all episodes instead of searching (ignores query)",
+                        "description": "If true, list episodes instead of searching (query filters still apply, newest first)",
+                        "default": false
+                    },
+                    "error": {
+                        "type": "string",
+                        "description": "Paste a compiler or runtime error to find stored resolutions. Paths, line numbers and identifiers are ignored; error codes (E0599, TS2345) and exception types are matched."
+                    },
+                    "explain": {
+                        "type": "boolean",
+                        "description": "If true, append the score breakdown for each result and the candidates that were filtered out",
                         "default": false
                     }
                 },
@@ -84,6 +93,24 @@ pub(crate) fn tool_definitions() -> Vec<Tool> {
                     "session_id": {
                         "type": "string",
                         "description": "Link this episode to a session. Auto-detected if omitted: reuses session from recent same-project episodes (within 2 hours)."
+                    },
+                    "tests_before": {
+                        "type": "object",
+                        "properties": {
+                            "passed": { "type": "integer" },
+                            "failed": { "type": "integer" },
+                            "skipped": { "type": "integer" }
+                        },
+                        "description": "Test counts from the first test run of the session. Requires tests_after; tests fixed by the end become a measurable reward."
+                    },
+                    "tests_after": {
+                        "type": "object",
+                        "properties": {
+                            "passed": { "type": "integer" },
+                            "failed": { "type": "integer" },
+                            "skipped": { "type": "integer" }
+                        },
+                        "description": "Test counts from the last test run of the session"
                     }
                 },
                 "required": ["summary", "task_type", "outcome"]
@@ -91,21 +118,38 @@ pub(crate) fn tool_definitions() -> Vec<Tool> {
         },
         Tool {
             name: "tempera_feedback".to_string(),
-            description: "Record whether retrieved episodes actually influenced your approach. Call after using memories. 'Helpful' means the insight changed how you solved the problem — not just that it was topically related.".to_string(),
+            description: "Record whether retrieved episodes actually influenced your approach. Call after using memories. 'Helpful' means the insight changed how you solved the problem — not just that it was topically related. Pass the retrieval_id shown with the results (or \"last\") so feedback is attached to that exact retrieval. Use grade (0-3) for partial help and aspects to say why.".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
+                    "retrieval_id": {
+                        "type": "string",
+                        "description": "Retrieval the feedback is about: an ID from tempera_retrieve, or \"last\" for this session's most recent retrieval"
+                    },
                     "episode_ids": {
                         "type": "array",
                         "items": { "type": "string" },
-                        "description": "IDs of episodes to provide feedback on"
+                        "description": "IDs of episodes to provide feedback on (defaults to all results of retrieval_id)"
                     },
                     "helpful": {
                         "type": "boolean",
-                        "description": "Whether the episodes were helpful"
+                        "description": "Whether the episodes were helpful (shorthand for grade 3 or 0)"
+                    },
+                    "grade": {
+                        "type": "integer",
+                        "minimum": 0,
+                        "maximum": 3,
+                        "description": "Graded feedback: 0 not helpful, 1 marginal, 2 partly changed the approach, 3 changed the approach. Takes precedence over helpful"
+                    },
+                    "aspects": {
+                        "type": "array",
+                        "items": {
+                            "type": "string",
+                            "enum": ["relevant", "correct", "outdated", "too_vague"]
+                        },
+                        "description": "Specific qualities of the episodes. 'outdated' flags them for review"
                     }
-                },
-                "required": ["episode_ids", "helpful"]
+                }
             }),
         },
         Tool {
@@ -183,5 +227,33 @@ pub(crate) fn tool_definitions() -> Vec<Tool> {
                 }
             }),
         },
+        Tool {
+            name: "tempera_link".to_string(),
+            description: "Link two episodes so they surface together. Relations: 'prerequisite' (from was needed before to), 'continuation' (to is the next step of from), 'alternative' (different approach to the same problem), 'related'. Alternative and related links are stored both ways. Use action 'unlink' to remove links between two episodes.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "from": {
+                        "type": "string",
+                        "description": "Source episode ID (full or 8-char prefix)"
+                    },
+                    "to": {
+                        "type": "string",
+                        "description": "Target episode ID (full or 8-char prefix)"
+                    },
+                    "relation": {
+                        "type": "string",
+                        "enum": ["continuation", "prerequisite", "alternative", "related"],
+                        "default": "related"
+                    },
+                    "action": {
+                        "type": "string",
+                        "enum": ["link", "unlink"],
+                        "default": "link"
+                    }
+                },
+                "required": ["from", "to"]
+            }),
+        },
     ]
 }
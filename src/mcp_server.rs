@@ -18,11 +18,25 @@
 use anyhow::Result;
 use std::io::{self, BufRead, Write};
 
+mod capture;
 mod config;
 mod episode;
+mod error_signature;
+mod events;
+mod exploration;
 mod feedback;
+mod graph;
+mod implicit_feedback;
 mod indexer;
+mod jsonl;
+mod ledger;
+mod link;
+mod llm;
+mod maintenance;
 mod mcp;
+mod position_bias;
+mod query;
+mod reranker;
 mod retrieve;
 mod stats;
 mod store;
@@ -30,6 +44,17 @@ mod utility;
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let config = config::Config::load().unwrap_or_default();
+
+    // Background maintenance process spawned by `maintenance::spawn_if_due`
+    if std::env::args().nth(1).as_deref() == Some("maintain") {
+        maintenance::run_due(&config, false).await?;
+        return Ok(());
+    }
+
+    maintenance::spawn_if_due(&config);
+    let mut last_maintenance_check = std::time::Instant::now();
+
     let mut server = mcp::McpServer::new();
     let stdin = io::stdin();
     let mut stdout = io::stdout();
@@ -71,6 +96,12 @@ async fn main() -> Result<()> {
             writeln!(stdout, "{}", serde_json::to_string(&response)?)?;
             stdout.flush()?;
         }
+
+        // Long sessions pick up jobs that fall due while they run
+        if last_maintenance_check.elapsed() >= maintenance::CHECK_INTERVAL {
+            maintenance::spawn_if_due(&config);
+            last_maintenance_check = std::time::Instant::now();
+        }
     }
 
     Ok(())
@@ -0,0 +1,238 @@
+// Copyright 2024-2026 Andrey Vasilevsky <anvanster@gmail.com>
+// SPDX-License-Identifier: Apache-2.0
+
+#![allow(dead_code)]
+//! Position-bias estimates for feedback learning.
+//!
+//! Results lower in a list are less likely to be read, so an episode shown at
+//! rank 5 without feedback is weaker evidence than one shown at rank 1. The
+//! examination propensity of each rank is learned from the retrieval event
+//! log: the rate at which results at that rank receive any explicit feedback,
+//! relative to rank 1. Impressions are then weighted by their propensity
+//! (inverse-propensity weighting), see `Utility::record_impression`.
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+use crate::events::{EventLog, FeedbackEvent, LogRecord, RetrievalEvent};
+
+/// Ranks beyond this share the last bucket
+pub const MAX_RANK: usize = 10;
+
+/// Most recent retrievals to learn from; older behaviour matters less and
+/// learning stays cheap as the log grows
+pub const LEARNING_WINDOW: usize = 2000;
+
+/// How long a long-running process reuses propensities it learned
+const CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Propensities last learned by this process, per log and propensity floor
+static CACHE: Mutex<Option<(PathBuf, f32, Instant, PositionBias)>> = Mutex::new(None);
+
+/// Pseudo-count of impressions behind the prior at each rank; observed rates
+/// take over once a rank has been shown more often than this
+const PRIOR_STRENGTH: f32 = 20.0;
+
+/// Examination propensity per rank (rank 1 = 1.0)
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionBias {
+    propensities: Vec<f32>,
+}
+
+impl PositionBias {
+    /// Prior before any feedback is logged: propensity falls off as 1/sqrt(rank)
+    pub fn prior(min_propensity: f32) -> Self {
+        Self {
+            propensities: (1..=MAX_RANK)
+                .map(|rank| prior_propensity(rank).clamp(min_propensity.min(1.0), 1.0))
+                .collect(),
+        }
+    }
+
+    /// Learn propensities from logged retrievals and the feedback bound to them
+    pub fn learn(
+        retrievals: &[RetrievalEvent],
+        feedback: &[FeedbackEvent],
+        min_propensity: f32,
+    ) -> Self {
+        let mut with_feedback: HashMap<&str, HashSet<&str>> = HashMap::new();
+        for fb in feedback {
+            with_feedback
+                .entry(fb.retrieval_id.as_str())
+                .or_default()
+                .extend(fb.episode_ids.iter().map(String::as_str));
+        }
+
+        let mut shown = [0u32; MAX_RANK];
+        let mut answered = [0u32; MAX_RANK];
+        for event in retrievals {
+            let ids = with_feedback.get(event.id.as_str());
+            for result in &event.results {
+                let bucket = result.rank.clamp(1, MAX_RANK) - 1;
+                shown[bucket] += 1;
+                if ids.is_some_and(|ids| ids.contains(result.episode_id.as_str())) {
+                    answered[bucket] += 1;
+                }
+            }
+        }
+
+        // Without rank-1 feedback there's nothing to normalize against
+        if answered[0] == 0 {
+            return Self::prior(min_propensity);
+        }
+        let top_rate = answered[0] as f32 / shown[0] as f32;
+
+        let propensities = (0..MAX_RANK)
+            .map(|bucket| {
+                if bucket == 0 {
+                    return 1.0;
+                }
+                let n = shown[bucket] as f32;
+                let observed = if n > 0.0 {
+                    (answered[bucket] as f32 / n / top_rate).min(1.0)
+                } else {
+                    0.0
+                };
+                let prior = prior_propensity(bucket + 1);
+                ((n * observed + PRIOR_STRENGTH * prior) / (n + PRIOR_STRENGTH))
+                    .clamp(min_propensity.min(1.0), 1.0)
+            })
+            .collect();
+
+        Self { propensities }
+    }
+
+    /// Learn from the last `LEARNING_WINDOW` retrievals in the event log,
+    /// reading it once; falls back to the prior when the log can't be read.
+    /// The result is reused for `CACHE_TTL`, so a long-running server doesn't
+    /// re-read the log on every retrieval.
+    pub fn from_log(log: &EventLog, config: &Config) -> Self {
+        let min = config.retrieval.min_propensity;
+        let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((path, floor, learned_at, bias)) = cache.as_ref() {
+            if path == log.path() && *floor == min && learned_at.elapsed() < CACHE_TTL {
+                return bias.clone();
+            }
+        }
+
+        let Ok(records) = log.records() else {
+            return Self::prior(min);
+        };
+        let mut retrievals = Vec::new();
+        let mut feedback = Vec::new();
+        for record in records {
+            match record {
+                LogRecord::Retrieval(e) => retrievals.push(e),
+                LogRecord::Feedback(f) => feedback.push(f),
+            }
+        }
+        let recent = &retrievals[retrievals.len().saturating_sub(LEARNING_WINDOW)..];
+
+        let bias = Self::learn(recent, &feedback, min);
+        *cache = Some((log.path().to_path_buf(), min, Instant::now(), bias.clone()));
+        bias
+    }
+
+    /// Examination propensity for a 1-based rank
+    pub fn propensity(&self, rank: usize) -> f32 {
+        self.propensities[rank.clamp(1, MAX_RANK) - 1]
+    }
+}
+
+fn prior_propensity(rank: usize) -> f32 {
+    1.0 / (rank as f32).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::RetrievedResult;
+    use chrono::Utc;
+
+    fn retrieval(ids: &[&str]) -> RetrievalEvent {
+        RetrievalEvent::new(
+            "query",
+            "api",
+            ids.iter()
+                .enumerate()
+                .map(|(i, id)| RetrievedResult {
+                    episode_id: id.to_string(),
+                    rank: i + 1,
+                    score: 0.5,
+                })
+                .collect(),
+        )
+    }
+
+    fn feedback(event: &RetrievalEvent, ids: &[&str]) -> FeedbackEvent {
+        FeedbackEvent {
+            retrieval_id: event.id.clone(),
+            timestamp: Utc::now(),
+            helpful: Some(true),
+            episode_ids: ids.iter().map(|s| s.to_string()).collect(),
+            grade: Some(3),
+            aspects: vec![],
+        }
+    }
+
+    #[test]
+    fn test_prior_without_feedback() {
+        let bias = PositionBias::learn(&[retrieval(&["a", "b"])], &[], 0.1);
+        assert_eq!(bias, PositionBias::prior(0.1));
+        assert_eq!(bias.propensity(1), 1.0);
+        assert!(bias.propensity(2) < 1.0);
+        assert!(bias.propensity(5) < bias.propensity(2));
+        // Deep ranks share the last bucket and respect the floor
+        assert_eq!(bias.propensity(50), bias.propensity(MAX_RANK));
+        assert!(PositionBias::prior(0.5).propensity(MAX_RANK) >= 0.5);
+    }
+
+    #[test]
+    fn test_learned_from_feedback() {
+        // Rank 1 always gets feedback, rank 2 never does
+        let mut retrievals = Vec::new();
+        let mut fb = Vec::new();
+        for _ in 0..200 {
+            let event = retrieval(&["a", "b"]);
+            fb.push(feedback(&event, &["a"]));
+            retrievals.push(event);
+        }
+        let bias = PositionBias::learn(&retrievals, &fb, 0.05);
+        assert!(bias.propensity(2) < PositionBias::prior(0.05).propensity(2));
+        assert!(bias.propensity(2) < 0.2);
+
+        // Both ranks answered equally: no position bias
+        let fb: Vec<_> = retrievals
+            .iter()
+            .map(|e| feedback(e, &["a", "b"]))
+            .collect();
+        let bias = PositionBias::learn(&retrievals, &fb, 0.05);
+        assert!(bias.propensity(2) > 0.9);
+    }
+
+    #[test]
+    fn test_from_log() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let log = EventLog::with_path(temp.path().join("retrievals.jsonl"));
+        let mut config = Config::default();
+        config.retrieval.min_propensity = 0.05;
+
+        let mut retrievals = Vec::new();
+        let mut fb = Vec::new();
+        for _ in 0..50 {
+            let event = retrieval(&["a", "b"]);
+            log.append(&LogRecord::Retrieval(event.clone())).unwrap();
+            let f = feedback(&event, &["a"]);
+            log.append(&LogRecord::Feedback(f.clone())).unwrap();
+            retrievals.push(event);
+            fb.push(f);
+        }
+
+        let learned = PositionBias::from_log(&log, &config);
+        assert_eq!(learned, PositionBias::learn(&retrievals, &fb, 0.05));
+        assert_ne!(learned, PositionBias::prior(0.05));
+    }
+}
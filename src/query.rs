@@ -0,0 +1,360 @@
+// Copyright 2024-2026 Andrey Vasilevsky <anvanster@gmail.com>
+// SPDX-License-Identifier: Apache-2.0
+
+#![allow(dead_code)]
+//! Structured query language shared by `retrieve`, `list` and the MCP server.
+//!
+//! A query mixes `key:value` filters with free text:
+//!
+//! ```text
+//! tag:rust outcome:failure type:bugfix since:30d project:api "connection pool" timeout
+//! ```
+//!
+//! Supported keys are `tag` (repeatable, all must match), `outcome`, `type`,
+//! `since` (`12h`, `30d`, `2w` or `YYYY-MM-DD`) and `project`. Quoted text
+//! is kept together; anything that isn't a known filter is free text.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+
+use crate::episode::{Episode, OutcomeStatus, TaskType};
+
+/// A parsed query: structured filters plus free text for similarity search
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EpisodeQuery {
+    /// Free text with filters removed
+    pub text: String,
+    pub filter: EpisodeFilter,
+}
+
+/// Structured filters applied to episodes
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EpisodeFilter {
+    pub project: Option<String>,
+    pub tags: Vec<String>,
+    pub outcome: Option<OutcomeStatus>,
+    pub task_type: Option<TaskType>,
+    pub since: Option<DateTime<Utc>>,
+}
+
+impl EpisodeQuery {
+    /// Parse a query string. Fails on malformed filter values
+    /// (e.g. `outcome:maybe`), so typos don't silently match everything.
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut query = EpisodeQuery::default();
+        let mut text = Vec::new();
+
+        for token in tokenize(input) {
+            let parsed = match token.value.split_once(':') {
+                Some((key, value)) if !token.quoted && !value.trim_matches('"').is_empty() => {
+                    Some((key.to_lowercase(), value.trim_matches('"').to_string()))
+                }
+                _ => None,
+            };
+            let Some((key, value)) = parsed else {
+                text.push(token.value);
+                continue;
+            };
+            let value = value.as_str();
+
+            match key.as_str() {
+                "tag" | "tags" => query
+                    .filter
+                    .tags
+                    .extend(value.split(',').map(|t| t.trim().to_string())),
+                "project" => query.filter.project = Some(value.to_string()),
+                "outcome" => query.filter.outcome = Some(parse_outcome(value)?),
+                "type" => query.filter.task_type = Some(parse_task_type(value)?),
+                "since" => query.filter.since = Some(parse_since(value)?),
+                _ => text.push(token.value),
+            }
+        }
+
+        query.text = text.join(" ");
+        Ok(query)
+    }
+
+    /// Fill in the project filter from a separate `--project` flag
+    /// unless the query already names one.
+    pub fn with_project(mut self, project: Option<&str>) -> Self {
+        if self.filter.project.is_none() {
+            self.filter.project = project.map(String::from);
+        }
+        self
+    }
+
+    /// True when every free-text word appears somewhere in the episode
+    pub fn text_matches(&self, episode: &Episode) -> bool {
+        if self.text.is_empty() {
+            return true;
+        }
+        let haystack = format!(
+            "{} {} {} {}",
+            episode.intent.raw_prompt,
+            episode.intent.extracted_intent,
+            episode.intent.domain.join(" "),
+            episode.context.files_modified.join(" ")
+        )
+        .to_lowercase();
+        self.text
+            .to_lowercase()
+            .split_whitespace()
+            .all(|w| haystack.contains(w))
+    }
+}
+
+impl EpisodeFilter {
+    pub fn is_empty(&self) -> bool {
+        *self == EpisodeFilter::default()
+    }
+
+    pub fn matches(&self, episode: &Episode) -> bool {
+        self.mismatch(episode).is_none()
+    }
+
+    /// Describe the first filter the episode fails, if any
+    pub fn mismatch(&self, episode: &Episode) -> Option<String> {
+        if let Some(proj) = &self.project {
+            if !project_matches(&episode.project, proj) {
+                return Some(format!("project filter ({} != {})", episode.project, proj));
+            }
+        }
+
+        for tag in &self.tags {
+            let tag_lower = tag.to_lowercase();
+            if !episode
+                .intent
+                .domain
+                .iter()
+                .any(|d| d.to_lowercase().contains(&tag_lower))
+            {
+                return Some(format!("tag:{} not present", tag));
+            }
+        }
+
+        if let Some(outcome) = &self.outcome {
+            if episode.outcome.status != *outcome {
+                return Some(format!("outcome is {}", episode.outcome.status));
+            }
+        }
+
+        if let Some(task_type) = &self.task_type {
+            if episode.intent.task_type != *task_type {
+                return Some(format!("type is {}", episode.intent.task_type));
+            }
+        }
+
+        if let Some(since) = self.since {
+            if episode.timestamp_start < since {
+                return Some(format!("older than since:{}", since.format("%Y-%m-%d")));
+            }
+        }
+
+        None
+    }
+}
+
+struct Token {
+    value: String,
+    quoted: bool,
+}
+
+/// Split on whitespace, keeping `"quoted phrases"` (and `key:"quoted value"`) together
+fn tokenize(input: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut quoted = false;
+
+    for c in input.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                // A token that *starts* with a quote is a phrase, not a filter
+                if current.is_empty() {
+                    quoted = true;
+                } else if !quoted {
+                    current.push(c);
+                }
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    tokens.push(Token {
+                        value: std::mem::take(&mut current),
+                        quoted,
+                    });
+                }
+                quoted = false;
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(Token {
+            value: current,
+            quoted,
+        });
+    }
+
+    tokens
+}
+
+pub fn parse_outcome(value: &str) -> Result<OutcomeStatus> {
+    match value.to_lowercase().as_str() {
+        "success" => Ok(OutcomeStatus::Success),
+        "partial" => Ok(OutcomeStatus::Partial),
+        "failure" | "failed" => Ok(OutcomeStatus::Failure),
+        _ => anyhow::bail!(
+            "Unknown outcome '{}' (expected success, partial, failure)",
+            value
+        ),
+    }
+}
+
+fn parse_task_type(value: &str) -> Result<TaskType> {
+    match value.to_lowercase().as_str() {
+        "bugfix" => Ok(TaskType::Bugfix),
+        "feature" => Ok(TaskType::Feature),
+        "refactor" => Ok(TaskType::Refactor),
+        "test" => Ok(TaskType::Test),
+        "docs" => Ok(TaskType::Docs),
+        "research" => Ok(TaskType::Research),
+        "debug" => Ok(TaskType::Debug),
+        "setup" => Ok(TaskType::Setup),
+        _ => anyhow::bail!(
+            "Unknown type '{}' (expected bugfix, feature, refactor, test, docs, research, debug, setup)",
+            value
+        ),
+    }
+}
+
+/// Parse `since:` as a relative age (`12h`, `30d`, `2w`) or a date (`2026-01-15`)
+fn parse_since(value: &str) -> Result<DateTime<Utc>> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc());
+    }
+
+    let invalid = || anyhow::anyhow!("Invalid since '{}' (use 12h, 30d, 2w or YYYY-MM-DD)", value);
+
+    // Split off the last character, which may be multi-byte in bad input
+    let (split, _) = value.char_indices().last().ok_or_else(invalid)?;
+    let (num, unit) = value.split_at(split);
+    let amount: i64 = num.parse().map_err(|_| invalid())?;
+    if amount < 0 {
+        return Err(invalid());
+    }
+    // Out-of-range ages are rejected rather than overflowing
+    let age = match unit {
+        "h" => Duration::try_hours(amount),
+        "d" => Duration::try_days(amount),
+        "w" => Duration::try_weeks(amount),
+        _ => None,
+    }
+    .ok_or_else(invalid)?;
+
+    Utc::now().checked_sub_signed(age).ok_or_else(invalid)
+}
+
+/// Whether `project` matches a `project:` filter: case-insensitive substring,
+/// the same on every search path
+pub fn project_matches(project: &str, filter: &str) -> bool {
+    project.to_lowercase().contains(&filter.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_filters_and_text() {
+        let q = EpisodeQuery::parse(
+            r#"tag:rust outcome:failure type:bugfix since:30d project:api "connection pool" timeout"#,
+        )
+        .unwrap();
+
+        assert_eq!(q.text, "connection pool timeout");
+        assert_eq!(q.filter.tags, vec!["rust"]);
+        assert_eq!(q.filter.outcome, Some(OutcomeStatus::Failure));
+        assert_eq!(q.filter.task_type, Some(TaskType::Bugfix));
+        assert_eq!(q.filter.project.as_deref(), Some("api"));
+        let since = q.filter.since.unwrap();
+        assert!(
+            (Utc::now() - since - Duration::days(30))
+                .num_seconds()
+                .abs()
+                < 5
+        );
+    }
+
+    #[test]
+    fn test_parse_plain_text_and_unknown_keys() {
+        let q = EpisodeQuery::parse("fix auth bug").unwrap();
+        assert_eq!(q.text, "fix auth bug");
+        assert!(q.filter.is_empty());
+
+        // Unknown keys and quoted filters stay in the text
+        let q = EpisodeQuery::parse(r#"see https://example.com "tag:literal""#).unwrap();
+        assert_eq!(q.text, "see https://example.com tag:literal");
+        assert!(q.filter.is_empty());
+
+        let q = EpisodeQuery::parse(r#"tag:"error handling" since:2026-01-15"#).unwrap();
+        assert_eq!(q.filter.tags, vec!["error handling"]);
+        assert!(q.filter.since.is_some());
+        assert!(q.text.is_empty());
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_values() {
+        assert!(EpisodeQuery::parse("outcome:maybe").is_err());
+        assert!(EpisodeQuery::parse("type:chore").is_err());
+        assert!(EpisodeQuery::parse("since:soon").is_err());
+        // Multi-byte unit and out-of-range ages are errors, not panics
+        assert!(EpisodeQuery::parse("since:30é").is_err());
+        assert!(EpisodeQuery::parse("since:9999999999999d").is_err());
+        assert!(EpisodeQuery::parse("since:9223372036854775807w").is_err());
+        assert!(EpisodeQuery::parse("since:-3d").is_err());
+    }
+
+    #[test]
+    fn test_filter_matches() {
+        let mut ep = Episode::new("api-server".to_string(), "fix pool timeout".to_string());
+        ep.intent.domain = vec!["rust".to_string(), "database".to_string()];
+        ep.intent.task_type = TaskType::Bugfix;
+        ep.outcome.status = OutcomeStatus::Failure;
+
+        let q = EpisodeQuery::parse("tag:rust outcome:failure type:bugfix project:api since:1d")
+            .unwrap();
+        assert!(q.filter.matches(&ep));
+
+        let q = EpisodeQuery::parse("tag:python").unwrap();
+        assert_eq!(
+            q.filter.mismatch(&ep).as_deref(),
+            Some("tag:python not present")
+        );
+
+        let q = EpisodeQuery::parse("outcome:success").unwrap();
+        assert!(!q.filter.matches(&ep));
+
+        // Project filters are case-insensitive substrings everywhere
+        assert!(project_matches("API-Server", "api"));
+        assert!(!project_matches("web", "api"));
+
+        let q = EpisodeQuery::parse("pool timeout").unwrap();
+        assert!(q.text_matches(&ep));
+        let q = EpisodeQuery::parse("pool deadlock").unwrap();
+        assert!(!q.text_matches(&ep));
+    }
+
+    #[test]
+    fn test_with_project() {
+        let q = EpisodeQuery::parse("timeout")
+            .unwrap()
+            .with_project(Some("api"));
+        assert_eq!(q.filter.project.as_deref(), Some("api"));
+
+        let q = EpisodeQuery::parse("project:web timeout")
+            .unwrap()
+            .with_project(Some("api"));
+        assert_eq!(q.filter.project.as_deref(), Some("web"));
+    }
+}
@@ -0,0 +1,129 @@
+// Copyright 2024-2026 Andrey Vasilevsky <anvanster@gmail.com>
+// SPDX-License-Identifier: Apache-2.0
+
+#![allow(dead_code)]
+
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result};
+use fastembed::{RerankInitOptions, RerankerModel, TextRerank};
+
+use crate::episode::Episode;
+use crate::indexer::EpisodeIndexer;
+
+/// Cross-encoder reranker for retrieval candidates.
+///
+/// The bi-encoder index scores query and episode independently, which ranks
+/// loosely-related episodes too high. A cross-encoder reads both together and
+/// produces a sharper relevance score for the short list of top candidates.
+pub struct EpisodeReranker {
+    model: TextRerank,
+}
+
+/// Reranker loaded by this process, with the model name it was loaded for
+static SHARED: Mutex<Option<(String, Arc<EpisodeReranker>)>> = Mutex::new(None);
+
+impl EpisodeReranker {
+    /// Reranker for `model_name`, loaded once per process and reused across
+    /// queries (a long-running MCP server would otherwise reload it per call)
+    pub fn shared(model_name: &str) -> Result<Arc<Self>> {
+        let mut shared = SHARED.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((name, reranker)) = shared.as_ref() {
+            if name == model_name {
+                return Ok(Arc::clone(reranker));
+            }
+        }
+        let reranker = Arc::new(Self::new(model_name)?);
+        *shared = Some((model_name.to_string(), Arc::clone(&reranker)));
+        Ok(reranker)
+    }
+
+    /// Load the configured reranker model (downloaded on first use)
+    pub fn new(model_name: &str) -> Result<Self> {
+        let model = parse_reranker_model(model_name)?;
+
+        let cache_dir = EpisodeIndexer::model_cache_path()?;
+        std::fs::create_dir_all(&cache_dir)?;
+
+        let model = TextRerank::try_new(
+            RerankInitOptions::new(model)
+                .with_cache_dir(cache_dir)
+                .with_show_download_progress(false),
+        )
+        .context("Failed to initialize reranker model")?;
+
+        Ok(Self { model })
+    }
+
+    /// Score each episode against the query.
+    /// Returns one score in 0.0 - 1.0 per episode, in input order.
+    pub fn rerank(&self, query: &str, episodes: &[&Episode]) -> Result<Vec<f32>> {
+        if episodes.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let documents: Vec<String> = episodes
+            .iter()
+            .map(|ep| EpisodeIndexer::episode_to_embedding_text(ep))
+            .collect();
+        let documents: Vec<&str> = documents.iter().map(|d| d.as_str()).collect();
+
+        let results = self
+            .model
+            .rerank(query, documents, false, None)
+            .context("Failed to rerank candidates")?;
+
+        // Results come back sorted by score; restore input order
+        let mut scores = vec![0.0; episodes.len()];
+        for result in results {
+            if let Some(slot) = scores.get_mut(result.index) {
+                *slot = normalize_score(result.score);
+            }
+        }
+
+        Ok(scores)
+    }
+}
+
+/// Map a config model name to a fastembed reranker
+fn parse_reranker_model(name: &str) -> Result<RerankerModel> {
+    match name.to_lowercase().as_str() {
+        "bge-reranker-base" => Ok(RerankerModel::BGERerankerBase),
+        "bge-reranker-v2-m3" => Ok(RerankerModel::BGERerankerV2M3),
+        "jina-reranker-v1-turbo-en" => Ok(RerankerModel::JINARerankerV1TurboEn),
+        "jina-reranker-v2-base-multilingual" => Ok(RerankerModel::JINARerankerV2BaseMultiligual),
+        _ => anyhow::bail!("Unknown reranker model: {}", name),
+    }
+}
+
+/// Cross-encoders emit raw logits; squash them into 0.0 - 1.0 so they can
+/// stand in for cosine similarity in the combined score.
+fn normalize_score(logit: f32) -> f32 {
+    1.0 / (1.0 + (-logit).exp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_reranker_model() {
+        assert_eq!(
+            parse_reranker_model("bge-reranker-base").unwrap(),
+            RerankerModel::BGERerankerBase
+        );
+        assert_eq!(
+            parse_reranker_model("JINA-Reranker-v1-Turbo-EN").unwrap(),
+            RerankerModel::JINARerankerV1TurboEn
+        );
+        assert!(parse_reranker_model("not-a-model").is_err());
+    }
+
+    #[test]
+    fn test_normalize_score() {
+        assert!((normalize_score(0.0) - 0.5).abs() < f32::EPSILON);
+        assert!(normalize_score(8.0) > 0.99);
+        assert!(normalize_score(-8.0) < 0.01);
+        assert!(normalize_score(2.0) > normalize_score(1.0));
+    }
+}
@@ -7,9 +7,17 @@ use chrono::Utc;
 use colored::Colorize;
 use std::io::Write;
 
+use crate::capture::{get_modified_files, get_recent_commit_files};
 use crate::config::Config;
-use crate::episode::{Episode, RetrievalRecord};
+use crate::episode::{Episode, ErrorRecord, OutcomeStatus, RetrievalRecord};
+use crate::error_signature::ErrorSignature;
+use crate::events::{EventLog, LogRecord, RetrievalEvent, RetrievedResult, current_project};
+use crate::exploration::{ExplorationMode, Rng, is_under_tested, sample_utility};
+use crate::graph::EpisodeGraph;
 use crate::indexer::EpisodeIndexer;
+use crate::position_bias::PositionBias;
+use crate::query::{EpisodeFilter, EpisodeQuery, project_matches};
+use crate::reranker::EpisodeReranker;
 use crate::store::EpisodeStore;
 
 /// Run the retrieve command
@@ -18,45 +26,199 @@ pub async fn run(
     limit: usize,
     project: Option<String>,
     format: &str,
+    explain: bool,
     config: &Config,
 ) -> Result<()> {
     let store = EpisodeStore::new()?;
 
     // Try vector search first if index exists
-    let episodes = match try_vector_search(query, limit, project.as_deref(), config).await {
-        Ok(results) if !results.is_empty() => {
-            println!("🔍 Using semantic vector search...\n");
-            results
-        }
-        _ => {
-            println!("🔍 Using text-based search (run 'tempera index' for semantic search)...\n");
-            retrieve_episodes_text(query, limit, project.as_deref(), config, &store)?
-        }
-    };
+    let outcome = search(query, limit, project.as_deref(), config, &store).await?;
+    if outcome.semantic {
+        println!("🔍 Using semantic vector search...\n");
+    } else {
+        println!("🔍 Using text-based search (run 'tempera index' for semantic search)...\n");
+    }
 
-    if episodes.is_empty() {
+    if outcome.is_empty() && !explain {
         println!("No relevant episodes found.");
         return Ok(());
     }
 
+    // Record retrieval for utility tracking; the event ID is what feedback binds to
+    let results: Vec<(&Episode, f32)> = outcome
+        .episodes
+        .iter()
+        .chain(&outcome.mistakes)
+        .map(|s| (&s.episode, s.combined_score))
+        .collect();
+    let event = record_retrievals(&results, query, &store, config)?;
+    let retrieval_id = event.as_ref().map(|e| e.id.as_str());
+
     // Display results based on format
     match format {
+        "json" if explain => {
+            let report = ExplainReport::from_outcome(&outcome, retrieval_id);
+            let json = serde_json::to_string_pretty(&report)?;
+            println!("{}", json);
+        }
         "json" => {
-            let json = serde_json::to_string_pretty(&episodes)?;
+            let json = serde_json::to_string_pretty(&serde_json::json!({
+                "retrieval_id": retrieval_id,
+                "episodes": outcome.episodes,
+                "mistakes": outcome.mistakes,
+            }))?;
             println!("{}", json);
         }
         _ => {
             // Default: markdown format
-            print_markdown_results(&episodes, query);
+            print_markdown_results(&outcome.episodes, &outcome.mistakes, query, retrieval_id);
+            if explain {
+                print_markdown_explain(&outcome);
+            }
         }
     }
 
-    // Record retrieval for utility tracking
-    record_retrievals(&episodes, query, &store)?;
+    Ok(())
+}
+
+/// Run the retrieve command in error-signature mode (`--error`)
+pub fn run_error(
+    error: &str,
+    limit: usize,
+    project: Option<String>,
+    format: &str,
+    config: &Config,
+) -> Result<()> {
+    let store = EpisodeStore::new()?;
+    let signature = ErrorSignature::from_message(error);
+    let matches = search_errors(&signature, limit, project.as_deref(), &store)?;
+
+    if matches.is_empty() {
+        println!("No stored errors match signature: {}", signature);
+        return Ok(());
+    }
+
+    let results: Vec<(&Episode, f32)> =
+        matches.iter().map(|m| (&m.episode, m.similarity)).collect();
+    let event = record_retrievals(&results, &format!("error: {}", signature), &store, config)?;
+    let retrieval_id = event.as_ref().map(|e| e.id.as_str());
+
+    match format {
+        "json" => {
+            let json = serde_json::to_string_pretty(&serde_json::json!({
+                "retrieval_id": retrieval_id,
+                "matches": matches,
+            }))?;
+            println!("{}", json);
+        }
+        _ => print_markdown_error_matches(&matches, &signature, retrieval_id),
+    }
 
     Ok(())
 }
 
+/// Minimum signature similarity for an error-search hit
+const MIN_ERROR_SIMILARITY: f32 = 0.3;
+
+/// A stored error that matches the searched signature
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ErrorMatch {
+    pub episode: Episode,
+    pub error: ErrorRecord,
+    pub similarity: f32,
+}
+
+/// Match an error signature against every stored `ErrorRecord`.
+/// Returns at most one (best) match per episode, resolved errors first on ties.
+pub fn search_errors(
+    signature: &ErrorSignature,
+    limit: usize,
+    project_filter: Option<&str>,
+    store: &EpisodeStore,
+) -> Result<Vec<ErrorMatch>> {
+    if signature.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut matches = Vec::new();
+    for ep in store.list_all()? {
+        if let Some(proj) = project_filter {
+            if !project_matches(&ep.project, proj) {
+                continue;
+            }
+        }
+
+        let best = ep
+            .context
+            .errors_encountered
+            .iter()
+            .map(|err| (err, error_record_similarity(signature, err)))
+            .filter(|(_, sim)| *sim >= MIN_ERROR_SIMILARITY)
+            .max_by(|(a, sa), (b, sb)| {
+                sa.partial_cmp(sb)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    .then(a.resolved.cmp(&b.resolved))
+            });
+
+        if let Some((err, similarity)) = best {
+            let error = err.clone();
+            matches.push(ErrorMatch {
+                episode: ep,
+                error,
+                similarity,
+            });
+        }
+    }
+
+    matches.sort_by(|a, b| {
+        b.similarity
+            .partial_cmp(&a.similarity)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(b.error.resolved.cmp(&a.error.resolved))
+            .then(
+                b.episode
+                    .utility
+                    .calculate_score()
+                    .partial_cmp(&a.episode.utility.calculate_score())
+                    .unwrap_or(std::cmp::Ordering::Equal),
+            )
+    });
+    matches.truncate(limit);
+
+    Ok(matches)
+}
+
+/// Signature similarity for a stored error. Captured messages often have the
+/// `error[E0599]:` prefix stripped, so codes in `error_type` count too.
+fn error_record_similarity(signature: &ErrorSignature, err: &ErrorRecord) -> f32 {
+    let mut stored = ErrorSignature::from_message(&err.message);
+    if stored.codes.is_empty() {
+        stored.codes = ErrorSignature::from_message(&err.error_type).codes;
+    }
+    signature.similarity(&stored)
+}
+
+/// Retrieve episodes with vector search, falling back to text search when the
+/// index is unavailable or returns nothing. The query may contain structured
+/// filters (see [`EpisodeQuery`]); filter-only queries skip the vector index.
+pub async fn search(
+    query: &str,
+    limit: usize,
+    project_filter: Option<&str>,
+    config: &Config,
+    store: &EpisodeStore,
+) -> Result<RetrievalOutcome> {
+    // Surface malformed filters instead of falling back silently
+    if EpisodeQuery::parse(query)?.text.is_empty() {
+        return text_search_explained(query, limit, project_filter, config, store);
+    }
+
+    match vector_search_explained(query, limit, project_filter, config).await {
+        Ok(outcome) if !outcome.is_empty() => Ok(outcome),
+        _ => text_search_explained(query, limit, project_filter, config, store),
+    }
+}
+
 /// Try to retrieve episodes using vector search
 pub async fn try_vector_search(
     query: &str,
@@ -64,46 +226,170 @@ pub async fn try_vector_search(
     project_filter: Option<&str>,
     config: &Config,
 ) -> Result<Vec<ScoredEpisode>> {
+    Ok(
+        vector_search_explained(query, limit, project_filter, config)
+            .await?
+            .episodes,
+    )
+}
+
+/// Vector search that also records why candidates were dropped
+pub async fn vector_search_explained(
+    query: &str,
+    limit: usize,
+    project_filter: Option<&str>,
+    config: &Config,
+) -> Result<RetrievalOutcome> {
     let indexer = EpisodeIndexer::new().await?;
 
     if !indexer.is_indexed().await {
         anyhow::bail!("Index not available");
     }
 
+    let query = EpisodeQuery::parse(query)?.with_project(project_filter);
+    if query.text.is_empty() {
+        anyhow::bail!("No search text to embed");
+    }
+    let project_filter = query.filter.project.as_deref();
+
     let store = EpisodeStore::new()?;
-    let search_results = indexer.search(query, limit * 2, project_filter).await?;
+    let mut dropped = Vec::new();
 
-    // Convert search results to scored episodes
-    let mut episodes = Vec::new();
-    for result in search_results {
-        if let Ok(episode) = store.load(&result.id) {
-            let utility = episode.utility.calculate_score();
-            let recency = calculate_recency_score(&episode, config.retrieval.recency_halflife_days);
-            let combined = combined_score(result.similarity_score, utility, recency, config);
+    // Over-fetch to leave room for filtering; the reranker also wants a
+    // meaningful candidate pool
+    let base_limit = if query.filter.is_empty() {
+        limit * 2
+    } else {
+        limit * 4
+    };
+    let fetch_limit = if config.retrieval.rerank {
+        base_limit.max(config.retrieval.rerank_top_n)
+    } else {
+        base_limit
+    };
+    let (search_results, excluded) = indexer
+        .search_with_excluded(&query.text, fetch_limit, project_filter)
+        .await?;
+
+    for result in excluded {
+        dropped.push(DroppedCandidate {
+            id: result.id,
+            summary: result.intent_text.chars().take(60).collect(),
+            similarity_score: result.similarity_score,
+            reason: format!(
+                "project filter ({} != {})",
+                result.project,
+                project_filter.unwrap_or_default()
+            ),
+        });
+    }
 
-            episodes.push(ScoredEpisode {
-                episode,
+    let mut candidates: Vec<(Episode, f32, Vec<f32>)> = Vec::new();
+    for result in search_results {
+        match store.load(&result.id) {
+            Ok(episode) => match query.filter.mismatch(&episode) {
+                Some(reason) => {
+                    dropped.push(DroppedCandidate::new(
+                        &episode,
+                        result.similarity_score,
+                        reason,
+                    ));
+                }
+                None => candidates.push((episode, result.similarity_score, result.vector)),
+            },
+            Err(_) => dropped.push(DroppedCandidate {
+                id: result.id,
+                summary: result.intent_text.chars().take(60).collect(),
                 similarity_score: result.similarity_score,
-                utility_score: utility,
-                combined_score: combined,
-            });
+                reason: "indexed but missing from episode store".to_string(),
+            }),
         }
     }
 
-    // Sort by combined score
-    episodes.sort_by(|a, b| {
-        b.combined_score
-            .partial_cmp(&a.combined_score)
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
+    // Optional cross-encoder pass over the top-N candidates.
+    // If the reranker can't load, fall back to bi-encoder similarity.
+    let rerank_scores = if config.retrieval.rerank {
+        match rerank_candidates(&query.text, &candidates, limit, config) {
+            Ok(scores) => {
+                // Unscored candidates would mix cosine and cross-encoder
+                // scales in one ranking, so they drop out
+                for (episode, similarity, _) in candidates.drain(scores.len()..) {
+                    dropped.push(DroppedCandidate::new(
+                        &episode,
+                        similarity,
+                        "past rerank_top_n".to_string(),
+                    ));
+                }
+                scores
+            }
+            Err(e) => {
+                eprintln!("⚠️  Reranking failed ({}), using vector similarity", e);
+                vec![]
+            }
+        }
+    } else {
+        vec![]
+    };
 
-    // Filter by minimum similarity
-    episodes.retain(|e| e.similarity_score >= config.retrieval.min_similarity);
+    let context = ScoringContext::current(config);
 
-    // Apply MMR for diversity
-    let episodes = apply_mmr(episodes, limit, config.retrieval.mmr_lambda);
+    // Convert search results to scored episodes
+    let episodes: Vec<ScoredEpisode> = candidates
+        .into_iter()
+        .enumerate()
+        .map(|(i, (episode, similarity, vector))| {
+            let embedding = (!vector.is_empty()).then_some(vector);
+            score_episode(
+                episode,
+                similarity,
+                rerank_scores.get(i).copied(),
+                embedding,
+                &context,
+                config,
+            )
+        })
+        .collect();
+
+    // Linked episodes outside the vector hits can join through the graph
+    let mut episodes = episodes;
+    if config.retrieval.graph_weight > 0.0 {
+        let all = store.list_all()?;
+        apply_graph_scores(&mut episodes, &all, &query.filter, &context, config);
+    }
 
-    Ok(episodes)
+    let (episodes, mistakes) = if query.filter.outcome.is_some() {
+        (episodes, vec![])
+    } else {
+        split_mistakes(episodes, config, &mut dropped)
+    };
+    let episodes = rank_candidates(episodes, limit, &context, config, &mut dropped);
+
+    Ok(RetrievalOutcome {
+        episodes,
+        mistakes,
+        dropped,
+        semantic: true,
+        min_similarity: config.retrieval.min_similarity,
+        project_filter: query.filter.project.clone(),
+    })
+}
+
+/// Rescore the top-N candidates with the configured cross-encoder, never
+/// fewer than `limit` so reranking can't shrink the result list.
+/// Returns scores aligned with the first candidates; the rest are left out.
+fn rerank_candidates(
+    query: &str,
+    candidates: &[(Episode, f32, Vec<f32>)],
+    limit: usize,
+    config: &Config,
+) -> Result<Vec<f32>> {
+    let reranker = EpisodeReranker::shared(&config.retrieval.rerank_model)?;
+    let top: Vec<&Episode> = candidates
+        .iter()
+        .take(config.retrieval.rerank_top_n.max(limit))
+        .map(|(ep, _, _)| ep)
+        .collect();
+    reranker.rerank(query, &top)
 }
 
 /// Retrieve relevant episodes using text-based search (fallback)
@@ -114,46 +400,390 @@ pub fn retrieve_episodes_text(
     config: &Config,
     store: &EpisodeStore,
 ) -> Result<Vec<ScoredEpisode>> {
+    Ok(text_search_explained(query, limit, project_filter, config, store)?.episodes)
+}
+
+/// Text-based search that also records why candidates were dropped
+pub fn text_search_explained(
+    query: &str,
+    limit: usize,
+    project_filter: Option<&str>,
+    config: &Config,
+    store: &EpisodeStore,
+) -> Result<RetrievalOutcome> {
+    let query = EpisodeQuery::parse(query)?.with_project(project_filter);
     let all_episodes = store.list_all()?;
+    let context = ScoringContext::current(config);
+    let mut dropped = Vec::new();
+
+    // Score episodes, applying structured filters
+    let mut scored = Vec::new();
+    for ep in all_episodes {
+        // Filter-only queries treat every matching episode as fully relevant
+        let similarity = if query.text.is_empty() {
+            1.0
+        } else {
+            calculate_text_similarity(&query.text, &ep)
+        };
+        if let Some(reason) = query.filter.mismatch(&ep) {
+            // Only report filter drops that would otherwise have matched
+            if similarity > 0.0 && !query.text.is_empty() {
+                dropped.push(DroppedCandidate::new(&ep, similarity, reason));
+            }
+            continue;
+        }
+        scored.push(score_episode(ep, similarity, None, None, &context, config));
+    }
 
-    // Score and rank episodes
-    let mut scored: Vec<ScoredEpisode> = all_episodes
-        .into_iter()
+    // Every stored episode is already a candidate, nothing to expand with
+    apply_graph_scores(&mut scored, &[], &query.filter, &context, config);
+
+    // An explicit outcome filter asks for those episodes as regular results
+    let (scored, mistakes) = if query.filter.outcome.is_some() {
+        (scored, vec![])
+    } else {
+        split_mistakes(scored, config, &mut dropped)
+    };
+    let scored = rank_candidates(scored, limit, &context, config, &mut dropped);
+
+    Ok(RetrievalOutcome {
+        episodes: scored,
+        mistakes,
+        dropped,
+        semantic: false,
+        min_similarity: config.retrieval.min_similarity,
+        project_filter: query.filter.project,
+    })
+}
+
+/// Score a single candidate and record the breakdown used for `--explain`
+fn score_episode(
+    episode: Episode,
+    similarity: f32,
+    rerank_score: Option<f32>,
+    embedding: Option<Vec<f32>>,
+    context: &ScoringContext,
+    config: &Config,
+) -> ScoredEpisode {
+    // Utility as seen from the project retrieving (see `Utility::project_score`)
+    let utility = episode
+        .utility
+        .project_score(&context.project, config.retrieval.project_utility_prior);
+    let recency = calculate_recency_score(&episode, config.retrieval.recency_halflife_days);
+    let working_tree = working_tree_overlap(&episode, &context.working_files, &context.project);
+    let relevance = rerank_score.unwrap_or(similarity);
+    let weights = ScoreWeights::from_config(config);
+    let combined = weights.apply(relevance, utility, recency, working_tree, 0.0);
+
+    ScoredEpisode {
+        episode,
+        similarity_score: similarity,
+        utility_score: utility,
+        combined_score: combined,
+        rerank_score,
+        embedding,
+        breakdown: ScoreBreakdown {
+            vector_score: similarity,
+            rerank_score,
+            utility,
+            recency,
+            similarity_weight: weights.similarity,
+            utility_weight: weights.utility,
+            recency_weight: weights.recency,
+            working_tree,
+            working_tree_weight: weights.working_tree,
+            graph: 0.0,
+            graph_weight: weights.graph,
+            combined,
+            mmr_penalty: 0.0,
+            mmr_score: 0.0,
+            explored: false,
+        },
+    }
+}
+
+/// Candidates the graph is built over; similarity edges are quadratic
+const GRAPH_POOL: usize = 30;
+
+/// Candidates below `min_similarity` are kept when the graph walk from the
+/// top hits reaches them at least this strongly
+const MIN_GRAPH_SCORE: f32 = 0.3;
+
+/// Score candidates with personalized PageRank from the top hits, when
+/// `graph_weight` is set. Episodes in `expand_from` that are linked to a
+/// candidate (explicit link either way, or same session) join the pool, so
+/// prerequisites and continuations can surface with their parents.
+fn apply_graph_scores(
+    candidates: &mut Vec<ScoredEpisode>,
+    expand_from: &[Episode],
+    filter: &EpisodeFilter,
+    context: &ScoringContext,
+    config: &Config,
+) {
+    if config.retrieval.graph_weight <= 0.0 || candidates.is_empty() {
+        return;
+    }
+
+    candidates.sort_by(|a, b| {
+        b.combined_score
+            .partial_cmp(&a.combined_score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    let mut pool_len = candidates.len().min(GRAPH_POOL);
+
+    // Seeds: the best relevant hits, weighted by their score
+    let min_similarity = config.retrieval.min_similarity;
+    let seeds: Vec<(String, f32)> = candidates[..pool_len]
+        .iter()
+        .filter(|c| c.similarity_score >= min_similarity)
+        .take(config.retrieval.graph_seeds)
+        .map(|c| (c.episode.id.clone(), c.combined_score))
+        .collect();
+    if seeds.is_empty() {
+        return;
+    }
+
+    let known: std::collections::HashSet<&str> =
+        candidates.iter().map(|c| c.episode.id.as_str()).collect();
+    let expansions: Vec<ScoredEpisode> = expand_from
+        .iter()
+        .filter(|ep| !known.contains(ep.id.as_str()) && filter.mismatch(ep).is_none())
         .filter(|ep| {
-            // Filter by project if specified
-            if let Some(proj) = project_filter {
-                ep.project.to_lowercase().contains(&proj.to_lowercase())
-            } else {
-                true
-            }
+            candidates[..pool_len]
+                .iter()
+                .any(|c| is_linked(&c.episode, ep))
         })
-        .map(|ep| {
-            let similarity = calculate_text_similarity(query, &ep);
-            let utility = ep.utility.calculate_score();
-            let recency = calculate_recency_score(&ep, config.retrieval.recency_halflife_days);
-            let combined = combined_score(similarity, utility, recency, config);
+        .map(|ep| score_episode(ep.clone(), 0.0, None, None, context, config))
+        .collect();
+    let added = expansions.len();
+    candidates.splice(pool_len..pool_len, expansions);
+    pool_len += added;
+
+    let pool = &candidates[..pool_len];
+    let episodes: Vec<&Episode> = pool.iter().map(|c| &c.episode).collect();
+    let mut graph = EpisodeGraph::new(&episodes, &config.bellman.relation_weights);
+    for (i, a) in pool.iter().enumerate() {
+        for b in &pool[i + 1..] {
+            let similarity = redundancy(a, b);
+            if similarity >= config.bellman.propagation_threshold {
+                graph.add_edge(&a.episode.id, &b.episode.id, similarity);
+            }
+        }
+    }
 
-            ScoredEpisode {
-                episode: ep,
-                similarity_score: similarity,
-                utility_score: utility,
-                combined_score: combined,
+    let seeds: Vec<(&str, f32)> = seeds.iter().map(|(id, w)| (id.as_str(), *w)).collect();
+    let scores = graph.personalized_pagerank(&seeds);
+    let weights = ScoreWeights::from_config(config);
+    for se in &mut candidates[..pool_len] {
+        let graph_score = scores.get(&se.episode.id).copied().unwrap_or(0.0);
+        let relevance = se.rerank_score.unwrap_or(se.similarity_score);
+        let b = &mut se.breakdown;
+        b.graph = graph_score;
+        b.combined = weights.apply(relevance, b.utility, b.recency, b.working_tree, graph_score);
+        se.combined_score = b.combined;
+    }
+}
+
+/// Explicit link either way, or the same session
+fn is_linked(a: &Episode, b: &Episode) -> bool {
+    let same_session = a.session_id.is_some() && a.session_id == b.session_id;
+    same_session || a.relation_to(&b.id).is_some() || b.relation_to(&a.id).is_some()
+}
+
+/// Failure episodes, plus partial ones that left errors unresolved. Partial is
+/// also the default outcome for hook captures, so a partial episode only counts
+/// as a mistake when it has an unresolved error to warn about.
+pub fn is_mistake(episode: &Episode) -> bool {
+    match episode.outcome.status {
+        OutcomeStatus::Failure => true,
+        OutcomeStatus::Partial => episode
+            .context
+            .errors_encountered
+            .iter()
+            .any(|e| !e.resolved),
+        OutcomeStatus::Success => false,
+    }
+}
+
+/// Move mistakes out of the candidate pool into their own channel. Mistakes
+/// are ranked by relevance alone (utility reflects how useful an episode was
+/// as a solution, which says nothing about a failure) and capped by
+/// `mistakes_limit` / `mistakes_min_similarity`. Returns (candidates, mistakes).
+fn split_mistakes(
+    candidates: Vec<ScoredEpisode>,
+    config: &Config,
+    dropped: &mut Vec<DroppedCandidate>,
+) -> (Vec<ScoredEpisode>, Vec<ScoredEpisode>) {
+    let limit = config.retrieval.mistakes_limit;
+    if limit == 0 {
+        return (candidates, vec![]);
+    }
+
+    let (mut mistakes, candidates): (Vec<_>, Vec<_>) = candidates
+        .into_iter()
+        .partition(|se| is_mistake(&se.episode));
+
+    for se in &mut mistakes {
+        se.combined_score = se.rerank_score.unwrap_or(se.similarity_score);
+        se.breakdown.combined = se.combined_score;
+    }
+    mistakes.sort_by(|a, b| {
+        b.combined_score
+            .partial_cmp(&a.combined_score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let min_similarity = config.retrieval.mistakes_min_similarity;
+    let mut selected = Vec::new();
+    for se in mistakes {
+        if se.combined_score < min_similarity {
+            if se.similarity_score > 0.0 {
+                dropped.push(DroppedCandidate::new(
+                    &se.episode,
+                    se.similarity_score,
+                    format!(
+                        "mistake below mistakes_min_similarity ({:.2} < {:.2})",
+                        se.combined_score, min_similarity
+                    ),
+                ));
             }
-        })
-        .filter(|se| se.similarity_score >= config.retrieval.min_similarity)
-        .collect();
+        } else if selected.len() >= limit {
+            dropped.push(DroppedCandidate::new(
+                &se.episode,
+                se.similarity_score,
+                format!("beyond mistakes_limit ({})", limit),
+            ));
+        } else {
+            selected.push(se);
+        }
+    }
+
+    (candidates, selected)
+}
+
+/// Sort by combined score, apply the min_similarity cut, then MMR.
+/// Anything removed along the way is appended to `dropped`.
+fn rank_candidates(
+    mut candidates: Vec<ScoredEpisode>,
+    limit: usize,
+    context: &ScoringContext,
+    config: &Config,
+    dropped: &mut Vec<DroppedCandidate>,
+) -> Vec<ScoredEpisode> {
+    // Exploring retrievals rank with utility sampled from the posterior
+    let mut rng = Rng::new();
+    let exploration = ExplorationMode::roll(config, &mut rng);
+    if exploration == Some(ExplorationMode::Thompson) {
+        for candidate in &mut candidates {
+            let sampled = sample_utility(
+                &candidate.episode.utility,
+                &context.project,
+                config.retrieval.project_utility_prior,
+                &mut rng,
+            );
+            rescore_utility(candidate, sampled, config);
+        }
+    }
 
     // Sort by combined score (descending)
-    scored.sort_by(|a, b| {
+    candidates.sort_by(|a, b| {
         b.combined_score
             .partial_cmp(&a.combined_score)
             .unwrap_or(std::cmp::Ordering::Equal)
     });
 
+    // Filter by minimum similarity
+    let min_similarity = config.retrieval.min_similarity;
+    let (kept, below): (Vec<_>, Vec<_>) = candidates.into_iter().partition(|e| {
+        e.similarity_score >= min_similarity || e.breakdown.graph >= MIN_GRAPH_SCORE
+    });
+    for se in below {
+        // Text search scores every stored episode; skip the ones with no overlap at all
+        if se.similarity_score > 0.0 {
+            dropped.push(DroppedCandidate::new(
+                &se.episode,
+                se.similarity_score,
+                format!(
+                    "below min_similarity ({:.2} < {:.2})",
+                    se.similarity_score, min_similarity
+                ),
+            ));
+        }
+    }
+
     // Apply MMR for diversity
-    let scored = apply_mmr(scored, limit, config.retrieval.mmr_lambda);
+    let (mut selected, mut rest) = apply_mmr_traced(kept, limit, config.retrieval.mmr_lambda);
+    if exploration == Some(ExplorationMode::Reserve) {
+        if let Some(displaced) = reserve_exploration_slot(&mut selected, &mut rest, limit) {
+            dropped.push(DroppedCandidate::new(
+                &displaced.episode,
+                displaced.similarity_score,
+                "displaced by exploration slot".to_string(),
+            ));
+        }
+    }
+    for se in rest {
+        dropped.push(DroppedCandidate::new(
+            &se.episode,
+            se.similarity_score,
+            format!(
+                "not selected by MMR (limit {}, score {:.2})",
+                limit, se.combined_score
+            ),
+        ));
+    }
+
+    selected
+}
+
+/// Replace a candidate's utility (e.g. with a posterior sample) and recombine
+fn rescore_utility(se: &mut ScoredEpisode, utility: f32, config: &Config) {
+    let relevance = se.rerank_score.unwrap_or(se.similarity_score);
+    let b = &mut se.breakdown;
+    b.utility = utility;
+    b.combined = ScoreWeights::from_config(config).apply(
+        relevance,
+        utility,
+        b.recency,
+        b.working_tree,
+        b.graph,
+    );
+    b.explored = true;
+    se.utility_score = utility;
+    se.combined_score = b.combined;
+}
+
+/// Give the last slot to the most relevant under-tested candidate, unless
+/// one was already selected. Returns the episode it displaced, if any.
+fn reserve_exploration_slot(
+    selected: &mut Vec<ScoredEpisode>,
+    rest: &mut Vec<ScoredEpisode>,
+    limit: usize,
+) -> Option<ScoredEpisode> {
+    if limit == 0 || selected.iter().any(|s| is_under_tested(&s.episode.utility)) {
+        return None;
+    }
+
+    let idx = rest
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| is_under_tested(&c.episode.utility))
+        .max_by(|(_, a), (_, b)| {
+            a.similarity_score
+                .partial_cmp(&b.similarity_score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })
+        .map(|(i, _)| i)?;
 
-    Ok(scored)
+    let mut explored = rest.remove(idx);
+    explored.breakdown.explored = true;
+    let displaced = if selected.len() >= limit {
+        selected.pop()
+    } else {
+        None
+    };
+    selected.push(explored);
+    displaced
 }
 
 /// Calculate recency score using exponential decay with configurable half-life.
@@ -168,14 +798,161 @@ fn calculate_recency_score(episode: &Episode, halflife_days: f32) -> f32 {
 
 /// Combine similarity, utility, and recency scores with weight normalization.
 fn combined_score(similarity: f32, utility: f32, recency: f32, config: &Config) -> f32 {
-    let sim_w = config.retrieval.similarity_weight;
-    let util_w = config.retrieval.utility_weight;
-    let rec_w = config.retrieval.recency_weight;
-    let total = sim_w + util_w + rec_w;
-    if total == 0.0 {
+    ScoreWeights::from_config(config).apply(similarity, utility, recency, 0.0, 0.0)
+}
+
+/// Retrieval weights from config, normalized to sum to 1.0 (all zero if unset)
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+pub struct ScoreWeights {
+    pub similarity: f32,
+    pub utility: f32,
+    pub recency: f32,
+    pub working_tree: f32,
+    pub graph: f32,
+}
+
+impl ScoreWeights {
+    pub fn from_config(config: &Config) -> Self {
+        let sim_w = config.retrieval.similarity_weight;
+        let util_w = config.retrieval.utility_weight;
+        let rec_w = config.retrieval.recency_weight;
+        let wt_w = config.retrieval.working_tree_weight;
+        let graph_w = config.retrieval.graph_weight;
+        let total = sim_w + util_w + rec_w + wt_w + graph_w;
+        if total == 0.0 {
+            return Self {
+                similarity: 0.0,
+                utility: 0.0,
+                recency: 0.0,
+                working_tree: 0.0,
+                graph: 0.0,
+            };
+        }
+        Self {
+            similarity: sim_w / total,
+            utility: util_w / total,
+            recency: rec_w / total,
+            working_tree: wt_w / total,
+            graph: graph_w / total,
+        }
+    }
+
+    /// Weighted sum of the individual score components
+    pub fn apply(
+        &self,
+        similarity: f32,
+        utility: f32,
+        recency: f32,
+        working_tree: f32,
+        graph: f32,
+    ) -> f32 {
+        self.similarity * similarity
+            + self.utility * utility
+            + self.recency * recency
+            + self.working_tree * working_tree
+            + self.graph * graph
+    }
+}
+
+/// Number of recent commits whose changes count as "working tree" context
+const WORKING_TREE_RECENT_COMMITS: usize = 3;
+
+/// What every candidate in one retrieval is scored against, worked out once
+/// per search instead of once per candidate
+#[derive(Debug, Clone, Default)]
+struct ScoringContext {
+    /// Project the retrieval is made from
+    project: String,
+    /// See `working_tree_files`
+    working_files: Vec<String>,
+}
+
+impl ScoringContext {
+    fn current(config: &Config) -> Self {
+        Self {
+            project: current_project(),
+            working_files: working_tree_files(config),
+        }
+    }
+}
+
+/// Files currently being worked on: uncommitted changes plus the last few commits.
+/// Empty when the boost is disabled or the current directory isn't a git repo.
+fn working_tree_files(config: &Config) -> Vec<String> {
+    if config.retrieval.working_tree_weight <= 0.0 {
+        return vec![];
+    }
+
+    let Ok(cwd) = std::env::current_dir() else {
+        return vec![];
+    };
+
+    let mut files = get_modified_files(&cwd).unwrap_or_default();
+    files.extend(get_recent_commit_files(&cwd, WORKING_TREE_RECENT_COMMITS).unwrap_or_default());
+    files.sort();
+    files.dedup();
+    files
+}
+
+/// Overlap between an episode's files and the working tree of `project`
+/// (0.0 - 1.0). Uses the best-matching pair; files the episode only read
+/// count for half. Repo-relative paths like `src/main.rs` mean nothing across
+/// projects, so episodes from other projects get no overlap.
+fn working_tree_overlap(episode: &Episode, working_files: &[String], project: &str) -> f32 {
+    if working_files.is_empty() || !episode.project.eq_ignore_ascii_case(project) {
         return 0.0;
     }
-    (sim_w * similarity + util_w * utility + rec_w * recency) / total
+
+    let best = |files: &[String]| {
+        files
+            .iter()
+            .flat_map(|f| working_files.iter().map(move |w| path_similarity(f, w)))
+            .fold(0.0_f32, f32::max)
+    };
+
+    best(&episode.context.files_modified).max(0.5 * best(&episode.context.files_read))
+}
+
+/// Similarity between two file paths, which may be absolute or repo-relative:
+/// 1.0 for the same file, 0.6 for the same directory, 0.3 for the same module
+/// (one directory nested inside the other, ignoring single top-level dirs like `src`).
+fn path_similarity(a: &str, b: &str) -> f32 {
+    let a: Vec<&str> = path_components(a);
+    let b: Vec<&str> = path_components(b);
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+
+    if a.ends_with(&b) || b.ends_with(&a) {
+        return 1.0;
+    }
+
+    let a_dir = &a[..a.len() - 1];
+    let b_dir = &b[..b.len() - 1];
+    if a_dir.is_empty() || b_dir.is_empty() {
+        return 0.0;
+    }
+
+    if a_dir.ends_with(b_dir) || b_dir.ends_with(a_dir) {
+        return 0.6;
+    }
+
+    let (short, long) = if a_dir.len() <= b_dir.len() {
+        (a_dir, b_dir)
+    } else {
+        (b_dir, a_dir)
+    };
+    if short.len() >= 2 && long.windows(short.len()).any(|w| w == short) {
+        return 0.3;
+    }
+
+    0.0
+}
+
+fn path_components(path: &str) -> Vec<&str> {
+    path.split(['/', '\\'])
+        .filter(|c| !c.is_empty() && *c != ".")
+        .collect()
 }
 
 /// Calculate text-based similarity between query and episode
@@ -214,7 +991,12 @@ fn calculate_text_similarity(query: &str, episode: &Episode) -> f32 {
 }
 
 /// Print results in markdown format
-fn print_markdown_results(episodes: &[ScoredEpisode], query: &str) {
+fn print_markdown_results(
+    episodes: &[ScoredEpisode],
+    mistakes: &[ScoredEpisode],
+    query: &str,
+    retrieval_id: Option<&str>,
+) {
     println!("{}", "## Relevant Past Experiences".bold());
     println!();
     println!("Query: {}", query.italic());
@@ -267,6 +1049,10 @@ fn print_markdown_results(episodes: &[ScoredEpisode], query: &str) {
             println!("**Tags**: {}", ep.intent.domain.join(", "));
         }
 
+        if !ep.related_episodes.is_empty() {
+            println!("**Linked**: {}", ep.format_relations());
+        }
+
         // Show errors if any were resolved
         let resolved_errors: Vec<_> = ep
             .context
@@ -284,33 +1070,220 @@ fn print_markdown_results(episodes: &[ScoredEpisode], query: &str) {
         println!();
     }
 
+    if !mistakes.is_empty() {
+        print_markdown_mistakes(mistakes);
+    }
+
+    print_feedback_hint(retrieval_id);
+}
+
+/// Footer telling the user how to give feedback on this retrieval
+fn print_feedback_hint(retrieval_id: Option<&str>) {
     println!("{}", "---".dimmed());
+    match retrieval_id {
+        Some(id) => println!(
+            "{}",
+            format!(
+                "Retrieval {}. To provide feedback: tempera feedback helpful --retrieval {} [--episodes <id>,<id>]",
+                &id[..8],
+                &id[..8]
+            )
+            .dimmed()
+        ),
+        None => println!(
+            "{}",
+            "To provide feedback: tempera feedback helpful --episodes <id>,<id>".dimmed()
+        ),
+    }
+}
+
+/// Print the "mistakes to avoid" section
+fn print_markdown_mistakes(mistakes: &[ScoredEpisode]) {
+    println!("{}", "## Mistakes to Avoid".bold());
+    println!();
+
+    for scored in mistakes {
+        let ep = &scored.episode;
+        println!(
+            "### ⚠ {}",
+            if ep.intent.extracted_intent.is_empty() {
+                &ep.intent.raw_prompt
+            } else {
+                &ep.intent.extracted_intent
+            }
+        );
+        println!(
+            "**When**: {} | **Project**: {} | **Outcome**: {}",
+            ep.timestamp_start.format("%Y-%m-%d"),
+            ep.project,
+            ep.outcome.status
+        );
+        println!(
+            "**Relevance**: {:.0}% similarity",
+            scored.similarity_score * 100.0
+        );
+
+        let unresolved: Vec<_> = ep
+            .context
+            .errors_encountered
+            .iter()
+            .filter(|e| !e.resolved)
+            .collect();
+        if !unresolved.is_empty() {
+            println!("**Unresolved errors**:");
+            for err in unresolved.iter().take(3) {
+                println!("  - {}", err.message);
+            }
+        }
+
+        println!();
+    }
+}
+
+/// Print error-signature matches with their stored resolutions
+fn print_markdown_error_matches(
+    matches: &[ErrorMatch],
+    signature: &ErrorSignature,
+    retrieval_id: Option<&str>,
+) {
+    println!("{}", "## Known Error Resolutions".bold());
+    println!();
+    println!("Signature: {}", signature.to_string().italic());
+    println!();
+
+    for (i, m) in matches.iter().enumerate() {
+        let ep = &m.episode;
+        println!(
+            "### {}. {}",
+            i + 1,
+            if ep.intent.extracted_intent.is_empty() {
+                &ep.intent.raw_prompt
+            } else {
+                &ep.intent.extracted_intent
+            }
+        );
+        println!("**Episode**: {} ({})", &ep.id[..8], ep.project);
+        println!("**Error**: {}", m.error.message);
+        println!("**Match**: {:.0}%", m.similarity * 100.0);
+        match (&m.error.resolution, m.error.resolved) {
+            (Some(resolution), _) => println!("**Resolution**: {}", resolution),
+            (None, true) => println!("**Resolution**: resolved (see episode for details)"),
+            (None, false) => println!("**Resolution**: {}", "unresolved".dimmed()),
+        }
+        println!();
+    }
+
+    print_feedback_hint(retrieval_id);
+}
+
+/// Print the score breakdown and dropped candidates for `--explain`
+fn print_markdown_explain(outcome: &RetrievalOutcome) {
+    println!();
+    println!("{}", "## Score Breakdown".bold());
+    println!();
     println!(
-        "{}",
-        "To provide feedback: tempera feedback helpful --episodes <id>,<id>".dimmed()
+        "Search: {} | min_similarity: {:.2}{}",
+        if outcome.semantic { "vector" } else { "text" },
+        outcome.min_similarity,
+        outcome
+            .project_filter
+            .as_deref()
+            .map(|p| format!(" | project: {}", p))
+            .unwrap_or_default()
     );
+    println!();
+
+    for (i, scored) in outcome.episodes.iter().enumerate() {
+        println!(
+            "{}. [{}] {}",
+            i + 1,
+            &scored.episode.id[..8],
+            format_breakdown(&scored.breakdown)
+        );
+    }
+
+    for scored in &outcome.mistakes {
+        println!(
+            "⚠ [{}] mistake, ranked by relevance {:.3}",
+            &scored.episode.id[..8],
+            scored.combined_score
+        );
+    }
+
+    if !outcome.dropped.is_empty() {
+        println!();
+        println!("{}", "### Dropped candidates".bold());
+        for dropped in &outcome.dropped {
+            println!("  - {}", format_dropped(dropped));
+        }
+    }
 }
 
-/// Record retrievals for utility tracking
-fn record_retrievals(episodes: &[ScoredEpisode], query: &str, store: &EpisodeStore) -> Result<()> {
-    let project = std::env::current_dir()
-        .ok()
-        .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
-        .unwrap_or_else(|| "unknown".to_string());
+/// Record a retrieval for utility tracking: appends a retrieval event with the
+/// ranked results, and tags each episode's retrieval record with the event ID
+/// so feedback can be attached to this exact retrieval.
+/// Repeat impressions within the same session and impression window don't
+/// increment `retrieval_count`, so repeated searches don't dilute utility.
+/// New impressions are weighted by the position-bias propensity of their rank.
+/// Returns None when nothing was retrieved.
+pub fn record_retrievals(
+    results: &[(&Episode, f32)],
+    query: &str,
+    store: &EpisodeStore,
+    config: &Config,
+) -> Result<Option<RetrievalEvent>> {
+    if results.is_empty() {
+        return Ok(None);
+    }
 
-    for scored in episodes {
-        let mut episode = scored.episode.clone();
+    let project = current_project();
+    let event = RetrievalEvent::new(
+        query,
+        &project,
+        results
+            .iter()
+            .enumerate()
+            .map(|(i, (ep, score))| RetrievedResult {
+                episode_id: ep.id.clone(),
+                rank: i + 1,
+                score: *score,
+            })
+            .collect(),
+    );
+    let log = EventLog::new()?;
+    let bias = PositionBias::from_log(&log, config);
+    log.append(&LogRecord::Retrieval(event.clone()))?;
+
+    let window = chrono::Duration::minutes(config.retrieval.impression_window_minutes as i64);
+    for (i, (episode, _)) in results.iter().enumerate() {
+        let rank = i + 1;
+        let mut episode = (*episode).clone();
+        let repeat = episode.is_repeat_impression(
+            &project,
+            event.session_id.as_deref(),
+            event.timestamp,
+            window,
+        );
 
-        // Add retrieval record
+        // Add retrieval record (also for repeats, so feedback can bind to it)
         episode.retrieval_history.push(RetrievalRecord {
-            timestamp: Utc::now(),
+            timestamp: event.timestamp,
             project: project.clone(),
             task_description: query.to_string(),
             was_helpful: None, // Will be updated via feedback
+            retrieval_id: Some(event.id.clone()),
+            session_id: event.session_id.clone(),
+            rank: Some(rank),
+            grade: None,
+            aspects: vec![],
+            implicit: None,
         });
 
-        // Update retrieval count
-        episode.utility.retrieval_count += 1;
+        // Count only new impressions, weighted by how likely this rank is to be read
+        if !repeat {
+            episode.utility.record_impression(bias.propensity(rank));
+            episode.utility.record_project_impression(&project);
+        }
 
         // Save updated episode
         store.update(&episode)?;
@@ -318,13 +1291,11 @@ fn record_retrievals(episodes: &[ScoredEpisode], query: &str, store: &EpisodeSto
 
     // Also save IDs to feedback log for easy reference
     let feedback_log = Config::feedback_log_path()?;
-    let ids: Vec<String> = episodes
-        .iter()
-        .map(|e| e.episode.id[..8].to_string())
-        .collect();
+    let ids: Vec<String> = results.iter().map(|(e, _)| e.id[..8].to_string()).collect();
     let log_entry = format!(
-        "{}\tquery:{}\tids:{}\n",
-        Utc::now().to_rfc3339(),
+        "{}\tretrieval:{}\tquery:{}\tids:{}\n",
+        event.timestamp.to_rfc3339(),
+        event.id,
         query.replace('\t', " "),
         ids.join(",")
     );
@@ -334,7 +1305,14 @@ fn record_retrievals(episodes: &[ScoredEpisode], query: &str, store: &EpisodeSto
         .open(feedback_log)?
         .write_all(log_entry.as_bytes())?;
 
-    Ok(())
+    Ok(Some(event))
+}
+
+/// Record that an episode was opened or used (e.g. a detail view by ID)
+pub fn record_use(episode: &Episode, store: &EpisodeStore) -> Result<()> {
+    let mut episode = episode.clone();
+    episode.utility.used_count += 1;
+    store.update(&episode)
 }
 
 /// A scored episode with similarity and utility scores
@@ -344,53 +1322,259 @@ pub struct ScoredEpisode {
     pub similarity_score: f32,
     pub utility_score: f32,
     pub combined_score: f32,
+    /// Cross-encoder relevance (0.0 - 1.0), present when reranking is enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rerank_score: Option<f32>,
+    /// Stored embedding from the vector index (None for text-search results)
+    #[serde(skip)]
+    pub embedding: Option<Vec<f32>>,
+    /// Per-component scores, surfaced by `--explain`
+    #[serde(skip)]
+    pub breakdown: ScoreBreakdown,
+}
+
+/// Full score breakdown for a single retrieved episode
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct ScoreBreakdown {
+    /// Raw bi-encoder (or text overlap) similarity
+    pub vector_score: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rerank_score: Option<f32>,
+    pub utility: f32,
+    pub recency: f32,
+    /// Normalized weights applied to relevance, utility and recency
+    pub similarity_weight: f32,
+    pub utility_weight: f32,
+    pub recency_weight: f32,
+    /// Overlap with files in the current git working tree
+    pub working_tree: f32,
+    pub working_tree_weight: f32,
+    /// Personalized PageRank score from the top hits (0.0 when off)
+    pub graph: f32,
+    pub graph_weight: f32,
+    pub combined: f32,
+    /// Redundancy penalty subtracted by MMR when the episode was selected
+    pub mmr_penalty: f32,
+    /// Final MMR score the episode was selected with
+    pub mmr_score: f32,
+    /// Selected by exploration (sampled utility or reserved slot)
+    pub explored: bool,
+}
+
+/// A candidate that was considered but not returned, and why
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DroppedCandidate {
+    pub id: String,
+    pub summary: String,
+    pub similarity_score: f32,
+    pub reason: String,
+}
+
+impl DroppedCandidate {
+    fn new(episode: &Episode, similarity_score: f32, reason: String) -> Self {
+        let summary = if episode.intent.extracted_intent.is_empty() {
+            &episode.intent.raw_prompt
+        } else {
+            &episode.intent.extracted_intent
+        };
+        Self {
+            id: episode.id.clone(),
+            summary: summary.chars().take(60).collect(),
+            similarity_score,
+            reason,
+        }
+    }
+}
+
+/// Result of a retrieval, including candidates dropped along the way
+#[derive(Debug, Clone)]
+pub struct RetrievalOutcome {
+    /// Best known methods
+    pub episodes: Vec<ScoredEpisode>,
+    /// Relevant failure episodes, scored and limited separately
+    pub mistakes: Vec<ScoredEpisode>,
+    pub dropped: Vec<DroppedCandidate>,
+    /// True when results came from the vector index
+    pub semantic: bool,
+    pub min_similarity: f32,
+    pub project_filter: Option<String>,
+}
+
+impl RetrievalOutcome {
+    pub fn is_empty(&self) -> bool {
+        self.episodes.is_empty() && self.mistakes.is_empty()
+    }
+}
+
+/// JSON shape for `retrieve --explain --format json`
+#[derive(Debug, serde::Serialize)]
+pub struct ExplainReport<'a> {
+    /// Event ID that feedback binds to; None when nothing was retrieved
+    pub retrieval_id: Option<&'a str>,
+    pub search: &'static str,
+    pub min_similarity: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_filter: Option<&'a str>,
+    pub results: Vec<ExplainedResult<'a>>,
+    pub mistakes: Vec<ExplainedResult<'a>>,
+    pub dropped: &'a [DroppedCandidate],
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct ExplainedResult<'a> {
+    pub id: &'a str,
+    pub summary: &'a str,
+    pub breakdown: &'a ScoreBreakdown,
+}
+
+impl<'a> From<&'a ScoredEpisode> for ExplainedResult<'a> {
+    fn from(se: &'a ScoredEpisode) -> Self {
+        Self {
+            id: &se.episode.id,
+            summary: &se.episode.intent.raw_prompt,
+            breakdown: &se.breakdown,
+        }
+    }
+}
+
+impl<'a> ExplainReport<'a> {
+    pub fn from_outcome(outcome: &'a RetrievalOutcome, retrieval_id: Option<&'a str>) -> Self {
+        Self {
+            retrieval_id,
+            search: if outcome.semantic { "vector" } else { "text" },
+            min_similarity: outcome.min_similarity,
+            project_filter: outcome.project_filter.as_deref(),
+            results: outcome.episodes.iter().map(ExplainedResult::from).collect(),
+            mistakes: outcome.mistakes.iter().map(ExplainedResult::from).collect(),
+            dropped: &outcome.dropped,
+        }
+    }
+}
+
+/// One-line score breakdown for a retrieved episode
+pub fn format_breakdown(b: &ScoreBreakdown) -> String {
+    let relevance = match b.rerank_score {
+        Some(r) => format!("rerank {:.3} (vector {:.3})", r, b.vector_score),
+        None => format!("vector {:.3}", b.vector_score),
+    };
+    let working_tree = if b.working_tree_weight > 0.0 {
+        format!(
+            " + working tree {:.3} × {:.2}",
+            b.working_tree, b.working_tree_weight
+        )
+    } else {
+        String::new()
+    };
+    let graph = if b.graph_weight > 0.0 {
+        format!(" + graph {:.3} × {:.2}", b.graph, b.graph_weight)
+    } else {
+        String::new()
+    };
+    format!(
+        "{} × {:.2} + utility {:.3} × {:.2} + recency {:.3} × {:.2}{}{} = {:.3}; MMR penalty {:.3} → {:.3}{}",
+        relevance,
+        b.similarity_weight,
+        b.utility,
+        b.utility_weight,
+        b.recency,
+        b.recency_weight,
+        working_tree,
+        graph,
+        b.combined,
+        b.mmr_penalty,
+        b.mmr_score,
+        if b.explored { " (exploration)" } else { "" }
+    )
+}
+
+/// One-line description of a dropped candidate
+pub fn format_dropped(d: &DroppedCandidate) -> String {
+    format!(
+        "[{}] {} (similarity {:.3}): {}",
+        &d.id[..8.min(d.id.len())],
+        d.summary,
+        d.similarity_score,
+        d.reason
+    )
 }
 
 /// Apply Maximal Marginal Relevance (MMR) for result diversity
 /// lambda: 0.0 = pure diversity, 1.0 = pure relevance
-pub fn apply_mmr(
+pub fn apply_mmr(candidates: Vec<ScoredEpisode>, limit: usize, lambda: f32) -> Vec<ScoredEpisode> {
+    apply_mmr_traced(candidates, limit, lambda).0
+}
+
+/// MMR that records each selection's penalty in its breakdown and also
+/// returns the candidates that were not selected.
+fn apply_mmr_traced(
     mut candidates: Vec<ScoredEpisode>,
     limit: usize,
     lambda: f32,
-) -> Vec<ScoredEpisode> {
+) -> (Vec<ScoredEpisode>, Vec<ScoredEpisode>) {
     if candidates.is_empty() || limit == 0 {
-        return vec![];
+        return (vec![], candidates);
     }
 
     let mut selected: Vec<ScoredEpisode> = Vec::with_capacity(limit);
 
     // First result is always the highest scoring
-    selected.push(candidates.remove(0));
+    let mut first = candidates.remove(0);
+    first.breakdown.mmr_penalty = 0.0;
+    first.breakdown.mmr_score = lambda * first.combined_score;
+    selected.push(first);
 
     while !candidates.is_empty() && selected.len() < limit {
         // Find candidate with best MMR score
-        let best_idx = candidates
+        let best = candidates
             .iter()
             .enumerate()
             .map(|(idx, candidate)| {
                 // Max similarity to any already-selected episode
                 let max_sim_to_selected = selected
                     .iter()
-                    .map(|s| text_overlap_similarity(&candidate.episode, &s.episode))
+                    .map(|s| redundancy(candidate, s))
                     .fold(0.0_f32, |a, b| a.max(b));
 
                 // MMR score: λ * relevance - (1-λ) * redundancy
-                let mmr_score =
-                    lambda * candidate.combined_score - (1.0 - lambda) * max_sim_to_selected;
+                let penalty = (1.0 - lambda) * max_sim_to_selected;
+                let mmr_score = lambda * candidate.combined_score - penalty;
 
-                (idx, mmr_score)
+                (idx, mmr_score, penalty)
             })
-            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
-            .map(|(idx, _)| idx);
+            .max_by(|(_, a, _), (_, b, _)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
 
-        if let Some(idx) = best_idx {
-            selected.push(candidates.remove(idx));
+        if let Some((idx, mmr_score, penalty)) = best {
+            let mut chosen = candidates.remove(idx);
+            chosen.breakdown.mmr_penalty = penalty;
+            chosen.breakdown.mmr_score = mmr_score;
+            selected.push(chosen);
         } else {
             break;
         }
     }
 
-    selected
+    (selected, candidates)
+}
+
+/// Redundancy between two candidates for MMR: cosine similarity of their stored
+/// embeddings, falling back to word overlap when either vector is unavailable.
+fn redundancy(a: &ScoredEpisode, b: &ScoredEpisode) -> f32 {
+    match (&a.embedding, &b.embedding) {
+        (Some(va), Some(vb)) if va.len() == vb.len() => cosine_similarity(va, vb),
+        _ => text_overlap_similarity(&a.episode, &b.episode),
+    }
+}
+
+/// Cosine similarity between two vectors (0.0 when either is zero-length)
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
 }
 
 /// Calculate text overlap between two episodes for MMR diversity
@@ -428,6 +1612,7 @@ pub fn text_overlap_similarity(a: &Episode, b: &Episode) -> f32 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::episode::EpisodeRelation;
 
     #[test]
     fn test_calculate_text_similarity() {
@@ -454,6 +1639,57 @@ mod tests {
         assert!((score_zero - 1.0).abs() < f32::EPSILON);
     }
 
+    fn scored(prompt: &str, combined: f32, embedding: Option<Vec<f32>>) -> ScoredEpisode {
+        ScoredEpisode {
+            episode: Episode::new("test".to_string(), prompt.to_string()),
+            similarity_score: combined,
+            utility_score: 0.5,
+            combined_score: combined,
+            rerank_score: None,
+            embedding,
+            breakdown: ScoreBreakdown::default(),
+        }
+    }
+
+    #[test]
+    fn test_cosine_similarity() {
+        assert!((cosine_similarity(&[1.0, 0.0], &[1.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 0.0]), 0.0);
+    }
+
+    #[test]
+    fn test_mmr_uses_embeddings() {
+        // Paraphrases share no words but have near-identical vectors
+        let candidates = vec![
+            scored("fix pool exhaustion", 0.9, Some(vec![1.0, 0.0, 0.0])),
+            scored(
+                "connections run out under load",
+                0.85,
+                Some(vec![0.99, 0.1, 0.0]),
+            ),
+            scored("flaky snapshot test", 0.8, Some(vec![0.0, 0.0, 1.0])),
+        ];
+
+        let selected = apply_mmr(candidates, 2, 0.5);
+        assert_eq!(selected.len(), 2);
+        assert_eq!(selected[0].episode.intent.raw_prompt, "fix pool exhaustion");
+        // The paraphrase is penalized as redundant, so the distinct episode wins
+        assert_eq!(selected[1].episode.intent.raw_prompt, "flaky snapshot test");
+    }
+
+    #[test]
+    fn test_mmr_falls_back_to_text_overlap() {
+        let candidates = vec![
+            scored("fix auth token bug", 0.9, None),
+            scored("fix auth token bug again", 0.85, Some(vec![1.0, 0.0])),
+            scored("update readme", 0.8, None),
+        ];
+
+        let selected = apply_mmr(candidates, 2, 0.5);
+        assert_eq!(selected[1].episode.intent.raw_prompt, "update readme");
+    }
+
     #[test]
     fn test_combined_score_normalization() {
         let config = Config::default();
@@ -478,4 +1714,343 @@ mod tests {
             score2
         );
     }
+
+    #[test]
+    fn test_score_weights_normalized() {
+        let mut config = Config::default();
+        config.retrieval.similarity_weight = 2.0;
+        config.retrieval.utility_weight = 1.0;
+        config.retrieval.recency_weight = 1.0;
+
+        let weights = ScoreWeights::from_config(&config);
+        assert!((weights.similarity - 0.5).abs() < 1e-6);
+        assert!((weights.utility - 0.25).abs() < 1e-6);
+        assert!((weights.recency - 0.25).abs() < 1e-6);
+
+        let ep = Episode::new("test".to_string(), "fix auth bug".to_string());
+        let scored = score_episode(ep, 0.8, None, None, &ScoringContext::default(), &config);
+        let b = &scored.breakdown;
+        let expected = b.similarity_weight * b.vector_score
+            + b.utility_weight * b.utility
+            + b.recency_weight * b.recency;
+        assert!((b.combined - expected).abs() < 1e-6);
+        assert_eq!(b.combined, scored.combined_score);
+    }
+
+    #[test]
+    fn test_mmr_records_penalty_and_leftovers() {
+        let candidates = vec![
+            scored("a", 0.9, Some(vec![1.0, 0.0])),
+            scored("b", 0.85, Some(vec![1.0, 0.0])),
+            scored("c", 0.5, Some(vec![0.0, 1.0])),
+        ];
+
+        let (selected, rest) = apply_mmr_traced(candidates, 2, 0.5);
+        assert_eq!(selected.len(), 2);
+        assert_eq!(selected[0].breakdown.mmr_penalty, 0.0);
+        // "c" is orthogonal to "a", so it is picked with no penalty
+        assert_eq!(selected[1].episode.intent.raw_prompt, "c");
+        assert!(selected[1].breakdown.mmr_penalty.abs() < 1e-6);
+        assert_eq!(rest.len(), 1);
+        assert_eq!(rest[0].episode.intent.raw_prompt, "b");
+    }
+
+    #[test]
+    fn test_rank_candidates_reports_drops() {
+        let mut config = Config::default();
+        config.retrieval.min_similarity = 0.3;
+        let candidates = vec![
+            scored("kept", 0.9, None),
+            scored("low", 0.1, None),
+            scored("extra", 0.8, None),
+        ];
+
+        let mut dropped = Vec::new();
+        let selected = rank_candidates(
+            candidates,
+            1,
+            &ScoringContext::default(),
+            &config,
+            &mut dropped,
+        );
+        assert_eq!(selected.len(), 1);
+        assert_eq!(dropped.len(), 2);
+        assert!(dropped.iter().any(|d| d.reason.contains("min_similarity")));
+        assert!(dropped.iter().any(|d| d.reason.contains("MMR")));
+    }
+
+    #[test]
+    fn test_reserve_exploration_slot() {
+        let tested = |prompt: &str, sim: f32| {
+            let mut se = scored(prompt, sim, None);
+            se.episode.utility.retrieval_count = 10;
+            se.episode.utility.helpful_count = 8;
+            se
+        };
+        let mut selected = vec![tested("bkm1", 0.9), tested("bkm2", 0.85)];
+        let mut rest = vec![
+            tested("bkm3", 0.8),
+            scored("new-low", 0.55, None),
+            scored("new-high", 0.7, None),
+        ];
+
+        let displaced = reserve_exploration_slot(&mut selected, &mut rest, 2).unwrap();
+        assert_eq!(displaced.episode.intent.raw_prompt, "bkm2");
+        assert_eq!(selected[1].episode.intent.raw_prompt, "new-high");
+        assert!(selected[1].breakdown.explored);
+
+        // An under-tested episode already selected needs no reserved slot
+        assert!(reserve_exploration_slot(&mut selected, &mut rest, 2).is_none());
+    }
+
+    #[test]
+    fn test_graph_surfaces_linked_episodes() {
+        let mut config = Config::default();
+        config.retrieval.graph_weight = 0.3;
+
+        let mut parent = Episode::new("test".to_string(), "deploy the api service".to_string());
+        let prereq = Episode::new("test".to_string(), "configure registry".to_string());
+        let unrelated = Episode::new("test".to_string(), "rename a variable".to_string());
+        parent.add_relation(&prereq.id, EpisodeRelation::Prerequisite);
+
+        let mut candidates = vec![score_episode(
+            parent.clone(),
+            0.9,
+            None,
+            None,
+            &ScoringContext::default(),
+            &config,
+        )];
+        let store = [prereq.clone(), unrelated.clone()];
+        apply_graph_scores(
+            &mut candidates,
+            &store,
+            &EpisodeFilter::default(),
+            &ScoringContext::default(),
+            &config,
+        );
+
+        // The prerequisite joins the pool through its link; the unrelated one doesn't
+        assert_eq!(candidates.len(), 2);
+        let linked = candidates
+            .iter()
+            .find(|c| c.episode.id == prereq.id)
+            .unwrap();
+        assert!(linked.breakdown.graph >= MIN_GRAPH_SCORE);
+        assert!(linked.combined_score > 0.0);
+
+        // ...and survives the min_similarity cut despite zero similarity
+        let mut dropped = Vec::new();
+        let ranked = rank_candidates(
+            candidates,
+            3,
+            &ScoringContext::default(),
+            &config,
+            &mut dropped,
+        );
+        assert!(ranked.iter().any(|c| c.episode.id == prereq.id));
+        assert_eq!(ranked[0].episode.id, parent.id);
+    }
+
+    #[test]
+    fn test_graph_off_by_default() {
+        let config = Config::default();
+        let mut parent = Episode::new("test".to_string(), "deploy".to_string());
+        let prereq = Episode::new("test".to_string(), "configure registry".to_string());
+        parent.add_relation(&prereq.id, EpisodeRelation::Prerequisite);
+
+        let mut candidates = vec![score_episode(
+            parent,
+            0.9,
+            None,
+            None,
+            &ScoringContext::default(),
+            &config,
+        )];
+        apply_graph_scores(
+            &mut candidates,
+            &[prereq],
+            &EpisodeFilter::default(),
+            &ScoringContext::default(),
+            &config,
+        );
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].breakdown.graph, 0.0);
+    }
+
+    #[test]
+    fn test_thompson_rescore() {
+        let config = Config::default();
+        let mut se = score_episode(
+            Episode::new("test".to_string(), "fix".to_string()),
+            0.8,
+            None,
+            None,
+            &ScoringContext::default(),
+            &config,
+        );
+        rescore_utility(&mut se, 1.0, &config);
+        assert!(se.breakdown.explored);
+        assert_eq!(se.utility_score, 1.0);
+        let expected =
+            ScoreWeights::from_config(&config).apply(0.8, 1.0, se.breakdown.recency, 0.0, 0.0);
+        assert!((se.combined_score - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_path_similarity() {
+        assert_eq!(path_similarity("src/retrieve.rs", "src/retrieve.rs"), 1.0);
+        assert_eq!(
+            path_similarity("/home/me/tempera/src/retrieve.rs", "./src/retrieve.rs"),
+            1.0
+        );
+        assert_eq!(path_similarity("src/mcp/tools.rs", "src/mcp/mod.rs"), 0.6);
+        assert_eq!(
+            path_similarity("src/mcp/handlers/retrieve.rs", "src/mcp/tools.rs"),
+            0.3
+        );
+        // A shared top-level `src` alone is not the same module
+        assert_eq!(path_similarity("src/main.rs", "src/mcp/tools.rs"), 0.0);
+        assert_eq!(path_similarity("README.md", "src/main.rs"), 0.0);
+    }
+
+    #[test]
+    fn test_working_tree_overlap() {
+        let mut ep = Episode::new("test".to_string(), "fix retrieval".to_string());
+        ep.context.files_modified = vec!["src/retrieve.rs".to_string()];
+        ep.context.files_read = vec!["src/mcp/tools.rs".to_string()];
+
+        assert_eq!(working_tree_overlap(&ep, &[], "test"), 0.0);
+        assert_eq!(
+            working_tree_overlap(&ep, &["src/retrieve.rs".to_string()], "test"),
+            1.0
+        );
+        // Read-only files count for half
+        assert_eq!(
+            working_tree_overlap(&ep, &["src/mcp/tools.rs".to_string()], "test"),
+            0.5
+        );
+        assert_eq!(
+            working_tree_overlap(&ep, &["docs/guide.md".to_string()], "test"),
+            0.0
+        );
+        // Same relative path in another project is a different file
+        assert_eq!(
+            working_tree_overlap(&ep, &["src/retrieve.rs".to_string()], "other"),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_working_tree_weight_in_combined_score() {
+        let mut config = Config::default();
+        config.retrieval.similarity_weight = 1.0;
+        config.retrieval.utility_weight = 0.0;
+        config.retrieval.working_tree_weight = 1.0;
+
+        let mut ep = Episode::new(current_project(), "fix retrieval".to_string());
+        ep.context.files_modified = vec!["src/retrieve.rs".to_string()];
+        let context = ScoringContext {
+            project: current_project(),
+            working_files: vec!["src/retrieve.rs".to_string()],
+        };
+        let boosted = score_episode(ep.clone(), 0.6, None, None, &context, &config);
+        let plain = score_episode(ep, 0.6, None, None, &ScoringContext::default(), &config);
+
+        assert!((boosted.combined_score - 0.8).abs() < 1e-6);
+        assert!((plain.combined_score - 0.3).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_error_record_similarity_uses_error_type_code() {
+        let signature = ErrorSignature::from_message(
+            "error[E0599]: no method named `foo` found for struct `Bar` in the current scope",
+        );
+        // Captured messages lose the `error[E0599]:` prefix
+        let stored = ErrorRecord {
+            error_type: "E0599".to_string(),
+            message: "no method named `baz` found for struct `Qux` in the current scope"
+                .to_string(),
+            resolved: true,
+            resolution: Some("import the trait".to_string()),
+        };
+        let other = ErrorRecord {
+            error_type: "E0308".to_string(),
+            ..stored.clone()
+        };
+
+        assert!(error_record_similarity(&signature, &stored) > 0.9);
+        assert!(error_record_similarity(&signature, &other) < 0.3);
+    }
+
+    #[test]
+    fn test_is_mistake() {
+        let mut ep = Episode::new("test".to_string(), "migrate db".to_string());
+        // Partial is the default outcome for captures with no known result
+        assert!(!is_mistake(&ep));
+
+        ep.context.errors_encountered.push(ErrorRecord {
+            error_type: "runtime".to_string(),
+            message: "deadlock detected".to_string(),
+            resolved: false,
+            resolution: None,
+        });
+        assert!(is_mistake(&ep));
+
+        ep.outcome.status = OutcomeStatus::Success;
+        assert!(!is_mistake(&ep));
+        ep.outcome.status = OutcomeStatus::Failure;
+        assert!(is_mistake(&ep));
+    }
+
+    #[test]
+    fn test_split_mistakes() {
+        let mut config = Config::default();
+        config.retrieval.mistakes_limit = 1;
+        config.retrieval.mistakes_min_similarity = 0.6;
+
+        let failure = |prompt: &str, similarity: f32| {
+            let mut se = scored(prompt, 0.1, None);
+            se.similarity_score = similarity;
+            se.episode.outcome.status = OutcomeStatus::Failure;
+            se
+        };
+        let candidates = vec![
+            scored("works", 0.9, None),
+            failure("close failure", 0.8),
+            failure("closer failure", 0.9),
+            failure("unrelated failure", 0.3),
+        ];
+
+        let mut dropped = Vec::new();
+        let (rest, mistakes) = split_mistakes(candidates, &config, &mut dropped);
+        assert_eq!(rest.len(), 1);
+        assert_eq!(mistakes.len(), 1);
+        // Ranked by relevance, not by the (low) combined score
+        assert_eq!(mistakes[0].episode.intent.raw_prompt, "closer failure");
+        assert_eq!(dropped.len(), 2);
+
+        // Limit 0 turns the channel off
+        config.retrieval.mistakes_limit = 0;
+        let (rest, mistakes) = split_mistakes(vec![failure("f", 0.9)], &config, &mut Vec::new());
+        assert_eq!(rest.len(), 1);
+        assert!(mistakes.is_empty());
+    }
+
+    #[test]
+    fn test_explain_report_carries_retrieval_id() {
+        let outcome = RetrievalOutcome {
+            episodes: vec![scored("works", 0.9, None)],
+            mistakes: vec![],
+            dropped: vec![],
+            semantic: true,
+            min_similarity: 0.3,
+            project_filter: None,
+        };
+        let report = ExplainReport::from_outcome(&outcome, Some("event-1"));
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["retrieval_id"], "event-1");
+        assert_eq!(json["search"], "vector");
+        assert_eq!(json["results"].as_array().unwrap().len(), 1);
+    }
 }
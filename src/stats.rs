@@ -10,11 +10,14 @@ use tabled::{Table, Tabled};
 
 use crate::config::Config;
 use crate::episode::{Episode, OutcomeStatus};
+use crate::ledger::{UtilityLedger, format_entry, summarize};
+use crate::query::{EpisodeQuery, parse_outcome};
 use crate::store::EpisodeStore;
 
 /// List episodes
 pub async fn list(
     limit: usize,
+    query: Option<String>,
     project: Option<String>,
     tag: Option<String>,
     outcome: Option<String>,
@@ -22,12 +25,15 @@ pub async fn list(
 ) -> Result<()> {
     let store = EpisodeStore::new()?;
 
-    let episodes = store.list_filtered(
-        limit,
-        project.as_deref(),
-        tag.as_deref(),
-        outcome.as_deref(),
-    )?;
+    // Flags are shorthands for the equivalent query filters
+    let mut query =
+        EpisodeQuery::parse(query.as_deref().unwrap_or_default())?.with_project(project.as_deref());
+    query.filter.tags.extend(tag);
+    if let Some(outcome) = outcome {
+        query.filter.outcome = Some(parse_outcome(&outcome)?);
+    }
+
+    let episodes = store.list_filtered(limit, &query)?;
 
     if episodes.is_empty() {
         println!("No episodes found.");
@@ -79,14 +85,102 @@ pub async fn show(id: &str, _config: &Config) -> Result<()> {
     println!();
     println!("{}", episode.to_markdown());
 
+    // Directed links from other episodes aren't stored on this one
+    let incoming = crate::link::incoming(&store, &episode.id)?;
+    if !incoming.is_empty() {
+        println!("{}", "## Linked From".bold());
+        for (other, relation) in &incoming {
+            println!("- {} ({})", &other.id[..8], relation);
+        }
+        println!();
+    }
+
     // Additional details not in markdown
     println!("{}", "## Utility Metrics".bold());
     println!("Retrieval count: {}", episode.utility.retrieval_count);
+    println!("Used count: {}", episode.utility.used_count);
+    if let Some(examined) = episode.utility.examined {
+        println!("Examined (position-weighted): {:.1}", examined);
+    }
     println!("Helpful count: {}", episode.utility.helpful_count);
+    if let Some(implicit) = episode.utility.implicit_credit {
+        println!("Implicit credit (weighted): {:.2}", implicit);
+    }
     println!(
         "Utility score: {:.2}%",
         episode.utility.calculate_score() * 100.0
     );
+    for (project, local) in &episode.utility.by_project {
+        println!(
+            "  in {}: {} retrieved, {} helpful, {:.2}%",
+            project,
+            local.retrieval_count,
+            local.helpful_count,
+            local.score() * 100.0
+        );
+    }
+
+    Ok(())
+}
+
+/// Explain an episode's utility score from the ledger of changes
+pub async fn why(id: &str, _config: &Config) -> Result<()> {
+    let store = EpisodeStore::new()?;
+    let episode = if id.to_lowercase() == "latest" || id.to_lowercase() == "last" {
+        store.load_latest()?
+    } else {
+        store.load(id)?
+    };
+    let entries = UtilityLedger::new()?.for_episode(&episode.id)?;
+    let utility = &episode.utility;
+    let current = utility.score.unwrap_or_else(|| utility.calculate_score());
+
+    println!(
+        "{}",
+        format!("🔎 Why is [{}] at {:.3}?", &episode.id[..8], current).bold()
+    );
+    println!(
+        "{}",
+        episode
+            .intent
+            .raw_prompt
+            .chars()
+            .take(80)
+            .collect::<String>()
+    );
+    println!();
+    println!(
+        "Base score: {:.3} | Wilson score: {:.3} ({} helpful / {:.1} trials)",
+        utility.base(),
+        utility.calculate_score(),
+        utility.helpful_count,
+        utility.trials()
+    );
+    println!();
+
+    if entries.is_empty() {
+        println!("No utility changes recorded yet.");
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        format!("## Utility history ({} changes)", entries.len()).bold()
+    );
+    for entry in &entries {
+        println!("  {}", format_entry(entry));
+    }
+
+    println!();
+    println!("{}", "## By mechanism".bold());
+    for (mechanism, summary) in summarize(&entries) {
+        println!(
+            "  {:<15} {:+.3} over {} change(s)",
+            mechanism.to_string(),
+            summary.total_delta,
+            summary.changes
+        );
+    }
 
     Ok(())
 }
@@ -7,6 +7,7 @@ use std::path::PathBuf;
 
 use crate::config::Config;
 use crate::episode::{Episode, OutcomeStatus};
+use crate::query::EpisodeQuery;
 
 /// Episode store for file-based and database storage
 pub struct EpisodeStore {
@@ -21,6 +22,11 @@ impl EpisodeStore {
         Ok(Self { episodes_dir })
     }
 
+    /// Store rooted at a specific directory
+    pub fn with_dir(episodes_dir: PathBuf) -> Self {
+        Self { episodes_dir }
+    }
+
     /// Save an episode to disk (both JSON and Markdown)
     pub fn save(&self, episode: &Episode) -> Result<PathBuf> {
         let date = episode.timestamp_start.format("%Y-%m-%d").to_string();
@@ -121,54 +127,12 @@ impl EpisodeStore {
         Ok(episodes)
     }
 
-    /// List episodes with filters
-    pub fn list_filtered(
-        &self,
-        limit: usize,
-        project: Option<&str>,
-        tag: Option<&str>,
-        outcome: Option<&str>,
-    ) -> Result<Vec<Episode>> {
-        let all_episodes = self.list_all()?;
-
-        let filtered: Vec<Episode> = all_episodes
+    /// List episodes matching a structured query (filters plus free-text words)
+    pub fn list_filtered(&self, limit: usize, query: &EpisodeQuery) -> Result<Vec<Episode>> {
+        let filtered: Vec<Episode> = self
+            .list_all()?
             .into_iter()
-            .filter(|ep| {
-                // Filter by project
-                if let Some(proj) = project {
-                    if !ep.project.to_lowercase().contains(&proj.to_lowercase()) {
-                        return false;
-                    }
-                }
-
-                // Filter by tag
-                if let Some(t) = tag {
-                    let t_lower = t.to_lowercase();
-                    if !ep
-                        .intent
-                        .domain
-                        .iter()
-                        .any(|d| d.to_lowercase().contains(&t_lower))
-                    {
-                        return false;
-                    }
-                }
-
-                // Filter by outcome
-                if let Some(o) = outcome {
-                    let expected_status = match o.to_lowercase().as_str() {
-                        "success" => OutcomeStatus::Success,
-                        "partial" => OutcomeStatus::Partial,
-                        "failure" => OutcomeStatus::Failure,
-                        _ => return true, // Unknown outcome filter, allow all
-                    };
-                    if ep.outcome.status != expected_status {
-                        return false;
-                    }
-                }
-
-                true
-            })
+            .filter(|ep| query.filter.matches(ep) && query.text_matches(ep))
             .take(limit)
             .collect();
 
@@ -393,6 +357,37 @@ mod tests {
         assert_eq!(all.len(), 2);
     }
 
+    #[test]
+    fn test_list_filtered_with_query() {
+        let (store, _temp) = create_test_store();
+
+        let mut ep1 = Episode::new("api".to_string(), "fix pool timeout".to_string());
+        ep1.intent.domain = vec!["rust".to_string()];
+        ep1.outcome.status = OutcomeStatus::Failure;
+        let mut ep2 = Episode::new("api".to_string(), "add pool metrics".to_string());
+        ep2.intent.domain = vec!["rust".to_string()];
+        let ep3 = Episode::new("web".to_string(), "fix pool timeout".to_string());
+
+        store.save(&ep1).unwrap();
+        store.save(&ep2).unwrap();
+        store.save(&ep3).unwrap();
+
+        let query = EpisodeQuery::parse("tag:rust pool").unwrap();
+        assert_eq!(store.list_filtered(10, &query).unwrap().len(), 2);
+
+        let query = EpisodeQuery::parse("tag:rust outcome:failure timeout").unwrap();
+        let found = store.list_filtered(10, &query).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, ep1.id);
+
+        let query = EpisodeQuery::parse("timeout")
+            .unwrap()
+            .with_project(Some("web"));
+        let found = store.list_filtered(10, &query).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, ep3.id);
+    }
+
     #[test]
     fn test_list_by_session() {
         let (store, _temp) = create_test_store();
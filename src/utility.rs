@@ -4,12 +4,13 @@
 #![allow(dead_code)]
 
 use anyhow::Result;
-use chrono::{Duration, Utc};
-use std::collections::HashMap;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{HashMap, HashSet};
 
-use crate::config::Config;
-use crate::episode::Episode;
+use crate::config::{Config, RelationWeights};
+use crate::episode::{Episode, EpisodeRelation};
 use crate::indexer::EpisodeIndexer;
+use crate::ledger::{LedgerEntry, Mechanism, UtilityLedger};
 use crate::store::EpisodeStore;
 
 /// Utility learning parameters
@@ -25,6 +26,14 @@ pub struct UtilityParams {
     pub propagation_threshold: f32,
     /// Maximum propagation depth (hops)
     pub max_propagation_depth: u32,
+    /// Discount factor for penalty propagation (0.0 - 1.0)
+    pub penalty_discount: f64,
+    /// Unhelpful share of rated retrievals that seeds penalty propagation
+    pub penalty_threshold: f32,
+    /// Weight of the measured reward from test results
+    pub test_reward_weight: f32,
+    /// Propagation weight per link type (session and explicit relations)
+    pub relation_weights: RelationWeights,
 }
 
 impl Default for UtilityParams {
@@ -35,6 +44,10 @@ impl Default for UtilityParams {
             learning_rate: 0.1,         // Conservative updates
             propagation_threshold: 0.5, // 50% similarity minimum
             max_propagation_depth: 2,   // 2-hop propagation
+            penalty_discount: 0.5,      // Failure spreads less than value
+            penalty_threshold: 0.7,     // Mostly unhelpful
+            test_reward_weight: 0.8,    // Red to green counts almost as a success
+            relation_weights: RelationWeights::default(),
         }
     }
 }
@@ -48,6 +61,10 @@ impl UtilityParams {
             learning_rate: config.bellman.alpha as f64,
             propagation_threshold: config.bellman.propagation_threshold,
             max_propagation_depth: config.bellman.max_propagation_depth,
+            penalty_discount: config.bellman.penalty_gamma as f64,
+            penalty_threshold: config.bellman.penalty_threshold,
+            test_reward_weight: config.bellman.test_reward_weight,
+            relation_weights: config.bellman.relation_weights.clone(),
         }
     }
 }
@@ -60,15 +77,194 @@ pub struct PropagationResult {
     pub total_utility_change: f64,
     pub decayed_episodes: usize,
     pub propagated_episodes: usize,
+    /// Episodes penalized by propagation from unhelpful neighbours
+    pub penalized_episodes: usize,
     /// Number of propagation hops executed
     pub hops_executed: u32,
     /// Whether propagation converged early (no updates in last hop)
     pub converged: bool,
 }
 
+/// Episodes touched by a propagation run. Reads see the run's own earlier
+/// updates; in dry-run mode updates stay in memory and nothing is written
+/// to the store or the utility ledger.
+pub struct PropagationRun<'a> {
+    store: &'a EpisodeStore,
+    dry_run: bool,
+    ledger: UtilityLedger,
+    /// Updated episodes, keyed by short ID (as `EpisodeStore::load` matches)
+    updated: HashMap<String, Episode>,
+    entries: Vec<LedgerEntry>,
+}
+
+/// Before/after utility of one episode changed by a propagation run
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ScoreDiff {
+    pub id: String,
+    pub intent: String,
+    pub before: f32,
+    pub after: f32,
+    pub mechanisms: Vec<Mechanism>,
+}
+
+impl<'a> PropagationRun<'a> {
+    pub fn new(store: &'a EpisodeStore, dry_run: bool) -> Result<Self> {
+        Ok(Self::with_ledger(store, dry_run, UtilityLedger::new()?))
+    }
+
+    pub fn with_ledger(store: &'a EpisodeStore, dry_run: bool, ledger: UtilityLedger) -> Self {
+        Self {
+            store,
+            dry_run,
+            ledger,
+            updated: HashMap::new(),
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    fn key(id: &str) -> &str {
+        &id[..8.min(id.len())]
+    }
+
+    pub fn load(&self, id: &str) -> Result<Episode> {
+        match self.updated.get(Self::key(id)) {
+            Some(ep) => Ok(ep.clone()),
+            None => self.store.load(id),
+        }
+    }
+
+    pub fn list_all(&self) -> Result<Vec<Episode>> {
+        Ok(self.overlay(self.store.list_all()?))
+    }
+
+    pub fn list_by_session(&self, session_id: &str) -> Result<Vec<Episode>> {
+        Ok(self.overlay(self.store.list_by_session(session_id)?))
+    }
+
+    fn overlay(&self, episodes: Vec<Episode>) -> Vec<Episode> {
+        episodes
+            .into_iter()
+            .map(|ep| self.updated.get(Self::key(&ep.id)).cloned().unwrap_or(ep))
+            .collect()
+    }
+
+    /// Apply a utility change: saves the episode (unless dry-run) and records
+    /// the ledger entry. Only the learned score is written, onto a fresh copy
+    /// from the store, so retrievals and feedback recorded by another process
+    /// since the run loaded the episode aren't overwritten.
+    pub fn update(&mut self, episode: &Episode, entry: LedgerEntry) -> Result<()> {
+        let mut episode = episode.clone();
+        if !self.dry_run {
+            let mut fresh = self.store.load(&episode.id)?;
+            fresh.utility.score = episode.utility.score;
+            fresh.utility.base_score = episode.utility.base_score;
+            fresh.utility.base_updated_at = episode.utility.base_updated_at;
+            self.store.update(&fresh)?;
+            self.ledger.append(&entry)?;
+            episode = fresh;
+        }
+        self.updated
+            .insert(Self::key(&episode.id).to_string(), episode);
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Source → target pairs `mechanism` has already changed, in the ledger
+    /// or earlier in this run
+    pub fn credited_pairs(&self, mechanism: Mechanism) -> Result<HashSet<(String, String)>> {
+        let logged = self.ledger.entries()?;
+        Ok(logged
+            .iter()
+            .chain(&self.entries)
+            .filter(|e| e.mechanism == mechanism)
+            .filter_map(|e| Some((e.source_episode.clone()?, e.episode_id.clone())))
+            .collect())
+    }
+
+    /// Every change made by this run, in order
+    pub fn entries(&self) -> &[LedgerEntry] {
+        &self.entries
+    }
+
+    /// Per-episode before/after scores, largest change first
+    pub fn diff(&self) -> Vec<ScoreDiff> {
+        let mut diffs: Vec<ScoreDiff> = Vec::new();
+        for entry in &self.entries {
+            match diffs.iter_mut().find(|d| d.id == entry.episode_id) {
+                Some(diff) => {
+                    diff.after = entry.new_value;
+                    if !diff.mechanisms.contains(&entry.mechanism) {
+                        diff.mechanisms.push(entry.mechanism);
+                    }
+                }
+                None => diffs.push(ScoreDiff {
+                    id: entry.episode_id.clone(),
+                    intent: self
+                        .updated
+                        .get(Self::key(&entry.episode_id))
+                        .map(|ep| ep.intent.raw_prompt.chars().take(50).collect())
+                        .unwrap_or_default(),
+                    before: entry.old_value,
+                    after: entry.new_value,
+                    mechanisms: vec![entry.mechanism],
+                }),
+            }
+        }
+        diffs.sort_by(|a, b| {
+            (b.after - b.before)
+                .abs()
+                .partial_cmp(&(a.after - a.before).abs())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        diffs
+    }
+
+    /// Machine-readable dry-run report: summary, per-mechanism totals and
+    /// per-episode changes
+    pub fn report(&self, result: &PropagationResult, credited: Option<usize>) -> serde_json::Value {
+        let diff = self.diff();
+        let mechanisms: serde_json::Map<String, serde_json::Value> =
+            crate::ledger::summarize(&self.entries)
+                .iter()
+                .map(|(m, s)| {
+                    (
+                        m.to_string(),
+                        serde_json::json!({
+                            "changes": s.changes,
+                            "episodes": s.episodes,
+                            "total_delta": s.total_delta,
+                        }),
+                    )
+                })
+                .collect();
+        serde_json::json!({
+            "dry_run": self.dry_run,
+            "summary": {
+                "episodes_processed": result.episodes_processed,
+                "episodes_changed": diff.len(),
+                "decayed": result.decayed_episodes,
+                "propagated": result.propagated_episodes,
+                "penalized": result.penalized_episodes,
+                "temporal_credited": credited,
+                "hops": result.hops_executed,
+                "converged": result.converged,
+                "net_change": diff.iter().map(|d| (d.after - d.before) as f64).sum::<f64>(),
+            },
+            "by_mechanism": mechanisms,
+            "changes": diff,
+        })
+    }
+}
+
 /// Run the full utility learning pipeline
-pub async fn run_propagation(config: &Config) -> Result<PropagationResult> {
-    let store = EpisodeStore::new()?;
+pub async fn run_propagation(
+    run: &mut PropagationRun<'_>,
+    config: &Config,
+) -> Result<PropagationResult> {
     let params = UtilityParams::from_config(config);
 
     let mut result = PropagationResult {
@@ -77,61 +273,105 @@ pub async fn run_propagation(config: &Config) -> Result<PropagationResult> {
         total_utility_change: 0.0,
         decayed_episodes: 0,
         propagated_episodes: 0,
+        penalized_episodes: 0,
         hops_executed: 0,
         converged: false,
     };
 
     // Load all episodes
-    let episodes = store.list_all()?;
+    let episodes = run.list_all()?;
     result.episodes_processed = episodes.len();
 
     if episodes.is_empty() {
         return Ok(result);
     }
 
-    println!("  Processing {} episodes...", episodes.len());
+    eprintln!("  Processing {} episodes...", episodes.len());
 
     // Step 1: Apply time-based decay
-    println!("  📉 Applying utility decay...");
-    let decay_result = apply_utility_decay(&store, &episodes, &params)?;
+    eprintln!("  📉 Applying utility decay...");
+    let decay_result = apply_utility_decay(run, &episodes, &params)?;
     result.decayed_episodes = decay_result.0;
     result.total_utility_change += decay_result.1;
 
     // Step 2: Bellman propagation (if we have the vector index)
-    println!("  🔄 Running Bellman propagation...");
-    match run_bellman_propagation(&store, &params, None).await {
+    eprintln!("  🔄 Running Bellman propagation...");
+    match run_bellman_propagation(run, &params, None).await {
         Ok(bellman_result) => {
             result.propagated_episodes = bellman_result.propagated;
             result.total_utility_change += bellman_result.total_change;
             result.hops_executed = bellman_result.hops_executed;
             result.converged = bellman_result.converged;
+
+            eprintln!("  🔻 Running penalty propagation...");
+            match run_penalty_propagation(run, &params, None).await {
+                Ok(penalty_result) => {
+                    result.penalized_episodes = penalty_result.propagated;
+                    result.total_utility_change += penalty_result.total_change;
+                }
+                Err(e) => eprintln!("    ⚠️  Skipping penalty propagation: {}", e),
+            }
         }
         Err(e) => {
-            println!("    ⚠️  Skipping vector propagation: {}", e);
+            eprintln!("    ⚠️  Skipping vector propagation: {}", e);
             // Fall back to tag-based propagation
-            let (propagated, change) = run_tag_propagation(&store, &episodes, &params)?;
+            let episodes = run.list_all()?;
+            let (propagated, change) = run_tag_propagation(run, &episodes, &params)?;
             result.propagated_episodes = propagated;
             result.total_utility_change += change;
         }
     }
 
     // Step 3: Update stored utility scores
-    println!("  💾 Saving updated utilities...");
-    let updated = save_utility_updates(&store)?;
+    eprintln!("  💾 Saving updated utilities...");
+    let updated = save_utility_updates(run)?;
     result.episodes_updated = updated;
 
     // Step 4: Sync utility scores to vector index
-    println!("  🔍 Syncing to vector index...");
-    if let Err(e) = sync_utility_to_index().await {
-        println!("    ⚠️  Index sync skipped: {}", e);
+    if !run.is_dry_run() {
+        eprintln!("  🔍 Syncing to vector index...");
+        if let Err(e) = sync_utility_to_index().await {
+            eprintln!("    ⚠️  Index sync skipped: {}", e);
+        }
     }
 
     Ok(result)
 }
 
-/// Apply time-based utility decay to episodes
+/// Weighted test reward an episode needs to seed Bellman propagation on its
+/// own, without helpful feedback
+const TEST_REWARD_SEED_VALUE: f32 = 0.5;
+
+/// Measured reward from an episode's test results, weighted by
+/// `test_reward_weight` (0.0 without test results)
+fn test_reward_value(episode: &Episode, params: &UtilityParams) -> f32 {
+    episode
+        .outcome
+        .test_reward()
+        .map_or(0.0, |r| r * params.test_reward_weight)
+}
+
+/// Value a Bellman source passes on: its utility score, or its weighted test
+/// reward when that's higher
+fn source_value(episode: &Episode, params: &UtilityParams) -> f32 {
+    episode
+        .utility
+        .calculate_score()
+        .max(test_reward_value(episode, params))
+}
+
+/// Apply time-based decay on its own; returns the number of episodes decayed
+pub fn run_decay(run: &mut PropagationRun<'_>, config: &Config) -> Result<usize> {
+    let params = UtilityParams::from_config(config);
+    let episodes = run.list_all()?;
+    Ok(apply_utility_decay(run, &episodes, &params)?.0)
+}
+
+/// Apply time-based utility decay to episodes.
+/// Decay is derived from each episode's base score, so running this any
+/// number of times a day gives the same result as running it once.
 fn apply_utility_decay(
-    store: &EpisodeStore,
+    run: &mut PropagationRun<'_>,
     episodes: &[Episode],
     params: &UtilityParams,
 ) -> Result<(usize, f64)> {
@@ -140,31 +380,22 @@ fn apply_utility_decay(
     let mut total_change = 0.0;
 
     for episode in episodes {
-        // Calculate days since last activity (retrieval or creation)
-        let last_activity = episode
-            .retrieval_history
-            .last()
-            .map(|r| r.timestamp)
-            .unwrap_or(episode.timestamp_end);
-
-        let days_inactive = (now - last_activity).num_days().max(0) as f64;
-
-        // Apply exponential decay: utility *= (1 - decay_rate)^days
-        let decay_factor = (1.0 - params.decay_rate).powf(days_inactive);
+        let old_score = episode
+            .utility
+            .score
+            .unwrap_or_else(|| episode.utility.calculate_score());
+        let new_score = decayed_score(episode, now, params.decay_rate);
 
-        // Only apply decay if significant
-        if decay_factor < 0.99 {
+        // Only write back when the derived score actually moved
+        if (new_score - old_score).abs() > 0.001 {
             let mut ep = episode.clone();
-            let old_score = ep.utility.calculate_score();
-
-            // Decay is applied by reducing the effective helpful ratio
-            // We don't change counts, but store the decayed score
-            let new_score = old_score * decay_factor as f32;
             ep.utility.score = Some(new_score);
+            run.update(
+                &ep,
+                LedgerEntry::new(&ep.id, Mechanism::Decay, old_score, new_score),
+            )?;
 
             total_change += (new_score - old_score) as f64;
-
-            store.update(&ep)?;
             decayed += 1;
         }
     }
@@ -172,6 +403,26 @@ fn apply_utility_decay(
     Ok((decayed, total_change))
 }
 
+/// Utility after time decay: `base * (1 - decay_rate)^days`, counting days
+/// from the last retrieval or the last time the base score was set,
+/// whichever is later. Never compounds on a previously decayed score.
+pub fn decayed_score(episode: &Episode, now: DateTime<Utc>, decay_rate: f64) -> f32 {
+    let last_retrieval = episode
+        .retrieval_history
+        .last()
+        .map(|r| r.timestamp)
+        .unwrap_or(episode.timestamp_end);
+    let anchor = episode
+        .utility
+        .base_updated_at
+        .map_or(last_retrieval, |at| at.max(last_retrieval));
+
+    let days_inactive = ((now - anchor).num_seconds() as f64 / 86_400.0).max(0.0);
+    let decay_factor = (1.0 - decay_rate).powf(days_inactive);
+
+    (episode.utility.base() as f64 * decay_factor) as f32
+}
+
 /// Result of a multi-hop Bellman propagation run
 pub struct BellmanResult {
     pub propagated: usize,
@@ -184,7 +435,7 @@ pub struct BellmanResult {
 /// At each hop, episodes updated in the previous hop become new sources,
 /// allowing value to flow through the similarity graph.
 pub async fn run_bellman_propagation(
-    store: &EpisodeStore,
+    run: &mut PropagationRun<'_>,
     params: &UtilityParams,
     project_filter: Option<&str>,
 ) -> Result<BellmanResult> {
@@ -200,7 +451,7 @@ pub async fn run_bellman_propagation(
     let mut converged = false;
 
     // Hop 0: seed sources are episodes with high helpfulness
-    let all_episodes = store.list_all()?;
+    let all_episodes = run.list_all()?;
     let episodes: Vec<_> = if let Some(proj) = project_filter {
         all_episodes
             .into_iter()
@@ -218,7 +469,9 @@ pub async fn run_bellman_propagation(
             } else {
                 0.0
             };
-            ratio > 0.5 && ep.utility.retrieval_count >= 2
+            let helpful = ratio > 0.5 && ep.utility.retrieval_count >= 2;
+            // A measured win (e.g. tests red to green) seeds value too
+            helpful || test_reward_value(ep, params) >= TEST_REWARD_SEED_VALUE
         })
         .map(|ep| ep.id.clone())
         .collect();
@@ -232,7 +485,7 @@ pub async fn run_bellman_propagation(
         });
     }
 
-    println!(
+    eprintln!(
         "    Found {} high-utility episodes to propagate from",
         source_ids.len()
     );
@@ -246,7 +499,7 @@ pub async fn run_bellman_propagation(
         // Load source episodes for this hop
         let sources: Vec<Episode> = source_ids
             .iter()
-            .filter_map(|id| store.load(id).ok())
+            .filter_map(|id| run.load(id).ok())
             .collect();
 
         for source in &sources {
@@ -267,9 +520,9 @@ pub async fn run_bellman_propagation(
                     continue;
                 }
 
-                if let Ok(mut target) = store.load(&result.id) {
+                if let Ok(mut target) = run.load(&result.id) {
                     let old_score = target.utility.score.unwrap_or(0.5);
-                    let source_score = source.utility.calculate_score();
+                    let source_score = source_value(source, params);
 
                     // Bellman update with depth-discounted gamma
                     let td_error =
@@ -279,8 +532,13 @@ pub async fn run_bellman_propagation(
                     let new_score = new_score.clamp(0.0, 1.0);
 
                     if (new_score - old_score).abs() > 0.01 {
-                        target.utility.score = Some(new_score);
-                        store.update(&target)?;
+                        target.utility.set_score(new_score, Utc::now());
+                        run.update(
+                            &target,
+                            LedgerEntry::new(&target.id, Mechanism::Bellman, old_score, new_score)
+                                .with_source(&source.id)
+                                .at_hop(depth + 1),
+                        )?;
                         hop_change += (new_score - old_score) as f64;
                         updated_this_hop.insert(target.id);
                     }
@@ -297,7 +555,7 @@ pub async fn run_bellman_propagation(
             break;
         }
 
-        println!(
+        eprintln!(
             "    Hop {}: updated {} episodes (Δ{:+.3})",
             depth + 1,
             updated_this_hop.len(),
@@ -324,23 +582,26 @@ pub async fn run_bellman_propagation(
 
     for source in &helpful_episodes {
         if let Some(session_id) = &source.session_id {
-            let session_episodes = store.list_by_session(session_id)?;
+            let session_episodes = run.list_by_session(session_id)?;
             for mut target in session_episodes {
                 if target.id == source.id {
                     continue;
                 }
                 let old_score = target.utility.score.unwrap_or(0.5);
-                let source_score = source.utility.calculate_score();
-
-                let session_similarity = 0.9_f64;
-                let td_error = params.discount_factor * source_score as f64 * session_similarity
-                    - old_score as f64;
-                let new_score = old_score + (params.learning_rate * td_error) as f32;
-                let new_score = new_score.clamp(0.0, 1.0);
+                let new_score = linked_score(
+                    old_score,
+                    source.utility.calculate_score(),
+                    params.relation_weights.session,
+                    params,
+                );
 
                 if (new_score - old_score).abs() > 0.01 {
-                    target.utility.score = Some(new_score);
-                    store.update(&target)?;
+                    target.utility.set_score(new_score, Utc::now());
+                    run.update(
+                        &target,
+                        LedgerEntry::new(&target.id, Mechanism::Session, old_score, new_score)
+                            .with_source(&source.id),
+                    )?;
                     total_change += (new_score - old_score) as f64;
                     total_propagated += 1;
                 }
@@ -348,6 +609,33 @@ pub async fn run_bellman_propagation(
         }
     }
 
+    // Explicit-link propagation, weighted by relation type (runs once)
+    for source in &helpful_episodes {
+        for (target_id, relation) in linked_episodes(source, &episodes) {
+            let Ok(mut target) = run.load(&target_id) else {
+                continue;
+            };
+            let old_score = target.utility.score.unwrap_or(0.5);
+            let new_score = linked_score(
+                old_score,
+                source.utility.calculate_score(),
+                params.relation_weights.weight(relation),
+                params,
+            );
+
+            if (new_score - old_score).abs() > 0.01 {
+                target.utility.set_score(new_score, Utc::now());
+                run.update(
+                    &target,
+                    LedgerEntry::new(&target.id, Mechanism::Relation, old_score, new_score)
+                        .with_source(&source.id),
+                )?;
+                total_change += (new_score - old_score) as f64;
+                total_propagated += 1;
+            }
+        }
+    }
+
     Ok(BellmanResult {
         propagated: total_propagated,
         total_change,
@@ -356,9 +644,228 @@ pub async fn run_bellman_propagation(
     })
 }
 
+/// Episodes explicitly linked to `source`, either way: its own links first,
+/// then links to it from `episodes`. Relations are as seen from the source
+/// (see `directed_relation`).
+fn linked_episodes(source: &Episode, episodes: &[Episode]) -> Vec<(String, EpisodeRelation)> {
+    let mut linked: Vec<(String, EpisodeRelation)> = source
+        .related_episodes
+        .iter()
+        .map(|r| (r.id.clone(), directed_relation(r.relationship, true)))
+        .collect();
+    for ep in episodes {
+        if let Some(relation) = ep.relation_to(&source.id) {
+            if !linked.iter().any(|(id, _)| *id == ep.id) {
+                linked.push((ep.id.clone(), directed_relation(relation, false)));
+            }
+        }
+    }
+    linked
+}
+
+/// Relation between `source` and `target`, if linked either way, as seen
+/// from the source (see `directed_relation`)
+fn relation_between(source: &Episode, target: &Episode) -> Option<EpisodeRelation> {
+    source
+        .relation_to(&target.id)
+        .map(|r| directed_relation(r, true))
+        .or_else(|| {
+            target
+                .relation_to(&source.id)
+                .map(|r| directed_relation(r, false))
+        })
+}
+
+/// Relation whose weight applies when value flows from a source to a linked
+/// episode. Directed links only carry their own weight one way: to the
+/// source's prerequisite (a link into the source) and to its continuation (a
+/// link out of it). The dependent and the predecessor count as related.
+fn directed_relation(relation: EpisodeRelation, outgoing: bool) -> EpisodeRelation {
+    match (relation, outgoing) {
+        (EpisodeRelation::Prerequisite, true) | (EpisodeRelation::Continuation, false) => {
+            EpisodeRelation::Related
+        }
+        (relation, _) => relation,
+    }
+}
+
+/// Score of an episode linked to a helpful source. A positive weight pulls
+/// it toward `gamma * source * weight` (the Bellman target); a negative
+/// (competitive) weight pulls it down toward `0.5 - gamma * source * |weight|`
+/// and never further, so repeated runs settle instead of draining it to 0.
+fn linked_score(old_score: f32, source_score: f32, weight: f32, params: &UtilityParams) -> f32 {
+    let td_error = if weight >= 0.0 {
+        params.discount_factor * source_score as f64 * weight as f64 - old_score as f64
+    } else {
+        let target = 0.5 - params.discount_factor * source_score as f64 * weight.abs() as f64;
+        (target - old_score as f64).min(0.0)
+    };
+    (old_score + (params.learning_rate * td_error) as f32).clamp(0.0, 1.0)
+}
+
+/// Whether an episode's feedback is negative enough to seed penalty
+/// propagation: at least two unhelpful ratings, making up more than
+/// `threshold` of its rated retrievals
+fn is_penalty_source(episode: &Episode, threshold: f32) -> bool {
+    let rated = episode
+        .retrieval_history
+        .iter()
+        .filter(|r| r.was_helpful.is_some() || r.grade.is_some())
+        .count();
+    let unhelpful = episode
+        .retrieval_history
+        .iter()
+        .filter(|r| r.was_helpful == Some(false))
+        .count();
+    unhelpful >= 2 && unhelpful as f32 / rated as f32 > threshold
+}
+
+/// Pull a score down toward a failing source's score, by `weight`
+/// (discount × similarity). Never raises a score.
+fn penalized_score(old_score: f32, source_score: f32, weight: f64, learning_rate: f64) -> f32 {
+    if source_score >= old_score {
+        return old_score;
+    }
+    let td_error = weight * (source_score - old_score) as f64;
+    (old_score + (learning_rate * td_error) as f32).clamp(0.0, 1.0)
+}
+
+/// Multi-hop penalty propagation: the mirror of `run_bellman_propagation`,
+/// seeded from consistently unhelpful episodes, so neighbours of a failing
+/// cluster lose utility instead of keeping the default. Uses its own
+/// discount (`penalty_gamma`) and the same similarity threshold and depth.
+pub async fn run_penalty_propagation(
+    run: &mut PropagationRun<'_>,
+    params: &UtilityParams,
+    project_filter: Option<&str>,
+) -> Result<BellmanResult> {
+    let indexer = EpisodeIndexer::new().await?;
+
+    if !indexer.is_indexed().await {
+        anyhow::bail!("Vector index not available");
+    }
+
+    let mut total_penalized = 0;
+    let mut total_change = 0.0;
+    let mut hops_executed = 0;
+    let mut converged = false;
+
+    let mut source_ids: std::collections::HashSet<String> = run
+        .list_all()?
+        .into_iter()
+        .filter(|ep| {
+            project_filter
+                .is_none_or(|proj| ep.project.to_lowercase().contains(&proj.to_lowercase()))
+        })
+        .filter(|ep| is_penalty_source(ep, params.penalty_threshold))
+        .map(|ep| ep.id)
+        .collect();
+
+    if source_ids.is_empty() {
+        return Ok(BellmanResult {
+            propagated: 0,
+            total_change: 0.0,
+            hops_executed: 0,
+            converged: true,
+        });
+    }
+
+    eprintln!(
+        "    Found {} unhelpful episodes to propagate penalties from",
+        source_ids.len()
+    );
+
+    // Seeds and everything penalized so far are never penalized again in
+    // this run, so a cluster can't drag itself down hop after hop
+    let mut visited = source_ids.clone();
+
+    for depth in 0..params.max_propagation_depth {
+        let depth_discount = params.penalty_discount.powi(depth as i32 + 1);
+        let mut updated_this_hop = std::collections::HashSet::new();
+        let mut hop_change = 0.0;
+
+        let sources: Vec<Episode> = source_ids
+            .iter()
+            .filter_map(|id| run.load(id).ok())
+            .collect();
+
+        for source in &sources {
+            let query = format!(
+                "{} {} {}",
+                source.intent.raw_prompt,
+                source.intent.domain.join(" "),
+                source.intent.task_type
+            );
+
+            let similar = indexer.search(&query, 10, project_filter).await?;
+
+            for result in similar {
+                if visited.contains(&result.id) {
+                    continue;
+                }
+                if result.similarity_score < params.propagation_threshold {
+                    continue;
+                }
+
+                if let Ok(mut target) = run.load(&result.id) {
+                    let old_score = target.utility.score.unwrap_or(0.5);
+                    // Seeds carry their feedback evidence, later hops the
+                    // score they were just penalized to
+                    let wilson = source.utility.calculate_score();
+                    let source_score = source.utility.score.map_or(wilson, |s| s.min(wilson));
+                    let new_score = penalized_score(
+                        old_score,
+                        source_score,
+                        depth_discount * result.similarity_score as f64,
+                        params.learning_rate,
+                    );
+
+                    if (new_score - old_score).abs() > 0.01 {
+                        target.utility.set_score(new_score, Utc::now());
+                        run.update(
+                            &target,
+                            LedgerEntry::new(&target.id, Mechanism::Penalty, old_score, new_score)
+                                .with_source(&source.id)
+                                .at_hop(depth + 1),
+                        )?;
+                        hop_change += (new_score - old_score) as f64;
+                        updated_this_hop.insert(target.id);
+                    }
+                }
+            }
+        }
+
+        total_penalized += updated_this_hop.len();
+        total_change += hop_change;
+        hops_executed = depth + 1;
+
+        if updated_this_hop.is_empty() {
+            converged = true;
+            break;
+        }
+
+        eprintln!(
+            "    Hop {}: penalized {} episodes (Δ{:+.3})",
+            depth + 1,
+            updated_this_hop.len(),
+            hop_change
+        );
+
+        visited.extend(updated_this_hop.iter().cloned());
+        source_ids = updated_this_hop;
+    }
+
+    Ok(BellmanResult {
+        propagated: total_penalized,
+        total_change,
+        hops_executed,
+        converged,
+    })
+}
+
 /// Fallback tag-based propagation when vector index is unavailable
 fn run_tag_propagation(
-    store: &EpisodeStore,
+    run: &mut PropagationRun<'_>,
     episodes: &[Episode],
     params: &UtilityParams,
 ) -> Result<(usize, f64)> {
@@ -403,8 +910,16 @@ fn run_tag_propagation(
                 let new_score = new_score.clamp(0.0, 1.0);
 
                 if (new_score - current).abs() > 0.01 {
-                    updated.utility.score = Some(new_score);
-                    store.update(&updated)?;
+                    updated.utility.set_score(new_score, Utc::now());
+                    run.update(
+                        &updated,
+                        LedgerEntry::new(
+                            &updated.id,
+                            Mechanism::TagPropagation,
+                            current,
+                            new_score,
+                        ),
+                    )?;
 
                     total_change += (new_score - current) as f64;
                     propagated += 1;
@@ -417,9 +932,9 @@ fn run_tag_propagation(
 }
 
 /// Save any pending utility updates
-fn save_utility_updates(store: &EpisodeStore) -> Result<usize> {
+fn save_utility_updates(run: &PropagationRun<'_>) -> Result<usize> {
     // Updates are saved incrementally, so just return count
-    let episodes = store.list_all()?;
+    let episodes = run.list_all()?;
     Ok(episodes
         .iter()
         .filter(|ep| ep.utility.score.is_some())
@@ -545,14 +1060,15 @@ pub struct PruneCandidate {
 }
 
 /// Calculate temporal credit assignment for a sequence of episodes
-/// Episodes that led to successful outcomes get credit
+/// Episodes that led to successful outcomes get credit, once per success
+/// (pairs already in the ledger are skipped)
 pub fn temporal_credit_assignment(
-    store: &EpisodeStore,
+    run: &mut PropagationRun<'_>,
     project: Option<&str>,
     params: &UtilityParams,
     config: &Config,
 ) -> Result<usize> {
-    let mut episodes = store.list_all()?;
+    let mut episodes = run.list_all()?;
 
     // Filter by project if specified
     if let Some(proj) = project {
@@ -566,14 +1082,23 @@ pub fn temporal_credit_assignment(
         return Ok(0);
     }
 
+    // Each success credits an episode once, however often this runs
+    let credited = run.credited_pairs(Mechanism::TemporalCredit)?;
     let mut updated = 0;
 
     // Look for success patterns: sequences where a success followed other episodes
     for i in 1..episodes.len() {
         let current = &episodes[i];
 
-        // If this episode was successful, credit preceding related episodes
-        if current.outcome.status == crate::episode::OutcomeStatus::Success {
+        // A declared success and a measured test reward both credit the
+        // preceding related episodes
+        let success = if current.outcome.status == crate::episode::OutcomeStatus::Success {
+            1.0
+        } else {
+            0.0
+        };
+        let reward = success + test_reward_value(current, params) as f64;
+        if reward > 0.0 {
             // Look back at recent episodes (configurable window)
             let lookback = Duration::hours(config.bellman.temporal_credit_window_hours);
 
@@ -586,13 +1111,20 @@ pub fn temporal_credit_assignment(
                     .as_ref()
                     .is_some_and(|s| prev.session_id.as_ref() == Some(s));
 
-                // Stop if too old AND not session-linked
-                if !same_session && current.timestamp_start - prev.timestamp_end > lookback {
+                // Explicit links also bypass the window and scale the credit
+                let relation = relation_between(current, prev);
+
+                // Stop if too old AND neither session-linked nor linked
+                if !same_session
+                    && relation.is_none()
+                    && current.timestamp_start - prev.timestamp_end > lookback
+                {
                     break;
                 }
 
-                // Check if related (same session, same project, or similar tags)
-                let related = same_session
+                // Check if related (link, same session, same project, or similar tags)
+                let related = relation.is_some()
+                    || same_session
                     || prev.project == current.project
                     || prev
                         .intent
@@ -600,19 +1132,37 @@ pub fn temporal_credit_assignment(
                         .iter()
                         .any(|t| current.intent.domain.contains(t));
 
-                if related {
+                if related && !credited.contains(&(current.id.clone(), prev.id.clone())) {
                     let mut prev_updated = prev.clone();
                     let old_score = prev_updated.utility.score.unwrap_or(0.5);
 
-                    // Give credit based on temporal distance
-                    let time_factor = 1.0 - (i - j) as f64 * 0.2; // Decreases by 20% per step
-                    let credit = params.discount_factor * time_factor * 0.1; // Small credit boost
+                    // Give credit based on temporal distance, scaled by the
+                    // relation weight for explicitly linked episodes
+                    let time_factor = (1.0 - (i - j) as f64 * 0.2).max(0.0); // Decreases by 20% per step
+                    let weight = relation.map_or(1.0, |r| params.relation_weights.weight(r));
+                    let new_score = if weight < 0.0 {
+                        // Competitors move toward a floor rather than losing
+                        // a fixed amount on every run (see `linked_score`)
+                        let source = (time_factor * reward.min(1.0)) as f32;
+                        linked_score(old_score, source, weight, params)
+                    } else {
+                        let credit =
+                            params.discount_factor * time_factor * weight as f64 * reward * 0.1; // Small credit boost
+                        (old_score as f64 + credit).clamp(0.0, 1.0) as f32
+                    };
 
-                    let new_score = (old_score as f64 + credit).min(1.0) as f32;
-
-                    if new_score > old_score + 0.01 {
-                        prev_updated.utility.score = Some(new_score);
-                        store.update(&prev_updated)?;
+                    if (new_score - old_score).abs() > 0.01 {
+                        prev_updated.utility.set_score(new_score, Utc::now());
+                        run.update(
+                            &prev_updated,
+                            LedgerEntry::new(
+                                &prev_updated.id,
+                                Mechanism::TemporalCredit,
+                                old_score,
+                                new_score,
+                            )
+                            .with_source(&current.id),
+                        )?;
                         updated += 1;
                     }
                 }
@@ -665,4 +1215,324 @@ mod tests {
         let decay_factor_100 = (1.0 - params.decay_rate).powf(100.0);
         assert!(decay_factor_100 < decay_factor);
     }
+
+    #[test]
+    fn test_decay_is_idempotent() {
+        let now = Utc::now();
+        let mut ep = Episode::new("test".to_string(), "fix".to_string());
+        ep.timestamp_end = now - Duration::days(10);
+        ep.utility.base_score = Some(0.8);
+
+        let expected = 0.8 * 0.99_f32.powi(10);
+        let first = decayed_score(&ep, now, 0.01);
+        assert!((first - expected).abs() < 1e-4);
+
+        // Writing the decayed score back and decaying again doesn't compound
+        ep.utility.score = Some(first);
+        assert!((decayed_score(&ep, now, 0.01) - first).abs() < 1e-6);
+
+        // Daily runs add up to the configured rate, not more
+        let later = now + Duration::days(5);
+        let expected = 0.8 * 0.99_f32.powi(15);
+        assert!((decayed_score(&ep, later, 0.01) - expected).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_dry_run_keeps_store_untouched() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let store = EpisodeStore::with_dir(temp.path().to_path_buf());
+        let mut ep = Episode::new("test".to_string(), "fix".to_string());
+        ep.timestamp_end = Utc::now() - Duration::days(30);
+        ep.utility.base_score = Some(0.8);
+        store.save(&ep).unwrap();
+
+        let mut run = PropagationRun::new(&store, true).unwrap();
+        let episodes = run.list_all().unwrap();
+        let (decayed, _) =
+            apply_utility_decay(&mut run, &episodes, &UtilityParams::default()).unwrap();
+        assert_eq!(decayed, 1);
+
+        // The run sees its own update, the store doesn't
+        let in_run = run.load(&ep.id).unwrap().utility.score.unwrap();
+        assert!(in_run < 0.8);
+        assert_eq!(store.load(&ep.id).unwrap().utility.score, None);
+
+        let diff = run.diff();
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff[0].mechanisms, vec![Mechanism::Decay]);
+        assert_eq!(diff[0].after, in_run);
+    }
+
+    #[test]
+    fn test_update_keeps_concurrent_changes() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let store = EpisodeStore::with_dir(temp.path().join("episodes"));
+        let ep = Episode::new("test".to_string(), "fix".to_string());
+        store.save(&ep).unwrap();
+
+        let ledger = UtilityLedger::with_path(temp.path().join("ledger.jsonl"));
+        let mut run = PropagationRun::with_ledger(&store, false, ledger);
+        let mut loaded = run.list_all().unwrap().remove(0);
+
+        // Another process records a retrieval after the run loaded the episode
+        let mut foreground = store.load(&ep.id).unwrap();
+        foreground.utility.record_impression(1.0);
+        store.update(&foreground).unwrap();
+
+        loaded.utility.set_score(0.9, Utc::now());
+        run.update(
+            &loaded,
+            LedgerEntry::new(&ep.id, Mechanism::Bellman, 0.5, 0.9),
+        )
+        .unwrap();
+
+        let saved = store.load(&ep.id).unwrap();
+        assert_eq!(saved.utility.retrieval_count, 1);
+        assert_eq!(saved.utility.score, Some(0.9));
+        assert_eq!(run.load(&ep.id).unwrap().utility.retrieval_count, 1);
+    }
+
+    #[test]
+    fn test_dry_run_report_is_json() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let store = EpisodeStore::with_dir(temp.path().to_path_buf());
+        let mut ep = Episode::new("test".to_string(), "fix".to_string());
+        ep.timestamp_end = Utc::now() - Duration::days(30);
+        ep.utility.base_score = Some(0.8);
+        store.save(&ep).unwrap();
+
+        let mut run = PropagationRun::new(&store, true).unwrap();
+        let episodes = run.list_all().unwrap();
+        let (decayed, _) =
+            apply_utility_decay(&mut run, &episodes, &UtilityParams::default()).unwrap();
+        let result = PropagationResult {
+            episodes_processed: 1,
+            episodes_updated: decayed,
+            total_utility_change: 0.0,
+            decayed_episodes: decayed,
+            propagated_episodes: 0,
+            penalized_episodes: 0,
+            hops_executed: 0,
+            converged: true,
+        };
+
+        // What `propagate --dry-run --format json` prints parses back
+        let output = serde_json::to_string_pretty(&run.report(&result, None)).unwrap();
+        let report: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(report["dry_run"], true);
+        assert_eq!(report["summary"]["episodes_changed"], 1);
+        assert_eq!(report["changes"][0]["id"], ep.id.as_str());
+    }
+
+    #[test]
+    fn test_decay_restarts_when_score_set() {
+        let now = Utc::now();
+        let mut ep = Episode::new("test".to_string(), "fix".to_string());
+        ep.timestamp_end = now - Duration::days(30);
+
+        ep.utility.set_score(0.7, now - Duration::days(2));
+        let expected = 0.7 * 0.99_f32.powi(2);
+        assert!((decayed_score(&ep, now, 0.01) - expected).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_linked_score_by_relation() {
+        let params = UtilityParams::default();
+        let weights = &params.relation_weights;
+        let score = |relation| linked_score(0.5, 0.9, weights.weight(relation), &params);
+
+        // Prerequisites of a helpful episode gain the most, alternatives compete
+        assert!(score(EpisodeRelation::Prerequisite) > score(EpisodeRelation::Related));
+        assert!(score(EpisodeRelation::Related) > score(EpisodeRelation::Alternative));
+        assert!(score(EpisodeRelation::Alternative) < 0.5);
+    }
+
+    #[test]
+    fn test_competitive_link_settles() {
+        let params = UtilityParams::default();
+        let weight = params.relation_weights.alternative;
+        assert!(weight < 0.0);
+
+        // Repeated runs converge instead of draining the alternative to 0
+        let mut score = 0.5;
+        for _ in 0..200 {
+            score = linked_score(score, 0.9, weight, &params);
+        }
+        assert!(score > 0.0);
+        assert!((linked_score(score, 0.9, weight, &params) - score).abs() < 1e-4);
+
+        // An alternative already below the target is left alone
+        assert_eq!(linked_score(0.1, 0.9, weight, &params), 0.1);
+    }
+
+    #[test]
+    fn test_linked_episodes_both_ways() {
+        let mut source = Episode::new("test".to_string(), "deploy".to_string());
+        let mut prereq = Episode::new("test".to_string(), "set up ci".to_string());
+        let other = Episode::new("test".to_string(), "rollback".to_string());
+        let dependent = Episode::new("test".to_string(), "canary rollout".to_string());
+        let mut previous = Episode::new("test".to_string(), "build image".to_string());
+        source.add_relation(&other.id, EpisodeRelation::Alternative);
+        source.add_relation(&dependent.id, EpisodeRelation::Prerequisite);
+        prereq.add_relation(&source.id, EpisodeRelation::Prerequisite);
+        previous.add_relation(&source.id, EpisodeRelation::Continuation);
+
+        let episodes = vec![
+            source.clone(),
+            prereq.clone(),
+            other.clone(),
+            dependent.clone(),
+            previous.clone(),
+        ];
+        let linked = linked_episodes(&source, &episodes);
+        // Only the source's own prerequisite gets the prerequisite weight;
+        // the episode depending on it and the one it continues are related
+        assert_eq!(
+            linked,
+            vec![
+                (other.id.clone(), EpisodeRelation::Alternative),
+                (dependent.id.clone(), EpisodeRelation::Related),
+                (prereq.id.clone(), EpisodeRelation::Prerequisite),
+                (previous.id.clone(), EpisodeRelation::Related),
+            ]
+        );
+        assert_eq!(
+            relation_between(&source, &dependent),
+            Some(EpisodeRelation::Related)
+        );
+        assert_eq!(
+            relation_between(&source, &prereq),
+            Some(EpisodeRelation::Prerequisite)
+        );
+    }
+
+    #[test]
+    fn test_temporal_credit_uses_relation_weights() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let store = EpisodeStore::with_dir(temp.path().to_path_buf());
+        let now = Utc::now();
+
+        let mut prereq = Episode::new("test".to_string(), "set up ci".to_string());
+        prereq.timestamp_start = now - Duration::minutes(30);
+        prereq.timestamp_end = prereq.timestamp_start;
+        let mut alternative = Episode::new("test".to_string(), "manual deploy".to_string());
+        alternative.timestamp_start = now - Duration::minutes(20);
+        alternative.timestamp_end = alternative.timestamp_start;
+        let mut success = Episode::new("test".to_string(), "deploy".to_string());
+        success.outcome.status = crate::episode::OutcomeStatus::Success;
+        prereq.add_relation(&success.id, EpisodeRelation::Prerequisite);
+        success.add_relation(&alternative.id, EpisodeRelation::Alternative);
+        for ep in [&prereq, &alternative, &success] {
+            store.save(ep).unwrap();
+        }
+
+        let ledger = UtilityLedger::with_path(temp.path().join("ledger.jsonl"));
+        let mut run = PropagationRun::with_ledger(&store, true, ledger);
+        let params = UtilityParams::default();
+        temporal_credit_assignment(&mut run, None, &params, &Config::default()).unwrap();
+
+        let score = |id: &str| run.load(id).unwrap().utility.score.unwrap();
+        assert!(score(&prereq.id) > 0.5);
+        assert!(score(&alternative.id) < 0.5);
+
+        // Repeated runs don't credit the same pair again
+        let (prereq_score, alternative_score) = (score(&prereq.id), score(&alternative.id));
+        for _ in 0..5 {
+            temporal_credit_assignment(&mut run, None, &params, &Config::default()).unwrap();
+        }
+        let score = |id: &str| run.load(id).unwrap().utility.score.unwrap();
+        assert_eq!(score(&prereq.id), prereq_score);
+        assert_eq!(score(&alternative.id), alternative_score);
+
+        // A competitor only moves toward its floor
+        let floor =
+            0.5 - params.discount_factor as f32 * 0.8 * params.relation_weights.alternative.abs();
+        assert!(alternative_score >= floor - 1e-4);
+    }
+
+    #[test]
+    fn test_temporal_credit_from_test_reward() {
+        use crate::episode::TestResults;
+
+        let temp = tempfile::TempDir::new().unwrap();
+        let store = EpisodeStore::with_dir(temp.path().to_path_buf());
+        let now = Utc::now();
+
+        let mut investigate =
+            Episode::new("test".to_string(), "investigate flaky test".to_string());
+        investigate.timestamp_start = now - Duration::minutes(30);
+        investigate.timestamp_end = investigate.timestamp_start;
+        // Not declared a success, but the tests went from red to green
+        let mut fix = Episode::new("test".to_string(), "fix race in setup".to_string());
+        fix.outcome.tests_before = Some(TestResults {
+            passed: 9,
+            failed: 1,
+            skipped: 0,
+        });
+        fix.outcome.tests_after = Some(TestResults {
+            passed: 10,
+            failed: 0,
+            skipped: 0,
+        });
+        for ep in [&investigate, &fix] {
+            store.save(ep).unwrap();
+        }
+
+        let mut params = UtilityParams::default();
+        let mut run = PropagationRun::new(&store, true).unwrap();
+        assert_eq!(source_value(&fix, &params), 0.8);
+        temporal_credit_assignment(&mut run, None, &params, &Config::default()).unwrap();
+        assert!(run.load(&investigate.id).unwrap().utility.score.unwrap() > 0.5);
+
+        // Off when the weight is zero
+        params.test_reward_weight = 0.0;
+        let mut run = PropagationRun::new(&store, true).unwrap();
+        assert_eq!(
+            temporal_credit_assignment(&mut run, None, &params, &Config::default()).unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_penalty_sources() {
+        use crate::episode::RetrievalRecord;
+
+        let record = |was_helpful: Option<bool>| RetrievalRecord {
+            timestamp: Utc::now(),
+            project: "test".to_string(),
+            task_description: "fix".to_string(),
+            was_helpful,
+            retrieval_id: None,
+            session_id: None,
+            rank: None,
+            grade: None,
+            aspects: vec![],
+            implicit: None,
+        };
+        let mut ep = Episode::new("test".to_string(), "fix".to_string());
+
+        // One unhelpful rating isn't a pattern
+        ep.retrieval_history = vec![record(Some(false)), record(None)];
+        assert!(!is_penalty_source(&ep, 0.7));
+
+        // Unrated impressions don't dilute the ratio
+        ep.retrieval_history.push(record(Some(false)));
+        ep.retrieval_history.push(record(None));
+        assert!(is_penalty_source(&ep, 0.7));
+
+        ep.retrieval_history.push(record(Some(true)));
+        assert!(!is_penalty_source(&ep, 0.7));
+    }
+
+    #[test]
+    fn test_penalized_score() {
+        // Pulls toward the failing source, more for closer neighbours
+        let near = penalized_score(0.5, 0.1, 0.5 * 0.9, 0.1);
+        let far = penalized_score(0.5, 0.1, 0.5 * 0.6, 0.1);
+        assert!(near < far && far < 0.5);
+        assert!((near - (0.5 - 0.1 * 0.45 * 0.4)).abs() < 1e-6);
+
+        // Never raises a score
+        assert_eq!(penalized_score(0.05, 0.1, 0.45, 0.1), 0.05);
+    }
 }